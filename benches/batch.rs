@@ -0,0 +1,44 @@
+//! Compare balancing a large batch serially against balancing it with rayon.
+//!
+//! Run with `cargo bench --bench batch`.
+
+use std::{hint::black_box, time::Instant};
+
+use balance_tui::batch;
+
+const EQUATIONS: &[&str] = &[
+    "Fe + O2 -> Fe2O3",
+    "H2 + O2 -> H2O",
+    "C3H8 + O2 -> CO2 + H2O",
+    "C6H12O6 + O2 -> CO2 + H2O",
+    "KMnO4 + HCl -> KCl + MnCl2 + H2O + Cl2",
+    "Al + H2SO4 -> Al2(SO4)3 + H2",
+    "Ca3(PO4)2 + SiO2 + C -> CaSiO3 + P4 + CO",
+    "Cu + HNO3 -> Cu(NO3)2 + NO + H2O",
+];
+const BATCH_SIZE: usize = 5000;
+
+fn main() {
+    let lines = EQUATIONS
+        .iter()
+        .copied()
+        .cycle()
+        .take(BATCH_SIZE)
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    black_box(batch::balance_all_serial(black_box(&lines), None));
+    let serial = start.elapsed();
+
+    let start = Instant::now();
+    black_box(batch::balance_all(black_box(&lines), None));
+    let parallel = start.elapsed();
+
+    println!("balanced {BATCH_SIZE} equations");
+    println!("serial:   {serial:?}");
+    println!("parallel: {parallel:?}");
+    println!(
+        "speedup:  {:.2}x",
+        serial.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
@@ -0,0 +1,3339 @@
+use std::{
+    cell::Cell,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chem_eq::Equation;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Position, Rect};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    balance::{self, balance_reduced},
+    batch::{self, BatchResult},
+    chem, classify, elements,
+    error::Error,
+    examples,
+    format::{copy_text, format_results, fractional_text, plain_equation, CopyFormat, Format},
+    history::History,
+    normalize::normalize,
+    quiz::{Quiz, Verdict},
+    redox::{self, Medium, Redox},
+    report::{self, Entry},
+    session::{SavedTab, Session, Toggles, VERSION},
+    stoich::{self, Conversion, Known, PercentYield, StoichError, Unit, Yield},
+    suggest::{self, suggest},
+    table,
+    theme::Theme,
+    title,
+    undo::{Edit, Snapshot, Undo},
+    worker::Worker,
+};
+
+/// Longest input allowed by default, far more than any real equation
+pub const DEFAULT_MAX_INPUT: usize = 2000;
+
+/// How long the second `q` to quit can take
+const QUIT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long to wait for the worker before showing that it's balancing, so
+/// quick equations don't flash the spinner
+const QUICK_BALANCE: Duration = Duration::from_millis(20);
+
+#[derive(Debug)]
+pub struct App {
+    pub input_mode: InputMode,
+    /// The pane keys go to in normal mode
+    pub focus: Focus,
+    pub input: String,
+    /// Position of the cursor in the input, counted in characters
+    pub cursor: usize,
+    pub output: Option<Result<Equation, Error>>,
+    /// What the balanced coefficients were divided by to make them as small as
+    /// possible, 1 if they already were
+    pub common_factor: usize,
+    /// How long parsing and balancing the output took
+    pub balance_time: Option<Duration>,
+    /// Show [`App::balance_time`] in the status line
+    pub show_timing: bool,
+    /// Lines the output is scrolled down by
+    pub output_scroll: u16,
+    /// Size of the output when it was last drawn, to know how far it scrolls
+    pub output_view: Cell<OutputView>,
+    /// Where the panes were last drawn, to know what was clicked
+    pub panes: Cell<Panes>,
+    pub status: Option<Status>,
+    /// The input changed since the equation was last balanced
+    pub edited: bool,
+    /// Draw the help panel under the output
+    pub show_help: bool,
+    pub history: History,
+    /// Equations starred to come back to, oldest first. Unlike the history
+    /// they're only ever removed by hand
+    pub favorites: Vec<String>,
+    /// Which favorite is chosen in the list of them
+    pub favorite_selected: usize,
+    pub undo: Undo,
+    /// Balances in the background when set, otherwise equations are balanced straight away
+    pub worker: Option<Worker>,
+    /// The worker is balancing the input, [`App::output`] is for an older input
+    pub balancing: bool,
+    /// When the worker was given the input, to show how long it's been balancing
+    pub balance_started: Option<Instant>,
+    /// Frame of the balancing animation
+    pub spinner: usize,
+    /// Longest input in characters, longer inputs are cut off
+    pub max_input: usize,
+    /// Show the input next to the balanced equation
+    pub side_by_side: bool,
+    /// Write coefficients of 1 in the output and copied equations
+    pub explicit_ones: bool,
+    /// Fix the capitals of element symbols in the input before balancing
+    pub auto_capitalize: bool,
+    /// Show the oxidation state of each element under the output
+    pub show_oxidation: bool,
+    /// Show the net ionic equation under the output
+    pub show_net_ionic: bool,
+    /// Write coefficients as fractions with one of the first reactant, in the
+    /// output and copied equations
+    pub fractional: bool,
+    /// Ask for a second `q` before quitting with an equation typed in
+    pub confirm_quit: bool,
+    /// Write the numbers in formulas in the output as subscripts
+    pub subscripts: bool,
+    /// The colours to draw with
+    pub theme: Theme,
+    /// Which setting is chosen on the settings screen
+    pub setting_selected: usize,
+    /// Something was changed on the settings screen, to be saved on exit
+    pub settings_changed: bool,
+    /// When the first `q` was pressed, while waiting for the second one
+    pub confirming_quit: Option<Instant>,
+    /// The last equation that was balanced, even if the input has changed since
+    pub last_balanced: Option<BatchResult>,
+    /// How many compounds and elements the input has, from the last time it
+    /// could be read
+    pub counts: Option<chem::Counts>,
+    /// Whether the input has changed so it can't be read since `counts`
+    pub counts_stale: bool,
+    /// What's typed to search the examples
+    pub example_query: String,
+    /// Which of the matching examples is chosen
+    pub example_selected: usize,
+    /// Which compound's amount is being typed in the stoichiometry panel
+    pub stoich_selected: usize,
+    /// The amount typed for each compound and its unit, blank when it isn't known
+    pub stoich_inputs: Vec<(String, Unit)>,
+    /// What's typed to convert between grams and moles, eg. `18 g H2O`
+    pub convert_query: String,
+    /// The file the balanced equation is saved to, kept between saves so
+    /// they can go in one file
+    pub save_path: String,
+    /// Whether saving adds to the end of the file rather than replacing it
+    pub save_append: bool,
+    /// The file equations are loaded from
+    pub load_path: String,
+    /// The equations in the file last loaded, stepped through with `<` and `>`
+    pub loaded: Vec<String>,
+    /// Which of the loaded equations is in the input
+    pub loaded_index: usize,
+    /// Esc hid the completion of the formula being typed, until it's typed more
+    pub completion_dismissed: bool,
+    /// Atomic number of the element highlighted in the periodic table
+    pub table_selected: u32,
+    /// What's typed to find an element in the periodic table, eg. `fe` or `iron`
+    pub table_query: String,
+    /// Practicing balancing by hand. The input isn't balanced while it's set
+    pub quiz: Option<Quiz>,
+    /// Balance by half-reactions in this solution instead of as it's written
+    pub redox: Option<Medium>,
+    /// The halves the output was balanced by, in redox mode
+    pub half_reactions: Option<Redox>,
+    /// Every open equation. The active tab's state is kept in the app's own
+    /// fields, its entry here is empty until another tab is switched to
+    pub tabs: Vec<Tab>,
+    pub active_tab: usize,
+}
+
+/// An equation in a tab that isn't shown
+#[derive(Debug)]
+pub struct Tab {
+    pub input: String,
+    pub cursor: usize,
+    pub output: Option<Result<Equation, Error>>,
+    pub common_factor: usize,
+    pub balance_time: Option<Duration>,
+    pub output_scroll: u16,
+    pub undo: Undo,
+    pub half_reactions: Option<Redox>,
+    /// The equation has to be balanced again when the tab is shown
+    pub edited: bool,
+}
+
+impl Default for Tab {
+    fn default() -> Self {
+        Self {
+            input: String::new(),
+            cursor: 0,
+            output: None,
+            common_factor: 1,
+            balance_time: None,
+            output_scroll: 0,
+            undo: Undo::default(),
+            half_reactions: None,
+            edited: false,
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            input_mode: InputMode::default(),
+            focus: Focus::default(),
+            input: String::new(),
+            cursor: 0,
+            output: None,
+            common_factor: 1,
+            balance_time: None,
+            show_timing: false,
+            output_scroll: 0,
+            output_view: Cell::default(),
+            panes: Cell::default(),
+            status: None,
+            edited: false,
+            show_help: true,
+            history: History::default(),
+            undo: Undo::default(),
+            worker: None,
+            balancing: false,
+            balance_started: None,
+            spinner: 0,
+            max_input: DEFAULT_MAX_INPUT,
+            side_by_side: false,
+            explicit_ones: false,
+            auto_capitalize: false,
+            show_oxidation: false,
+            show_net_ionic: false,
+            fractional: false,
+            confirm_quit: true,
+            subscripts: false,
+            theme: Theme::Dark,
+            setting_selected: 0,
+            settings_changed: false,
+            confirming_quit: None,
+            last_balanced: None,
+            counts: None,
+            counts_stale: false,
+            example_query: String::new(),
+            example_selected: 0,
+            stoich_selected: 0,
+            stoich_inputs: Vec::new(),
+            convert_query: String::new(),
+            save_path: "equations.txt".to_string(),
+            save_append: true,
+            load_path: "equations.txt".to_string(),
+            loaded: Vec::new(),
+            loaded_index: 0,
+            favorites: Vec::new(),
+            favorite_selected: 0,
+            completion_dismissed: false,
+            table_selected: 1,
+            table_query: String::new(),
+            quiz: None,
+            redox: None,
+            half_reactions: None,
+            tabs: vec![Tab::default()],
+            active_tab: 0,
+        }
+    }
+}
+
+impl App {
+    /// Byte index of the cursor in the input
+    fn cursor_index(&self) -> usize {
+        self.input
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.input.len(), |(i, _)| i)
+    }
+
+    /// What the output was balanced from. In redox mode that's the equation
+    /// with the water, ions and electrons added, otherwise it's the input
+    pub fn balanced_from(&self) -> &str {
+        match &self.half_reactions {
+            Some(redox) => &redox.equation,
+            None => &self.input,
+        }
+    }
+
+    /// What the amounts typed in the stoichiometry panel react to make, or
+    /// nothing until there's a balanced equation and an amount typed
+    pub fn stoich_yield(&self) -> Option<Result<Yield, StoichError>> {
+        let Some(Ok(eq)) = &self.output else {
+            return None;
+        };
+        let (known, _) = self.stoich_known(eq)?;
+        let formulas = chem::typed_formulas(self.balanced_from(), eq);
+        Some(stoich::react(eq, formulas, &known))
+    }
+
+    /// The percent yield when amounts of reactants and of one product are
+    /// typed in the stoichiometry panel, taking the product's as what was made
+    pub fn stoich_percent_yield(&self) -> Option<Result<PercentYield, StoichError>> {
+        let Some(Ok(eq)) = &self.output else {
+            return None;
+        };
+        let (_, actual) = self.stoich_known(eq)?;
+        let actual = actual?;
+        Some(
+            self.stoich_yield()?
+                .and_then(|y| stoich::yield_of(eq, &y, actual.index, actual.amount, actual.unit)),
+        )
+    }
+
+    /// The amounts typed in the stoichiometry panel that react, and the one
+    /// that was made if there are reactants and just one product typed
+    fn stoich_known(&self, eq: &Equation) -> Option<(Vec<Known>, Option<Known>)> {
+        let mut known: Vec<Known> = self
+            .stoich_inputs
+            .iter()
+            .enumerate()
+            // a lone `.` is on its way to being a number
+            .filter_map(|(index, (amount, unit))| {
+                Some(Known {
+                    index,
+                    amount: amount.parse().ok()?,
+                    unit: *unit,
+                })
+            })
+            .collect();
+        if known.is_empty() || self.stoich_inputs.len() != eq.num_compounds() {
+            return None;
+        }
+        let reactants = stoich::reactants(eq);
+        let (made, used): (Vec<&Known>, Vec<&Known>) =
+            known.iter().partition(|k| !reactants.contains(&k.index));
+        let actual = match (made.as_slice(), used.is_empty()) {
+            (&[&product], false) => Some(product),
+            _ => None,
+        };
+        if let Some(actual) = actual {
+            known.retain(|k| k.index != actual.index);
+        }
+        Some((known, actual))
+    }
+
+    /// What's typed in the converter in the other unit, eg. `0.9992 mol` for
+    /// `18 g H2O`. `None` until something's typed
+    pub fn converted(&self) -> Option<Result<String, String>> {
+        if self.convert_query.trim().is_empty() {
+            return None;
+        }
+        let conversion: Conversion = match self.convert_query.parse() {
+            Ok(c) => c,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(
+            stoich::convert(&conversion)
+                .map(|n| format!("{} {}", stoich::round(n), conversion.unit.toggled()))
+                .map_err(|e| e.to_string()),
+        )
+    }
+
+    /// Replace the input, moving the cursor to its end, and balance it
+    pub fn set_input(&mut self, input: &str) {
+        self.undo.record(Edit::Replace, self.snapshot());
+        self.input = match input.char_indices().nth(self.max_input) {
+            Some((end, _)) => {
+                self.warn_too_long();
+                input[..end].to_string()
+            }
+            None => input.to_string(),
+        };
+        self.cursor = self.input.chars().count();
+        self.update_eq();
+    }
+
+    /// Insert a character before the cursor
+    pub fn insert(&mut self, c: char) {
+        if self.input.chars().count() >= self.max_input {
+            self.warn_too_long();
+            return;
+        }
+        let edit = Edit::Insert {
+            space: c.is_whitespace(),
+        };
+        self.undo.record(edit, self.snapshot());
+        let idx = self.cursor_index();
+        self.input.insert(idx, c);
+        self.cursor += 1;
+        self.edited = true;
+        self.completion_dismissed = false;
+    }
+
+    /// Insert pasted text before the cursor, undone in one go. Line breaks
+    /// become spaces, and whatever doesn't fit in [`App::max_input`] is cut off
+    pub fn paste(&mut self, text: &str) {
+        let text = text
+            .trim_matches(['\r', '\n'])
+            .lines()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let room = self.max_input.saturating_sub(self.input.chars().count());
+        let text: String = match text.char_indices().nth(room) {
+            Some((end, _)) => {
+                self.warn_too_long();
+                text[..end].to_string()
+            }
+            None => text,
+        };
+        if text.is_empty() {
+            return;
+        }
+        self.undo.record(Edit::Replace, self.snapshot());
+        let idx = self.cursor_index();
+        self.input.insert_str(idx, &text);
+        self.cursor += text.chars().count();
+        self.edited = true;
+    }
+
+    /// Remove the character before the cursor
+    pub fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.undo.record(Edit::Remove, self.snapshot());
+        self.cursor -= 1;
+        let idx = self.cursor_index();
+        self.input.remove(idx);
+        self.edited = true;
+        self.completion_dismissed = false;
+    }
+
+    /// The rest of the common formula being typed, to show after the cursor and
+    /// fill in with tab. Only while editing at the end of a formula
+    pub fn completion(&self) -> Option<&'static str> {
+        if !matches!(self.input_mode, InputMode::Editing) || self.completion_dismissed {
+            return None;
+        }
+        let (before, after) = self.input.split_at(self.cursor_index());
+        if after.starts_with(|c: char| !c.is_whitespace()) {
+            return None;
+        }
+        suggest::complete(before)
+    }
+
+    /// Remove the character under the cursor
+    pub fn delete(&mut self) {
+        let idx = self.cursor_index();
+        if idx < self.input.len() {
+            self.undo.record(Edit::Remove, self.snapshot());
+            self.input.remove(idx);
+            self.edited = true;
+        }
+    }
+
+    fn warn_too_long(&mut self) {
+        self.status = Some(Status::Warning(format!(
+            "Input is limited to {} characters",
+            self.max_input
+        )));
+    }
+
+    /// Undo the last group of edits
+    pub fn undo(&mut self) {
+        if let Some(snapshot) = self.undo.undo(self.snapshot()) {
+            self.restore(snapshot);
+        }
+    }
+
+    /// Redo the last undone group of edits
+    pub fn redo(&mut self) {
+        if let Some(snapshot) = self.undo.redo(self.snapshot()) {
+            self.restore(snapshot);
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            input: self.input.clone(),
+            cursor: self.cursor,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.input = snapshot.input;
+        self.cursor = snapshot.cursor;
+        self.edited = true;
+    }
+
+    /// Scroll the output by `lines`, down when positive, staying inside it
+    pub fn scroll_output(&mut self, lines: i32) {
+        let max = self.output_view.get().overflow();
+        // start from what's shown, the output may have grown since it was scrolled
+        let from = i32::from(self.output_scroll.min(max));
+        self.output_scroll = (from + lines).clamp(0, i32::from(max)) as u16;
+    }
+
+    /// Handle a left click on the terminal. Clicking the input edits it with the
+    /// cursor where it was clicked, clicking the output focuses it
+    pub fn click(&mut self, column: u16, row: u16) {
+        if !matches!(self.input_mode, InputMode::Normal | InputMode::Editing) {
+            return;
+        }
+        let panes = self.panes.get();
+        let pos = Position::new(column, row);
+        if panes.input.contains(pos) {
+            let width = panes.input.width.saturating_sub(2);
+            // the border comes before the scrolled text, which starts with a space
+            let inside = (column - panes.input.x).saturating_sub(1) + self.input_scroll(width);
+            let clicked = usize::from(inside.saturating_sub(1));
+            let mut before = 0;
+            let cursor = self.input.chars().position(|c| {
+                before += c.width().unwrap_or(0);
+                before > clicked
+            });
+            self.undo.split();
+            self.cursor = cursor.unwrap_or_else(|| self.input.chars().count());
+            self.focus = Focus::Input;
+            self.input_mode = InputMode::Editing;
+        } else if panes.output.contains(pos) {
+            if let InputMode::Editing = self.input_mode {
+                self.history.push(&self.input);
+                self.input_mode = InputMode::Normal;
+            }
+            self.focus = Focus::Output;
+        }
+    }
+
+    /// Open an empty tab after the active one and switch to it
+    pub fn new_tab(&mut self) {
+        let tab = self.park();
+        self.tabs[self.active_tab] = tab;
+        self.active_tab += 1;
+        self.tabs.insert(self.active_tab, Tab::default());
+        self.unpark(Tab::default());
+    }
+
+    /// Close the active tab, switching to the next one. The last tab is cleared instead
+    pub fn close_tab(&mut self) {
+        self.park();
+        if self.tabs.len() > 1 {
+            self.tabs.remove(self.active_tab);
+            self.active_tab = self.active_tab.min(self.tabs.len() - 1);
+        }
+        let tab = std::mem::take(&mut self.tabs[self.active_tab]);
+        self.unpark(tab);
+    }
+
+    /// Switch to the tab at `index`, if there is one
+    pub fn switch_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() || index == self.active_tab {
+            return;
+        }
+        let tab = self.park();
+        self.tabs[self.active_tab] = tab;
+        self.active_tab = index;
+        let tab = std::mem::take(&mut self.tabs[index]);
+        self.unpark(tab);
+    }
+
+    /// Everything open, to be saved and restored later
+    pub fn session(&self) -> Session {
+        let tabs = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| match i == self.active_tab {
+                true => SavedTab {
+                    input: self.input.clone(),
+                    cursor: self.cursor,
+                },
+                false => SavedTab {
+                    input: tab.input.clone(),
+                    cursor: tab.cursor,
+                },
+            })
+            .collect();
+        Session {
+            version: VERSION,
+            tabs,
+            active_tab: self.active_tab,
+            history: self.history.entries().to_vec(),
+            toggles: Toggles {
+                show_help: self.show_help,
+                side_by_side: self.side_by_side,
+                explicit_ones: self.explicit_ones,
+                show_oxidation: self.show_oxidation,
+                show_net_ionic: self.show_net_ionic,
+                fractional: self.fractional,
+                redox: self.redox,
+            },
+        }
+    }
+
+    /// Every tab's equation for a report, balancing any that haven't been
+    /// since they changed. The active tab has its amounts too
+    pub fn report_entries(&self) -> Vec<Entry> {
+        let balanced =
+            |input: &str, output: &Option<Result<Equation, Error>>, edited: bool| match output
+                .as_ref()
+                .filter(|_| !edited)
+            {
+                Some(output) => BatchResult {
+                    input: input.to_string(),
+                    output: output.clone(),
+                },
+                None => match self.redox {
+                    Some(medium) => BatchResult::redox(input, medium),
+                    None => BatchResult::new(input),
+                },
+            };
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| match i == self.active_tab {
+                true => Entry {
+                    result: balanced(
+                        self.balanced_from(),
+                        &self.output,
+                        self.edited || self.balancing,
+                    ),
+                    amounts: self.stoich_yield().and_then(Result::ok),
+                },
+                false => Entry::new(balanced(&tab.input, &tab.output, tab.edited)),
+            })
+            .collect()
+    }
+
+    /// Read the equations in the file at `load_path` and put the first in the
+    /// input. A file that can't be read or has none is said in the status line
+    fn load_file(&mut self) {
+        let path = batch::expand_home(self.load_path.trim());
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                self.status = Some(Status::Error(format!(
+                    "Couldn't read {}: {e}",
+                    path.display()
+                )));
+                return;
+            }
+        };
+        let equations: Vec<String> = batch::equations(&text)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        if equations.is_empty() {
+            self.status = Some(Status::Warning(format!(
+                "No equations in {}",
+                path.display()
+            )));
+            return;
+        }
+        self.loaded = equations;
+        self.show_loaded(0);
+    }
+
+    /// Put the loaded equation at `index` in the input, saying which it is
+    fn show_loaded(&mut self, index: usize) {
+        let Some(equation) = self.loaded.get(index).cloned() else {
+            return;
+        };
+        self.loaded_index = index;
+        self.set_input(&equation);
+        self.status = Some(Status::Info(format!(
+            "Equation {} of {} from {}, < and > for the others",
+            index + 1,
+            self.loaded.len(),
+            self.load_path.trim()
+        )));
+    }
+
+    /// Whether the input is one of the favorites
+    pub fn is_favorite(&self) -> bool {
+        let input = self.input.trim();
+        !input.is_empty() && self.favorites.iter().any(|f| f == input)
+    }
+
+    /// Star the input, or unstar it if it already is
+    fn toggle_favorite(&mut self) {
+        let input = self.input.trim().to_string();
+        if input.is_empty() {
+            self.status = Some(Status::Warning("Nothing to add to favorites".to_string()));
+        } else if self.is_favorite() {
+            self.favorites.retain(|f| *f != input);
+            self.status = Some(Status::Info("Removed from favorites".to_string()));
+        } else {
+            self.favorites.push(input);
+            self.status = Some(Status::Info(
+                "Added to favorites, f to see them".to_string(),
+            ));
+        }
+    }
+
+    /// Every equation entered this session balanced again, with the input
+    /// too if it hasn't been entered yet
+    pub fn transcript(&self) -> Vec<BatchResult> {
+        let mut inputs = self.history.this_session().to_vec();
+        let input = self.input.trim();
+        if !input.is_empty() && inputs.last().map(String::as_str) != Some(input) {
+            inputs.push(input.to_string());
+        }
+        inputs
+            .iter()
+            .map(|input| match self.redox {
+                Some(medium) => BatchResult::redox(input, medium),
+                None => BatchResult::new(input),
+            })
+            .collect()
+    }
+
+    /// Open a saved session in place of the tabs open now. Its history is
+    /// added to the history already loaded
+    pub fn restore_session(&mut self, session: Session) {
+        let toggles = session.toggles;
+        self.show_help = toggles.show_help;
+        self.side_by_side = toggles.side_by_side;
+        self.explicit_ones = toggles.explicit_ones;
+        self.show_oxidation = toggles.show_oxidation;
+        self.show_net_ionic = toggles.show_net_ionic;
+        self.fractional = toggles.fractional;
+        self.redox = toggles.redox;
+        for entry in &session.history {
+            self.history.push(entry);
+        }
+
+        self.park();
+        self.tabs = session
+            .tabs
+            .into_iter()
+            .map(|tab| Tab {
+                cursor: tab.cursor.min(tab.input.chars().count()),
+                input: tab.input,
+                // balanced when it's shown
+                edited: true,
+                ..Default::default()
+            })
+            .collect();
+        if self.tabs.is_empty() {
+            self.tabs.push(Tab::default());
+        }
+        self.active_tab = session.active_tab.min(self.tabs.len() - 1);
+        let tab = std::mem::take(&mut self.tabs[self.active_tab]);
+        self.unpark(tab);
+    }
+
+    /// Take the active tab's state out of the app
+    fn park(&mut self) -> Tab {
+        let tab = Tab {
+            input: std::mem::take(&mut self.input),
+            cursor: self.cursor,
+            output: self.output.take(),
+            common_factor: self.common_factor,
+            balance_time: self.balance_time,
+            output_scroll: self.output_scroll,
+            undo: std::mem::take(&mut self.undo),
+            half_reactions: self.half_reactions.take(),
+            // the worker's result would be for the wrong tab
+            edited: self.edited || self.balancing,
+        };
+        self.balancing = false;
+        tab
+    }
+
+    /// Make `tab` the active tab's state
+    fn unpark(&mut self, tab: Tab) {
+        self.input = tab.input;
+        self.cursor = tab.cursor;
+        self.output = tab.output;
+        self.common_factor = tab.common_factor;
+        self.balance_time = tab.balance_time;
+        self.output_scroll = tab.output_scroll;
+        self.undo = tab.undo;
+        self.half_reactions = tab.half_reactions;
+        self.edited = false;
+        if tab.edited {
+            self.update_eq();
+        }
+    }
+
+    /// Move the cursor by `by` characters, staying inside the input
+    pub fn move_cursor(&mut self, by: isize) {
+        let len = self.input.chars().count();
+        self.cursor = self.cursor.saturating_add_signed(by).min(len);
+    }
+
+    /// How many columns the input has to be scrolled to keep the cursor in view,
+    /// when `width` columns are visible
+    pub fn input_scroll(&self, width: u16) -> u16 {
+        // leading space + text before the cursor + the cursor itself
+        let needed = 1 + self.input[..self.cursor_index()].width() + 1;
+        let scroll = needed.saturating_sub(width as usize);
+        u16::try_from(scroll).unwrap_or(u16::MAX)
+    }
+
+    /// Column the cursor is drawn at, relative to the inside of the input box
+    pub fn cursor_column(&self, width: u16) -> u16 {
+        let before = 1 + self.input[..self.cursor_index()].width();
+        let column = before.saturating_sub(self.input_scroll(width) as usize);
+        // scrolling keeps this inside the box
+        column.min(width.saturating_sub(1) as usize) as u16
+    }
+
+    /// What the input probably should have been, when it couldn't be balanced
+    pub fn suggestion(&self) -> Option<String> {
+        match self.output {
+            Some(Err(_)) if !self.balancing => suggest(&self.input),
+            _ => None,
+        }
+    }
+
+    /// The input finished as combustion, when it's only a fuel and oxygen and
+    /// so couldn't be balanced
+    pub fn combustion(&self) -> Option<String> {
+        match self.output {
+            Some(Err(_)) if !self.balancing => classify::complete_combustion(&self.input),
+            _ => None,
+        }
+    }
+
+    /// Highlight the element [`App::table_query`] finds, leaving the highlight
+    /// where it was while nothing matches
+    fn find_element(&mut self) {
+        if let Some(info) = elements::search(&self.table_query) {
+            self.table_selected = info.number;
+        }
+    }
+
+    /// Start practicing with a random example, or stop and balance what's typed
+    pub fn toggle_quiz(&mut self) {
+        if let Some(quiz) = self.quiz.take() {
+            self.status = Some(Status::Info(format!(
+                "Quiz over, {}/{} right",
+                quiz.right, quiz.finished
+            )));
+            self.update_eq();
+            return;
+        }
+        let quiz = Quiz::new();
+        let question = quiz.example.equation;
+        self.quiz = Some(quiz);
+        self.set_input(question);
+        self.input_mode = InputMode::Editing;
+        self.focus = Focus::Input;
+        self.status = Some(Status::Info(
+            "Add coefficients to balance it, enter to check".to_string(),
+        ));
+    }
+
+    /// Switch between balancing normally and by half-reactions in an acidic
+    /// then a basic solution
+    pub fn toggle_redox(&mut self) {
+        self.redox = match self.redox {
+            None => Some(Medium::Acidic),
+            Some(Medium::Acidic) => Some(Medium::Basic),
+            Some(Medium::Basic) => None,
+        };
+        let msg = match self.redox {
+            Some(medium) => format!("Balancing by half-reactions in a {medium} solution"),
+            None => "Balancing as written".to_string(),
+        };
+        self.status = Some(Status::Info(msg));
+        // the other tabs were balanced the old way
+        self.tabs.iter_mut().for_each(|t| t.edited = true);
+        self.update_eq();
+    }
+
+    /// Check the input against the quiz, or move on once the question is solved
+    fn submit_answer(&mut self) {
+        let Some(quiz) = &mut self.quiz else {
+            return;
+        };
+        if quiz.solved {
+            quiz.next();
+            let question = quiz.example.equation;
+            self.set_input(question);
+            self.status = None;
+            return;
+        }
+        self.status = Some(match quiz.check(&self.input) {
+            Verdict::Correct => Status::Info("Correct! Enter for the next one".to_string()),
+            Verdict::Incorrect => {
+                Status::Warning("Not balanced, try again or ctrl-a for the answer".to_string())
+            }
+            Verdict::Changed => {
+                Status::Warning("Only add coefficients, keep the compounds the same".to_string())
+            }
+            Verdict::Invalid => Status::Warning("That isn't an equation".to_string()),
+        });
+    }
+
+    /// The terminal title for the last equation balanced, if there's been one
+    pub fn window_title(&self) -> Option<String> {
+        let last = self.last_balanced.as_ref()?;
+        let eq = last.output.as_ref().ok()?;
+        Some(title::title(&plain_equation(&last.input, eq, false)))
+    }
+
+    /// Show the result of balancing the current input
+    fn set_output(&mut self, output: Result<(Equation, usize), Error>, took: Duration) {
+        self.balance_time = Some(took);
+        self.common_factor = 1;
+        self.output_scroll = 0;
+        let output = output.map(|(eq, factor)| {
+            self.common_factor = factor;
+            self.last_balanced = Some(BatchResult {
+                input: self.balanced_from().to_string(),
+                output: Ok(eq.clone()),
+            });
+            eq
+        });
+        self.output = Some(output);
+    }
+
+    /// Count the compounds and elements in the input, keeping the last counts
+    /// when it can't be read
+    fn update_counts(&mut self) {
+        if self.input.trim().is_empty() {
+            self.counts = None;
+            self.counts_stale = false;
+            return;
+        }
+        match balance::parse(&self.input) {
+            Ok(eq) => {
+                self.counts = Some(chem::counts(&eq));
+                self.counts_stale = false;
+            }
+            Err(_) => self.counts_stale = true,
+        }
+    }
+
+    /// Called every tick of the event loop, even without input. Returns whether
+    /// anything changed and has to be drawn
+    pub fn on_tick(&mut self) -> bool {
+        let mut changed = false;
+        if self
+            .confirming_quit
+            .is_some_and(|since| since.elapsed() >= QUIT_TIMEOUT)
+        {
+            self.confirming_quit = None;
+            self.status = None;
+            changed = true;
+        }
+        if self.balancing {
+            self.spinner = self.spinner.wrapping_add(1);
+            if let Some(done) = self.worker.as_mut().and_then(|w| w.receive(Duration::ZERO)) {
+                self.set_output(done.output, done.took);
+                self.balancing = false;
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn update_eq(&mut self) {
+        self.edited = false;
+        self.balancing = false;
+        self.half_reactions = None;
+        // balancing would give the answer away
+        if self.input.is_empty() || self.quiz.is_some() {
+            self.output = None;
+            self.balance_time = None;
+            self.update_counts();
+            return;
+        }
+        if self.auto_capitalize {
+            // only the case of letters changes, so the cursor stays put
+            self.input = suggest::capitalize(&self.input);
+        }
+        self.update_counts();
+        if self.input.chars().count() > self.max_input {
+            self.output = Some(Err(Error::TooLong(self.max_input)));
+            self.balance_time = None;
+            return;
+        }
+        // half-reactions are quick to balance, so they don't need the worker
+        if let Some(medium) = self.redox {
+            let start = Instant::now();
+            let output = match redox::balance(&self.input, medium) {
+                Ok(halves) => {
+                    let eq = Equation::new(&normalize(&halves.equation));
+                    self.half_reactions = Some(halves);
+                    eq.map(|eq| (eq, 1)).map_err(Error::from)
+                }
+                Err(e) => Err(e.into()),
+            };
+            self.set_output(output, start.elapsed());
+            return;
+        }
+        let Some(worker) = &mut self.worker else {
+            let start = Instant::now();
+            let output = balance_reduced(&self.input);
+            self.set_output(output, start.elapsed());
+            return;
+        };
+        self.balance_started = Some(Instant::now());
+        worker.submit(&self.input);
+        match worker.receive(QUICK_BALANCE) {
+            Some(done) => self.set_output(done.output, done.took),
+            None => self.balancing = true,
+        }
+    }
+}
+
+/// Where each pane is on the screen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Panes {
+    pub input: Rect,
+    pub output: Rect,
+}
+
+/// How much of the output fits in its pane
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputView {
+    /// Lines of output once it's wrapped
+    pub lines: u16,
+    /// Lines that fit inside the pane
+    pub height: u16,
+}
+
+impl OutputView {
+    /// Lines that don't fit, which is how far the output can scroll
+    pub fn overflow(self) -> u16 {
+        self.lines.saturating_sub(self.height)
+    }
+}
+
+/// A message shown in the status line
+#[derive(Debug)]
+pub enum Status {
+    Info(String),
+    Warning(String),
+    Error(String),
+}
+
+#[derive(Debug, Default)]
+pub enum InputMode {
+    Editing,
+    #[default]
+    Normal,
+    /// The full screen list of keybindings is shown
+    Help,
+    /// Choosing how to copy the balanced equation
+    Copy,
+    /// Searching the examples for an equation to balance
+    Examples,
+    /// Working out amounts of each compound from one that's known
+    Stoich,
+    /// Converting between grams and moles of a compound
+    Convert,
+    /// Looking up elements in the periodic table
+    Table,
+    /// Typing the file to save the balanced equation to
+    Save,
+    /// Typing the file to load equations from
+    Load,
+    /// Choosing how to copy or save the equations entered this session
+    Transcript,
+    /// Picking one of the favorite equations
+    Favorites,
+    /// Changing the settings that are saved to the config file
+    Settings,
+}
+
+impl InputMode {
+    /// Every mode, add new ones here so their help is counted in [`Self::HELP_LINES`]
+    const ALL: [Self; 13] = [
+        Self::Editing,
+        Self::Normal,
+        Self::Help,
+        Self::Copy,
+        Self::Examples,
+        Self::Stoich,
+        Self::Convert,
+        Self::Table,
+        Self::Save,
+        Self::Load,
+        Self::Transcript,
+        Self::Favorites,
+        Self::Settings,
+    ];
+
+    /// Lines in the longest help text, for the help panel to fit them all
+    pub const HELP_LINES: u16 = {
+        let mut most = 0;
+        let mut i = 0;
+        while i < Self::ALL.len() {
+            let lines = Self::ALL[i].help_lines();
+            if lines > most {
+                most = lines;
+            }
+            i += 1;
+        }
+        most
+    };
+
+    /// Lines in this mode's help text
+    pub const fn help_lines(&self) -> u16 {
+        let text = self.to_help().as_bytes();
+        let mut lines = 1;
+        let mut i = 0;
+        while i < text.len() {
+            if text[i] == b'\n' {
+                lines += 1;
+            }
+            i += 1;
+        }
+        lines
+    }
+
+    pub const fn to_help(&self) -> &'static str {
+        match self {
+            Self::Normal => " i or e          to edit\n q or esc        to quit\n y               to copy balanced equation\n ?               to toggle this help",
+            Self::Editing => " esc or ctrl-[   leave editing mode\n ← → home end    move the cursor\n ↑ ↓             previous equations\n tab             fill in the grey formula\n ctrl-z ctrl-y   undo and redo\n ctrl-o          edit it in $EDITOR",
+            Self::Help => " any key         close this screen",
+            Self::Copy => " p l u j         choose a format\n esc             cancel",
+            Self::Examples => " type            to search\n ↑ ↓             choose an example\n enter           use it\n esc             cancel",
+            Self::Stoich => " type            how much there is\n ↑ ↓             choose a compound\n tab             switch between g and mol\n esc             close",
+            Self::Convert => " type            an amount, eg. 18 g H2O\n esc or enter    close",
+            Self::Table => " ← → ↑ ↓         choose an element\n type            find one by symbol or name\n enter           type its symbol\n esc             close",
+            Self::Save => " type            the file to save to\n tab             add to it or replace it\n enter           save\n esc             cancel",
+            Self::Load => " type            the file to load\n enter           load its first equation\n esc             cancel",
+            Self::Favorites => " ↑ ↓             choose a favorite\n enter           use it\n delete          remove it\n esc             close",
+            Self::Settings => " ↑ ↓             choose a setting\n enter or space  turn it on or off\n esc             close",
+            Self::Transcript => " p m c           copy as plain text, markdown or csv\n P M C           save as them to a file\n esc             cancel",
+        }
+    }
+}
+
+/// An option that can be changed on the settings screen, in the order they're
+/// listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    LightTheme,
+    ShowHelp,
+    Subscripts,
+    ConfirmQuit,
+    ExplicitOnes,
+}
+
+impl Setting {
+    pub const ALL: [Self; 5] = [
+        Self::LightTheme,
+        Self::ShowHelp,
+        Self::Subscripts,
+        Self::ConfirmQuit,
+        Self::ExplicitOnes,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::LightTheme => "Colours for a light background",
+            Self::ShowHelp => "Show the keys under the output",
+            Self::Subscripts => "Write numbers as subscripts",
+            Self::ConfirmQuit => "Confirm quitting with an equation typed in",
+            Self::ExplicitOnes => "Write coefficients of 1",
+        }
+    }
+
+    /// Whether it's turned on in `app`
+    pub const fn value(self, app: &App) -> bool {
+        match self {
+            Self::LightTheme => matches!(app.theme, Theme::Light),
+            Self::ShowHelp => app.show_help,
+            Self::Subscripts => app.subscripts,
+            Self::ConfirmQuit => app.confirm_quit,
+            Self::ExplicitOnes => app.explicit_ones,
+        }
+    }
+
+    fn toggle(self, app: &mut App) {
+        let value = match self {
+            Self::LightTheme => {
+                app.theme = app.theme.toggled();
+                return;
+            }
+            Self::ShowHelp => &mut app.show_help,
+            Self::Subscripts => &mut app.subscripts,
+            Self::ConfirmQuit => &mut app.confirm_quit,
+            Self::ExplicitOnes => &mut app.explicit_ones,
+        };
+        *value = !*value;
+    }
+}
+
+/// A pane that can be focused, in the order tab moves through them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Focus {
+    #[default]
+    Input,
+    Output,
+}
+
+impl Focus {
+    const ALL: [Self; 2] = [Self::Input, Self::Output];
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let i = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Something the terminal has to do after a key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    /// Clear the terminal and draw everything again, for when it's garbled
+    Redraw,
+    /// Copy the text to the clipboard
+    Copy {
+        text: String,
+        format: CopyFormat,
+    },
+    /// Save everything open to be restored with `--resume`
+    SaveSession,
+    /// Write a report or transcript to a file
+    Export {
+        report: String,
+        path: PathBuf,
+    },
+    /// Edit the input in the user's editor
+    OpenEditor,
+    /// Stop in the background like ctrl-z does in a shell, until it's resumed
+    Suspend,
+    /// Copy the equations entered this session to the clipboard
+    CopyTranscript {
+        text: String,
+        format: Format,
+    },
+    /// Write the text to a file as a line, after what's there if `append`
+    Save {
+        text: String,
+        path: PathBuf,
+        append: bool,
+    },
+}
+
+/// Apply a key press to the app. To balance once after a burst of key presses,
+/// edits don't balance the equation, call [`App::update_eq`] when [`App::edited`] is set.
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Option<Action> {
+    // any key other than a second `q` cancels quitting
+    if key.kind != KeyEventKind::Release && app.confirming_quit.take().is_some() {
+        app.status = None;
+        if matches!(
+            translate(&app.input_mode, app.focus, key),
+            Some(Command::Quit)
+        ) {
+            return Some(Action::Quit);
+        }
+    }
+    match translate(&app.input_mode, app.focus, key)? {
+        Command::ForceQuit => return Some(Action::Quit),
+        // nothing is lost quitting without an equation
+        Command::Quit if app.confirm_quit && !app.input.is_empty() => {
+            app.confirming_quit = Some(Instant::now());
+            app.status = Some(Status::Warning("Press q again to quit".to_string()));
+        }
+        Command::Quit => return Some(Action::Quit),
+        Command::Redraw => return Some(Action::Redraw),
+        Command::Edit => app.input_mode = InputMode::Editing,
+        // the first esc only hides the completion
+        Command::StopEditing if app.completion().is_some() => app.completion_dismissed = true,
+        Command::StopEditing => {
+            app.history.push(&app.input);
+            app.input_mode = InputMode::Normal;
+        }
+        Command::Complete => {
+            if let Some(rest) = app.completion() {
+                app.paste(rest);
+            }
+        }
+        Command::FocusNext => app.focus = app.focus.next(),
+        Command::FocusPrev => app.focus = app.focus.prev(),
+        Command::ScrollUp => app.scroll_output(-1),
+        Command::ScrollDown => app.scroll_output(1),
+        Command::PageUp => app.scroll_output(-i32::from(app.output_view.get().height.max(1))),
+        Command::PageDown => app.scroll_output(i32::from(app.output_view.get().height.max(1))),
+        Command::ToggleHelp => app.show_help = !app.show_help,
+        Command::NewTab => app.new_tab(),
+        Command::CloseTab => app.close_tab(),
+        Command::PrevTab => app.switch_tab(app.active_tab.saturating_sub(1)),
+        Command::NextTab => app.switch_tab(app.active_tab + 1),
+        Command::GoToTab(index) => app.switch_tab(index),
+        Command::ToggleSideBySide => app.side_by_side = !app.side_by_side,
+        Command::ToggleRedox => app.toggle_redox(),
+        Command::ToggleOnes => {
+            app.explicit_ones = !app.explicit_ones;
+            let msg = if app.explicit_ones {
+                "Showing coefficients of 1"
+            } else {
+                "Hiding coefficients of 1"
+            };
+            app.status = Some(Status::Info(msg.to_string()));
+        }
+        Command::ToggleOxidation => {
+            app.show_oxidation = !app.show_oxidation;
+            let msg = if app.show_oxidation {
+                "Showing oxidation states"
+            } else {
+                "Hiding oxidation states"
+            };
+            app.status = Some(Status::Info(msg.to_string()));
+        }
+        Command::ToggleFractional => {
+            app.fractional = !app.fractional;
+            let msg = if app.fractional {
+                "Showing fractional coefficients"
+            } else {
+                "Showing whole number coefficients"
+            };
+            app.status = Some(Status::Info(msg.to_string()));
+        }
+        Command::ToggleNetIonic => {
+            app.show_net_ionic = !app.show_net_ionic;
+            let msg = if app.show_net_ionic {
+                "Showing the net ionic equation"
+            } else {
+                "Hiding the net ionic equation"
+            };
+            app.status = Some(Status::Info(msg.to_string()));
+        }
+        Command::ShowKeys => app.input_mode = InputMode::Help,
+        Command::CloseKeys => app.input_mode = InputMode::Normal,
+        Command::CopyAs(CopyFormat::Input) => {
+            app.input_mode = InputMode::Normal;
+            if app.input.is_empty() {
+                app.status = Some(Status::Warning("Nothing to copy".to_string()));
+            } else {
+                return Some(Action::Copy {
+                    text: app.input.clone(),
+                    format: CopyFormat::Input,
+                });
+            }
+        }
+        // the output is out of date while balancing
+        Command::Copy | Command::CopyAs(_) if app.balancing => {
+            app.input_mode = InputMode::Normal;
+            app.status = Some(Status::Warning("Still balancing".to_string()));
+        }
+        Command::Copy | Command::CopyAs(_) if !matches!(app.output, Some(Ok(_))) => {
+            app.input_mode = InputMode::Normal;
+            app.status = Some(Status::Warning("Nothing to copy".to_string()));
+        }
+        Command::Copy => app.input_mode = InputMode::Copy,
+        Command::AcceptSuggestion => match app.suggestion().or_else(|| app.combustion()) {
+            Some(suggestion) => {
+                app.set_input(&suggestion);
+                app.status = None;
+            }
+            None => app.status = Some(Status::Warning("Nothing to suggest".to_string())),
+        },
+        Command::EditBalanced => match &app.output {
+            _ if app.balancing => {
+                app.status = Some(Status::Warning("Still balancing".to_string()));
+            }
+            Some(Ok(eq)) => {
+                let balanced = plain_equation(app.balanced_from(), eq, false);
+                app.set_input(&balanced);
+                app.input_mode = InputMode::Editing;
+                app.focus = Focus::Input;
+            }
+            _ => app.status = Some(Status::Warning("Nothing balanced to edit".to_string())),
+        },
+        Command::Reverse => match chem::reverse(&app.input) {
+            Some(reversed) => app.set_input(&reversed),
+            None if app.input.trim().is_empty() => {
+                app.status = Some(Status::Warning("Nothing to reverse".to_string()));
+            }
+            None => {
+                app.status = Some(Status::Warning(
+                    "Couldn't read the equation to reverse".to_string(),
+                ));
+            }
+        },
+        Command::CopyAs(format) => {
+            app.input_mode = InputMode::Normal;
+            if let Some(Ok(eq)) = &app.output {
+                let text = if app.fractional {
+                    fractional_text(format, app.balanced_from(), eq, 0)
+                } else {
+                    copy_text(format, app.balanced_from(), eq, app.explicit_ones)
+                };
+                return Some(Action::Copy { text, format });
+            }
+        }
+        Command::CancelCopy => app.input_mode = InputMode::Normal,
+        Command::ShowExamples => {
+            app.example_query.clear();
+            app.example_selected = 0;
+            app.input_mode = InputMode::Examples;
+        }
+        Command::SearchExamples(c) => {
+            app.example_query.push(c);
+            app.example_selected = 0;
+        }
+        Command::SearchBackspace => {
+            app.example_query.pop();
+            app.example_selected = 0;
+        }
+        Command::PrevExample => app.example_selected = app.example_selected.saturating_sub(1),
+        Command::NextExample => {
+            let found = examples::search(&app.example_query).len();
+            app.example_selected = (app.example_selected + 1).min(found.saturating_sub(1));
+        }
+        // the coefficients are only a ratio once they're balanced
+        Command::ShowStoich if app.balancing || app.edited => {
+            app.status = Some(Status::Warning("Still balancing".to_string()));
+        }
+        Command::ShowStoich => match &app.output {
+            Some(Ok(eq)) if eq.is_balanced() => {
+                app.stoich_selected = 0;
+                app.stoich_inputs = vec![(String::new(), Unit::default()); eq.num_compounds()];
+                app.input_mode = InputMode::Stoich;
+            }
+            _ => app.status = Some(Status::Warning("Balance an equation first".to_string())),
+        },
+        Command::CloseStoich => app.input_mode = InputMode::Normal,
+        Command::PrevCompound => app.stoich_selected = app.stoich_selected.saturating_sub(1),
+        Command::NextCompound => {
+            if let Some(Ok(eq)) = &app.output {
+                app.stoich_selected = (app.stoich_selected + 1).min(eq.num_compounds() - 1);
+            }
+        }
+        Command::TypeAmount(c) => {
+            if let Some((amount, _)) = app.stoich_inputs.get_mut(app.stoich_selected) {
+                amount.push(c);
+            }
+        }
+        Command::AmountBackspace => {
+            if let Some((amount, _)) = app.stoich_inputs.get_mut(app.stoich_selected) {
+                amount.pop();
+            }
+        }
+        Command::ToggleUnit => {
+            if let Some((_, unit)) = app.stoich_inputs.get_mut(app.stoich_selected) {
+                *unit = unit.toggled();
+            }
+        }
+        Command::ToggleQuiz => app.toggle_quiz(),
+        Command::Submit => app.submit_answer(),
+        Command::RevealAnswer => match &mut app.quiz {
+            Some(quiz) => {
+                quiz.reveal();
+                app.status = Some(Status::Info(format!(
+                    "{}, enter for the next one",
+                    quiz.answer
+                )));
+            }
+            None => app.status = Some(Status::Warning("Not in a quiz, Q starts one".to_string())),
+        },
+        Command::CloseExamples => app.input_mode = InputMode::Normal,
+        Command::ShowConvert => {
+            app.convert_query.clear();
+            app.input_mode = InputMode::Convert;
+        }
+        Command::TypeConversion(c) => app.convert_query.push(c),
+        Command::ConversionBackspace => {
+            app.convert_query.pop();
+        }
+        Command::CloseConvert => app.input_mode = InputMode::Normal,
+        Command::ShowSave if app.balancing || app.edited => {
+            app.status = Some(Status::Warning("Still balancing".to_string()));
+        }
+        Command::ShowSave => match &app.output {
+            Some(Ok(_)) => app.input_mode = InputMode::Save,
+            _ => app.status = Some(Status::Warning("Nothing balanced to save".to_string())),
+        },
+        Command::TypeSavePath(c) => app.save_path.push(c),
+        Command::SavePathBackspace => {
+            app.save_path.pop();
+        }
+        Command::ToggleAppend => app.save_append = !app.save_append,
+        Command::SaveEquation => match &app.output {
+            _ if app.save_path.trim().is_empty() => {
+                app.status = Some(Status::Warning("Type a file to save to".to_string()));
+            }
+            Some(Ok(eq)) => {
+                app.input_mode = InputMode::Normal;
+                let text = plain_equation(app.balanced_from(), eq, app.explicit_ones);
+                return Some(Action::Save {
+                    text,
+                    path: batch::expand_home(app.save_path.trim()),
+                    append: app.save_append,
+                });
+            }
+            _ => app.input_mode = InputMode::Normal,
+        },
+        Command::CloseSave => app.input_mode = InputMode::Normal,
+        Command::ShowLoad => app.input_mode = InputMode::Load,
+        Command::TypeLoadPath(c) => app.load_path.push(c),
+        Command::LoadPathBackspace => {
+            app.load_path.pop();
+        }
+        Command::LoadFile if app.load_path.trim().is_empty() => {
+            app.status = Some(Status::Warning("Type a file to load".to_string()));
+        }
+        Command::LoadFile => {
+            app.input_mode = InputMode::Normal;
+            app.load_file();
+        }
+        Command::CloseLoad => app.input_mode = InputMode::Normal,
+        Command::ToggleFavorite => app.toggle_favorite(),
+        Command::ShowFavorites if app.favorites.is_empty() => {
+            app.status = Some(Status::Warning(
+                "No favorites yet, * adds the equation".to_string(),
+            ));
+        }
+        Command::ShowFavorites => {
+            app.favorite_selected = app.favorite_selected.min(app.favorites.len() - 1);
+            app.input_mode = InputMode::Favorites;
+        }
+        Command::PrevFavorite => app.favorite_selected = app.favorite_selected.saturating_sub(1),
+        Command::NextFavorite => {
+            let last = app.favorites.len().saturating_sub(1);
+            app.favorite_selected = (app.favorite_selected + 1).min(last);
+        }
+        Command::PickFavorite => {
+            if let Some(favorite) = app.favorites.get(app.favorite_selected).cloned() {
+                app.set_input(&favorite);
+            }
+            app.input_mode = InputMode::Normal;
+        }
+        Command::RemoveFavorite => {
+            if app.favorite_selected < app.favorites.len() {
+                app.favorites.remove(app.favorite_selected);
+            }
+            if app.favorites.is_empty() {
+                app.input_mode = InputMode::Normal;
+            } else {
+                app.favorite_selected = app.favorite_selected.min(app.favorites.len() - 1);
+            }
+        }
+        Command::CloseFavorites => app.input_mode = InputMode::Normal,
+        Command::ShowSettings => app.input_mode = InputMode::Settings,
+        Command::PrevSetting => app.setting_selected = app.setting_selected.saturating_sub(1),
+        Command::NextSetting => {
+            app.setting_selected = (app.setting_selected + 1).min(Setting::ALL.len() - 1);
+        }
+        Command::ToggleSetting => {
+            Setting::ALL[app.setting_selected].toggle(app);
+            app.settings_changed = true;
+        }
+        Command::CloseSettings => app.input_mode = InputMode::Normal,
+        Command::ShowTranscript => app.input_mode = InputMode::Transcript,
+        Command::Transcript { format, to_file } => {
+            app.input_mode = InputMode::Normal;
+            let results = app.transcript();
+            if results.is_empty() {
+                app.status = Some(Status::Warning(
+                    "Nothing entered yet this session".to_string(),
+                ));
+                return None;
+            }
+            let text = format_results(format, &results);
+            return Some(match to_file {
+                true => Action::Export {
+                    report: text,
+                    path: PathBuf::from(transcript_path(format)),
+                },
+                false => Action::CopyTranscript { text, format },
+            });
+        }
+        Command::CancelTranscript => app.input_mode = InputMode::Normal,
+        Command::StepLoaded(_) if app.loaded.is_empty() => {
+            app.status = Some(Status::Warning(
+                "Nothing loaded, l loads equations from a file".to_string(),
+            ));
+        }
+        Command::StepLoaded(by) => {
+            let last = app.loaded.len() - 1;
+            let index = app.loaded_index.saturating_add_signed(by).min(last);
+            app.show_loaded(index);
+        }
+        Command::SaveSession => return Some(Action::SaveSession),
+        Command::OpenEditor => return Some(Action::OpenEditor),
+        Command::Suspend => return Some(Action::Suspend),
+        Command::Export => {
+            return Some(Action::Export {
+                report: report::markdown(&app.report_entries()),
+                path: PathBuf::from(report::DEFAULT_PATH),
+            })
+        }
+        Command::ShowTable => {
+            app.table_query.clear();
+            app.input_mode = InputMode::Table;
+        }
+        Command::MoveTable(rows, columns) => {
+            app.table_query.clear();
+            app.table_selected = table::step(app.table_selected, rows, columns);
+        }
+        Command::FindElement(c) => {
+            app.table_query.push(c);
+            app.find_element();
+        }
+        Command::FindBackspace => {
+            app.table_query.pop();
+            app.find_element();
+        }
+        Command::InsertElement => {
+            app.paste(table::element(app.table_selected).symbol());
+            app.input_mode = InputMode::Editing;
+            app.focus = Focus::Input;
+        }
+        Command::CloseTable => app.input_mode = InputMode::Normal,
+        Command::PickExample => {
+            if let Some(e) = examples::search(&app.example_query).get(app.example_selected) {
+                app.set_input(e.equation);
+                app.input_mode = InputMode::Normal;
+            }
+        }
+        Command::Insert(c) => app.insert(c),
+        Command::Backspace => app.backspace(),
+        Command::Delete => app.delete(),
+        Command::Undo => app.undo(),
+        Command::Redo => app.redo(),
+        Command::Left => {
+            app.undo.split();
+            app.move_cursor(-1);
+        }
+        Command::Right => {
+            app.undo.split();
+            app.move_cursor(1);
+        }
+        Command::Home => {
+            app.undo.split();
+            app.cursor = 0;
+        }
+        Command::End => {
+            app.undo.split();
+            app.cursor = app.input.chars().count();
+        }
+        Command::Older => {
+            if let Some(entry) = app.history.older(&app.input) {
+                let entry = entry.to_string();
+                app.set_input(&entry);
+            }
+        }
+        Command::Newer => {
+            if let Some(entry) = app.history.newer() {
+                let entry = entry.to_string();
+                app.set_input(&entry);
+            }
+        }
+    }
+    None
+}
+
+/// Apply text pasted into the terminal. It goes into the input, starting to
+/// edit it from normal mode, or into the search when picking an example
+pub fn handle_paste(app: &mut App, text: &str) {
+    match app.input_mode {
+        InputMode::Normal => {
+            app.input_mode = InputMode::Editing;
+            app.focus = Focus::Input;
+            app.paste(text);
+        }
+        InputMode::Editing => app.paste(text),
+        InputMode::Examples => {
+            app.example_query
+                .extend(text.chars().filter(|c| !c.is_control()));
+            app.example_selected = 0;
+        }
+        InputMode::Convert => app
+            .convert_query
+            .extend(text.chars().filter(|c| !c.is_control())),
+        InputMode::Table => {
+            app.table_query
+                .extend(text.chars().filter(|c| !c.is_control()));
+            app.find_element();
+        }
+        InputMode::Save => app
+            .save_path
+            .extend(text.chars().filter(|c| !c.is_control())),
+        InputMode::Load => app
+            .load_path
+            .extend(text.chars().filter(|c| !c.is_control())),
+        InputMode::Help
+        | InputMode::Copy
+        | InputMode::Stoich
+        | InputMode::Transcript
+        | InputMode::Favorites
+        | InputMode::Settings => {}
+    }
+}
+
+/// Where a transcript is saved, in the directory the tui was started in
+fn transcript_path(format: Format) -> &'static str {
+    match format {
+        Format::Plain => "transcript.txt",
+        Format::Json => "transcript.json",
+        Format::Markdown => "transcript.md",
+        Format::Csv => "transcript.csv",
+    }
+}
+
+/// Something a key press asks the app to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// Quit, unless it has to be confirmed
+    Quit,
+    /// Quit straight away
+    ForceQuit,
+    Redraw,
+    Edit,
+    StopEditing,
+    Complete,
+    FocusNext,
+    FocusPrev,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ToggleHelp,
+    NewTab,
+    CloseTab,
+    PrevTab,
+    NextTab,
+    GoToTab(usize),
+    ToggleSideBySide,
+    ToggleRedox,
+    Reverse,
+    ToggleOnes,
+    ToggleOxidation,
+    ToggleNetIonic,
+    ToggleFractional,
+    ShowKeys,
+    CloseKeys,
+    Copy,
+    CopyAs(CopyFormat),
+    CancelCopy,
+    ShowExamples,
+    SearchExamples(char),
+    SearchBackspace,
+    PrevExample,
+    NextExample,
+    PickExample,
+    CloseExamples,
+    /// Work out amounts from the balanced equation
+    ShowStoich,
+    ShowConvert,
+    TypeConversion(char),
+    ConversionBackspace,
+    CloseConvert,
+    /// Ask for a file to save the balanced equation to
+    ShowSave,
+    TypeSavePath(char),
+    SavePathBackspace,
+    /// Switch between adding to the file and replacing it
+    ToggleAppend,
+    SaveEquation,
+    CloseSave,
+    /// Ask for a file to load equations from
+    ShowLoad,
+    TypeLoadPath(char),
+    LoadPathBackspace,
+    LoadFile,
+    CloseLoad,
+    /// Move through the loaded equations by this many
+    StepLoaded(isize),
+    /// Star the input, or unstar it
+    ToggleFavorite,
+    ShowFavorites,
+    PrevFavorite,
+    NextFavorite,
+    PickFavorite,
+    RemoveFavorite,
+    CloseFavorites,
+    ShowSettings,
+    PrevSetting,
+    NextSetting,
+    /// Turn the chosen setting on or off
+    ToggleSetting,
+    CloseSettings,
+    /// Ask how to copy or save the transcript
+    ShowTranscript,
+    /// Copy the equations entered this session, or save them to a file
+    Transcript {
+        format: Format,
+        to_file: bool,
+    },
+    CancelTranscript,
+    SaveSession,
+    /// Write a report of every tab's equation
+    Export,
+    /// Edit the input in `$VISUAL` or `$EDITOR`
+    OpenEditor,
+    Suspend,
+    ShowTable,
+    /// Move the highlight in the periodic table by rows and columns
+    MoveTable(isize, isize),
+    FindElement(char),
+    FindBackspace,
+    InsertElement,
+    CloseTable,
+    CloseStoich,
+    PrevCompound,
+    NextCompound,
+    TypeAmount(char),
+    AmountBackspace,
+    ToggleUnit,
+    ToggleQuiz,
+    Submit,
+    RevealAnswer,
+    /// Edit the balanced equation in place of the input
+    EditBalanced,
+    AcceptSuggestion,
+    Insert(char),
+    Backspace,
+    Delete,
+    Undo,
+    Redo,
+    Left,
+    Right,
+    Home,
+    End,
+    Older,
+    Newer,
+}
+
+/// Turn a key event into a command for the current mode. In normal mode the
+/// arrows and enter go to the focused pane
+fn translate(mode: &InputMode, focus: Focus, key: KeyEvent) -> Option<Command> {
+    match key.kind {
+        // windows and the kitty protocol report releases too, which would double every key
+        KeyEventKind::Release => return None,
+        // holding a key should only repeat typing, not mode changes or copying
+        KeyEventKind::Repeat if !matches!(mode, InputMode::Editing) => return None,
+        KeyEventKind::Press | KeyEventKind::Repeat => {}
+    }
+
+    match (mode, key.code) {
+        (_, KeyCode::Char('c')) if key.modifiers == KeyModifiers::CONTROL => {
+            Some(Command::ForceQuit)
+        }
+        (_, KeyCode::Char('l')) if key.modifiers == KeyModifiers::CONTROL => Some(Command::Redraw),
+        // before i and e, which edit whatever the modifiers
+        (InputMode::Normal | InputMode::Editing, KeyCode::Char('e'))
+            if key.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(Command::Export)
+        }
+        (InputMode::Normal, KeyCode::Char('q') | KeyCode::Esc) => Some(Command::Quit),
+        (InputMode::Normal, KeyCode::Char('i' | 'e')) => Some(Command::Edit),
+        (InputMode::Normal, KeyCode::Char('y')) => Some(Command::Copy),
+        (InputMode::Normal, KeyCode::Char('Y')) => Some(Command::CopyAs(CopyFormat::Input)),
+        (InputMode::Normal, KeyCode::Char('L')) => Some(Command::CopyAs(CopyFormat::Latex)),
+        (InputMode::Normal, KeyCode::Char('U')) => Some(Command::CopyAs(CopyFormat::Unicode)),
+        (InputMode::Normal, KeyCode::Char('r')) => Some(Command::EditBalanced),
+        (InputMode::Normal, KeyCode::Char('E')) => Some(Command::ShowExamples),
+        (InputMode::Normal, KeyCode::Char('Q')) => Some(Command::ToggleQuiz),
+        (InputMode::Normal | InputMode::Editing, KeyCode::Char('a'))
+            if key.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(Command::RevealAnswer)
+        }
+        (InputMode::Normal, KeyCode::Char('a')) => Some(Command::AcceptSuggestion),
+        (InputMode::Normal, KeyCode::Char('?')) => Some(Command::ToggleHelp),
+        (InputMode::Normal | InputMode::Editing, KeyCode::Char('t'))
+            if key.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(Command::NewTab)
+        }
+        (InputMode::Normal | InputMode::Editing, KeyCode::Char('w'))
+            if key.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(Command::CloseTab)
+        }
+        (InputMode::Normal | InputMode::Editing, KeyCode::Char('s'))
+            if key.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(Command::SaveSession)
+        }
+        (InputMode::Normal | InputMode::Editing, KeyCode::Char('o'))
+            if key.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(Command::OpenEditor)
+        }
+        // only unix has job control, ctrl-z is left alone elsewhere
+        #[cfg(unix)]
+        (InputMode::Normal, KeyCode::Char('z')) if key.modifiers == KeyModifiers::CONTROL => {
+            Some(Command::Suspend)
+        }
+        (InputMode::Normal, KeyCode::Char('n')) => Some(Command::NewTab),
+        (InputMode::Normal, KeyCode::Char('x')) => Some(Command::CloseTab),
+        (InputMode::Normal, KeyCode::Char('[')) => Some(Command::PrevTab),
+        (InputMode::Normal, KeyCode::Char(']')) => Some(Command::NextTab),
+        (InputMode::Normal, KeyCode::Char(c @ '1'..='9')) => {
+            Some(Command::GoToTab(c as usize - '1' as usize))
+        }
+        (InputMode::Normal, KeyCode::Char('o')) => Some(Command::ToggleOnes),
+        (InputMode::Normal, KeyCode::Char('O')) => Some(Command::ToggleOxidation),
+        (InputMode::Normal, KeyCode::Char('I')) => Some(Command::ToggleNetIonic),
+        (InputMode::Normal, KeyCode::Char('F')) => Some(Command::ToggleFractional),
+        (InputMode::Normal, KeyCode::Char('v')) => Some(Command::ToggleSideBySide),
+        (InputMode::Normal, KeyCode::Char('R')) => Some(Command::ToggleRedox),
+        (InputMode::Normal, KeyCode::Char('S')) => Some(Command::Reverse),
+        (InputMode::Normal, KeyCode::Char('s')) => Some(Command::ShowStoich),
+        (InputMode::Normal, KeyCode::Char('c')) => Some(Command::ShowConvert),
+        (InputMode::Normal, KeyCode::Char('p')) => Some(Command::ShowTable),
+        (InputMode::Normal, KeyCode::Char('w')) => Some(Command::ShowSave),
+        (InputMode::Normal, KeyCode::Char('l')) => Some(Command::ShowLoad),
+        (InputMode::Normal, KeyCode::Char('T')) => Some(Command::ShowTranscript),
+        (InputMode::Normal, KeyCode::Char('*')) => Some(Command::ToggleFavorite),
+        (InputMode::Normal, KeyCode::Char('f')) => Some(Command::ShowFavorites),
+        (InputMode::Normal, KeyCode::Char(',')) => Some(Command::ShowSettings),
+        (InputMode::Normal, KeyCode::Char('<')) => Some(Command::StepLoaded(-1)),
+        (InputMode::Normal, KeyCode::Char('>')) => Some(Command::StepLoaded(1)),
+        (InputMode::Normal, KeyCode::Char('h') | KeyCode::F(1)) => Some(Command::ShowKeys),
+        (InputMode::Normal, KeyCode::Tab) => Some(Command::FocusNext),
+        (InputMode::Normal, KeyCode::BackTab) => Some(Command::FocusPrev),
+        (InputMode::Normal, code) => match (focus, code) {
+            (Focus::Input, KeyCode::Enter) => Some(Command::Edit),
+            (Focus::Input, KeyCode::Up) => Some(Command::Older),
+            (Focus::Input, KeyCode::Down) => Some(Command::Newer),
+            (Focus::Output, KeyCode::Enter) => Some(Command::Copy),
+            (Focus::Output, KeyCode::Up) => Some(Command::ScrollUp),
+            (Focus::Output, KeyCode::Down) => Some(Command::ScrollDown),
+            (Focus::Output, KeyCode::PageUp) => Some(Command::PageUp),
+            (Focus::Output, KeyCode::PageDown) => Some(Command::PageDown),
+            _ => None,
+        },
+        (InputMode::Help, _) => Some(Command::CloseKeys),
+        (InputMode::Copy, KeyCode::Char('p')) => Some(Command::CopyAs(CopyFormat::Plain)),
+        (InputMode::Copy, KeyCode::Char('l')) => Some(Command::CopyAs(CopyFormat::Latex)),
+        (InputMode::Copy, KeyCode::Char('u')) => Some(Command::CopyAs(CopyFormat::Unicode)),
+        (InputMode::Copy, KeyCode::Char('j')) => Some(Command::CopyAs(CopyFormat::Json)),
+        (InputMode::Copy, KeyCode::Esc) => Some(Command::CancelCopy),
+        (InputMode::Examples, KeyCode::Esc) => Some(Command::CloseExamples),
+        (InputMode::Examples, KeyCode::Enter) => Some(Command::PickExample),
+        (InputMode::Examples, KeyCode::Up) => Some(Command::PrevExample),
+        (InputMode::Examples, KeyCode::Down) => Some(Command::NextExample),
+        (InputMode::Examples, KeyCode::Backspace) => Some(Command::SearchBackspace),
+        (InputMode::Examples, KeyCode::Char(c)) => Some(Command::SearchExamples(c)),
+        (InputMode::Convert, KeyCode::Esc | KeyCode::Enter) => Some(Command::CloseConvert),
+        (InputMode::Convert, KeyCode::Backspace) => Some(Command::ConversionBackspace),
+        (InputMode::Convert, KeyCode::Char(c)) => Some(Command::TypeConversion(c)),
+        (InputMode::Settings, KeyCode::Esc) => Some(Command::CloseSettings),
+        (InputMode::Settings, KeyCode::Up) => Some(Command::PrevSetting),
+        (InputMode::Settings, KeyCode::Down) => Some(Command::NextSetting),
+        (InputMode::Settings, KeyCode::Enter | KeyCode::Char(' ')) => Some(Command::ToggleSetting),
+        (InputMode::Favorites, KeyCode::Esc) => Some(Command::CloseFavorites),
+        (InputMode::Favorites, KeyCode::Enter) => Some(Command::PickFavorite),
+        (InputMode::Favorites, KeyCode::Up) => Some(Command::PrevFavorite),
+        (InputMode::Favorites, KeyCode::Down) => Some(Command::NextFavorite),
+        (InputMode::Favorites, KeyCode::Delete | KeyCode::Backspace) => {
+            Some(Command::RemoveFavorite)
+        }
+        (InputMode::Transcript, KeyCode::Esc) => Some(Command::CancelTranscript),
+        (InputMode::Transcript, KeyCode::Char(c)) => {
+            let format = match c.to_ascii_lowercase() {
+                'p' => Format::Plain,
+                'm' => Format::Markdown,
+                'c' => Format::Csv,
+                _ => return None,
+            };
+            Some(Command::Transcript {
+                format,
+                to_file: c.is_ascii_uppercase(),
+            })
+        }
+        (InputMode::Load, KeyCode::Esc) => Some(Command::CloseLoad),
+        (InputMode::Load, KeyCode::Enter) => Some(Command::LoadFile),
+        (InputMode::Load, KeyCode::Backspace) => Some(Command::LoadPathBackspace),
+        (InputMode::Load, KeyCode::Char(c)) => Some(Command::TypeLoadPath(c)),
+        (InputMode::Save, KeyCode::Esc) => Some(Command::CloseSave),
+        (InputMode::Save, KeyCode::Enter) => Some(Command::SaveEquation),
+        (InputMode::Save, KeyCode::Tab) => Some(Command::ToggleAppend),
+        (InputMode::Save, KeyCode::Backspace) => Some(Command::SavePathBackspace),
+        (InputMode::Save, KeyCode::Char(c)) => Some(Command::TypeSavePath(c)),
+        (InputMode::Table, KeyCode::Esc) => Some(Command::CloseTable),
+        (InputMode::Table, KeyCode::Enter) => Some(Command::InsertElement),
+        (InputMode::Table, KeyCode::Up) => Some(Command::MoveTable(-1, 0)),
+        (InputMode::Table, KeyCode::Down) => Some(Command::MoveTable(1, 0)),
+        (InputMode::Table, KeyCode::Left) => Some(Command::MoveTable(0, -1)),
+        (InputMode::Table, KeyCode::Right) => Some(Command::MoveTable(0, 1)),
+        (InputMode::Table, KeyCode::Backspace) => Some(Command::FindBackspace),
+        (InputMode::Table, KeyCode::Char(c)) => Some(Command::FindElement(c)),
+        (InputMode::Stoich, KeyCode::Esc) => Some(Command::CloseStoich),
+        (InputMode::Stoich, KeyCode::Up) => Some(Command::PrevCompound),
+        (InputMode::Stoich, KeyCode::Down) => Some(Command::NextCompound),
+        (InputMode::Stoich, KeyCode::Tab) => Some(Command::ToggleUnit),
+        (InputMode::Stoich, KeyCode::Backspace) => Some(Command::AmountBackspace),
+        (InputMode::Stoich, KeyCode::Char(c @ ('0'..='9' | '.'))) => Some(Command::TypeAmount(c)),
+        (InputMode::Editing, KeyCode::Esc) => Some(Command::StopEditing),
+        (InputMode::Editing, KeyCode::Enter) => Some(Command::Submit),
+        (InputMode::Editing, KeyCode::Tab) => Some(Command::Complete),
+        (InputMode::Editing, KeyCode::Char('[')) if key.modifiers == KeyModifiers::CONTROL => {
+            Some(Command::StopEditing)
+        }
+        (InputMode::Editing, KeyCode::Char('z')) if key.modifiers == KeyModifiers::CONTROL => {
+            Some(Command::Undo)
+        }
+        (InputMode::Editing, KeyCode::Char('y' | 'r'))
+            if key.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(Command::Redo)
+        }
+        (InputMode::Editing, KeyCode::Char(c)) => Some(Command::Insert(c)),
+        (InputMode::Editing, KeyCode::Backspace) => Some(Command::Backspace),
+        (InputMode::Editing, KeyCode::Delete) => Some(Command::Delete),
+        (InputMode::Editing, KeyCode::Left) => Some(Command::Left),
+        (InputMode::Editing, KeyCode::Right) => Some(Command::Right),
+        (InputMode::Editing, KeyCode::Home) => Some(Command::Home),
+        (InputMode::Editing, KeyCode::End) => Some(Command::End),
+        (InputMode::Editing, KeyCode::Up) => Some(Command::Older),
+        (InputMode::Editing, KeyCode::Down) => Some(Command::Newer),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEventState;
+
+    use super::*;
+
+    fn key(code: KeyCode, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind,
+            state: KeyEventState::NONE,
+        }
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Action> {
+        handle_key(app, key(code, KeyEventKind::Press))
+    }
+
+    /// Type text into a new app in editing mode
+    fn typed(text: &str) -> App {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('i'));
+        for c in text.chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        app.update_eq();
+        app
+    }
+
+    #[test]
+    fn ignore_release() {
+        let release = key(KeyCode::Char('a'), KeyEventKind::Release);
+        assert_eq!(translate(&InputMode::Editing, Focus::Input, release), None);
+        assert_eq!(translate(&InputMode::Normal, Focus::Input, release), None);
+
+        let press = key(KeyCode::Char('a'), KeyEventKind::Press);
+        assert_eq!(
+            translate(&InputMode::Editing, Focus::Input, press),
+            Some(Command::Insert('a'))
+        );
+    }
+
+    #[test]
+    fn repeat_only_edits() {
+        let backspace = key(KeyCode::Backspace, KeyEventKind::Repeat);
+        assert_eq!(
+            translate(&InputMode::Editing, Focus::Input, backspace),
+            Some(Command::Backspace)
+        );
+
+        let copy = key(KeyCode::Char('y'), KeyEventKind::Repeat);
+        assert_eq!(translate(&InputMode::Normal, Focus::Input, copy), None);
+    }
+
+    #[test]
+    fn ctrl_c_quits() {
+        let mut ctrl_c = key(KeyCode::Char('c'), KeyEventKind::Press);
+        ctrl_c.modifiers = KeyModifiers::CONTROL;
+        let mut app = App::default();
+        assert_eq!(handle_key(&mut app, ctrl_c), Some(Action::Quit));
+        app.input_mode = InputMode::Editing;
+        assert_eq!(handle_key(&mut app, ctrl_c), Some(Action::Quit));
+    }
+
+    #[test]
+    fn focus() {
+        let mut app = App::default();
+        assert_eq!(app.focus, Focus::Input);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.focus, Focus::Output);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.focus, Focus::Input);
+        press(&mut app, KeyCode::BackTab);
+        assert_eq!(app.focus, Focus::Output);
+
+        // tab fills in the grey formula while editing, the focus stays
+        app.input_mode = InputMode::Editing;
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.focus, Focus::Output);
+    }
+
+    #[test]
+    fn complete() {
+        let mut app = typed("Zn + HC");
+        assert_eq!(app.completion(), Some("l"));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input, "Zn + HCl");
+        assert_eq!(app.cursor, 8);
+        assert_eq!(app.completion(), None);
+
+        // not in the middle of a formula
+        let mut app = typed("NaH + HN");
+        app.cursor = 2;
+        assert_eq!(app.completion(), None);
+        app.cursor = 3;
+        assert_eq!(app.completion(), Some("CO3"));
+
+        // esc hides it and stays editing, then leaves as usual
+        app.cursor = 8;
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.input_mode, InputMode::Editing));
+        assert_eq!(app.completion(), None);
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input, "NaH + HN");
+        // until more is typed
+        press(&mut app, KeyCode::Char('O'));
+        assert_eq!(app.completion(), Some("3"));
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn keys_go_to_focused_pane() {
+        let mut app = App::default();
+        app.history.push("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Editing));
+
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Copy));
+    }
+
+    #[test]
+    fn scroll_output() {
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Tab);
+        app.output_view.set(OutputView {
+            lines: 10,
+            height: 3,
+        });
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.output_scroll, 1);
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.output_scroll, 4);
+        press(&mut app, KeyCode::PageDown);
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.output_scroll, 7);
+        press(&mut app, KeyCode::PageUp);
+        assert_eq!(app.output_scroll, 4);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.output_scroll, 3);
+
+        // after a resize shows more, scrolling moves from where the output is drawn
+        app.output_view.set(OutputView {
+            lines: 10,
+            height: 9,
+        });
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.output_scroll, 0);
+
+        // a new equation starts at the top
+        app.set_input("Fe + O2 -> Fe2O3");
+        assert_eq!(app.output_scroll, 0);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.output_scroll, 0);
+    }
+
+    #[test]
+    fn toggle_ones() {
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('o'));
+        assert!(app.explicit_ones);
+        assert!(matches!(&app.status, Some(Status::Info(s)) if s == "Showing coefficients of 1"));
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(
+            press(&mut app, KeyCode::Char('u')),
+            Some(Action::Copy {
+                text: "2H₂ + 1O₂ → 2H₂O".to_string(),
+                format: CopyFormat::Unicode
+            })
+        );
+        press(&mut app, KeyCode::Char('o'));
+        assert!(!app.explicit_ones);
+    }
+
+    #[test]
+    fn toggle_fractional() {
+        let mut app = typed("C2H6 + O2 -> CO2 + H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('F'));
+        assert!(app.fractional);
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(
+            press(&mut app, KeyCode::Char('l')),
+            Some(Action::Copy {
+                text: r"\mathrm{C_{2}H_{6}} + \tfrac{7}{2}\mathrm{O_{2}} \rightarrow 2\mathrm{CO_{2}} + 3\mathrm{H_{2}O}".to_string(),
+                format: CopyFormat::Latex
+            })
+        );
+        press(&mut app, KeyCode::Char('F'));
+        assert!(!app.fractional);
+    }
+
+    #[test]
+    fn toggle_net_ionic() {
+        let mut app = typed("HCl + NaOH -> NaCl + H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('I'));
+        assert!(app.show_net_ionic);
+        assert!(
+            matches!(&app.status, Some(Status::Info(s)) if s == "Showing the net ionic equation")
+        );
+        press(&mut app, KeyCode::Char('I'));
+        assert!(!app.show_net_ionic);
+    }
+
+    #[test]
+    fn click() {
+        let mut app = App::default();
+        app.set_input("Fe + O2 -> Fe2O3");
+        app.panes.set(Panes {
+            input: Rect::new(1, 2, 78, 3),
+            output: Rect::new(1, 5, 78, 3),
+        });
+        // on the `O` of `O2`, after the border and the leading space
+        app.click(1 + 2 + 5, 3);
+        assert!(matches!(app.input_mode, InputMode::Editing));
+        assert_eq!(app.cursor, 5);
+        // past the end of the text
+        app.click(60, 3);
+        assert_eq!(app.cursor, 16);
+        // on the border's leading space
+        app.click(2, 3);
+        assert_eq!(app.cursor, 0);
+
+        app.click(10, 6);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.focus, Focus::Output);
+        assert_eq!(app.history.entries().last().unwrap(), "Fe + O2 -> Fe2O3");
+
+        // outside every pane
+        app.click(10, 20);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.focus, Focus::Output);
+    }
+
+    #[test]
+    fn click_scrolled_input() {
+        let mut app = App::default();
+        app.set_input(&"CH4 + O2 -> CO2 + H2O ".repeat(5));
+        app.panes.set(Panes {
+            input: Rect::new(1, 2, 20, 3),
+            output: Rect::new(1, 5, 20, 3),
+        });
+        // the cursor is at the end, so the input is scrolled
+        let scroll = usize::from(app.input_scroll(18));
+        assert!(scroll > 0);
+        app.click(3, 3);
+        assert_eq!(app.cursor, scroll);
+    }
+
+    #[test]
+    fn tabs() {
+        let mut app = typed("Fe + O2 -> Fe2O3");
+        app.insert('x');
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.tabs.len(), 2);
+        assert_eq!(app.active_tab, 1);
+        assert!(app.input.is_empty() && app.output.is_none());
+
+        for c in "H2 + O2 -> H2O".chars() {
+            app.insert(c);
+        }
+        app.update_eq();
+        press(&mut app, KeyCode::Char('['));
+        // the first tab is as it was left, including its undo history
+        assert_eq!(app.input, "Fe + O2 -> Fe2O3x");
+        assert_eq!(app.cursor, 17);
+        app.undo();
+        assert_eq!(app.input, "Fe + O2 -> ");
+        app.redo();
+
+        press(&mut app, KeyCode::Char('2'));
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "2H2 + O2 -> 2H2O"
+        );
+        // past the last tab
+        press(&mut app, KeyCode::Char(']'));
+        press(&mut app, KeyCode::Char('9'));
+        assert_eq!(app.active_tab, 1);
+
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.tabs.len(), 1);
+        assert_eq!(app.input, "Fe + O2 -> Fe2O3x");
+        // the last tab is cleared rather than closed
+        ctrl(&mut app, 'w');
+        assert_eq!(app.tabs.len(), 1);
+        assert!(app.input.is_empty());
+    }
+
+    #[test]
+    fn session() {
+        let mut app = typed("Fe + O2 -> Fe2O3");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('n'));
+        app.set_input("H2 + O2 -> H2O");
+        app.cursor = 2;
+        press(&mut app, KeyCode::Char('['));
+        press(&mut app, KeyCode::Char('O'));
+        assert_eq!(ctrl(&mut app, 's'), Some(Action::SaveSession));
+        let session = app.session();
+        assert_eq!(session.tabs[1].input, "H2 + O2 -> H2O");
+        assert_eq!(session.tabs[1].cursor, 2);
+
+        let mut restored = App::default();
+        restored.restore_session(session);
+        assert_eq!(restored.tabs.len(), 2);
+        assert_eq!(restored.active_tab, 0);
+        assert!(restored.show_oxidation);
+        assert_eq!(restored.history.entries(), ["Fe + O2 -> Fe2O3"]);
+        assert_eq!(
+            restored
+                .output
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .equation(),
+            "4Fe + 3O2 -> 2Fe2O3"
+        );
+        // the other tab is balanced when it's switched to
+        restored.switch_tab(1);
+        assert_eq!(restored.cursor, 2);
+        assert_eq!(
+            restored
+                .output
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .equation(),
+            "2H2 + O2 -> 2H2O"
+        );
+    }
+
+    #[test]
+    fn export() {
+        let mut app = typed("Fe + O2 -> Fe2O3");
+        // typing ctrl-e while editing still exports
+        app.insert('x');
+        app.new_tab();
+        app.set_input("H2 + O2 -> H2O");
+        let Some(Action::Export { report, path }) = ctrl(&mut app, 'e') else {
+            panic!("ctrl-e doesn't export");
+        };
+        assert_eq!(path, PathBuf::from(report::DEFAULT_PATH));
+        assert!(report.contains("## 1. Fe + O2 -> Fe2O3x\n\nCouldn't balance it"));
+        assert!(report.contains("## 2. H2 + O2 -> H2O\n\nBalanced: `2H2 + O2 -> 2H2O`"));
+    }
+
+    #[test]
+    fn new_tab_while_editing() {
+        let mut app = typed("Fe");
+        ctrl(&mut app, 't');
+        assert_eq!(app.tabs.len(), 2);
+        assert!(matches!(app.input_mode, InputMode::Editing));
+        // the number keys are typed
+        press(&mut app, KeyCode::Char('1'));
+        assert_eq!(app.input, "1");
+    }
+
+    #[test]
+    fn switch_while_balancing() {
+        let mut app = App {
+            worker: Some(Worker::spawn()),
+            ..Default::default()
+        };
+        app.set_input("Fe + O2 -> Fe2O3");
+        app.balancing = true;
+        app.new_tab();
+        assert!(!app.balancing);
+        // balanced again when switched back to
+        app.switch_tab(0);
+        while app.balancing {
+            app.on_tick();
+        }
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "4Fe + 3O2 -> 2Fe2O3"
+        );
+    }
+
+    #[test]
+    fn common_factor() {
+        let mut app = typed("4H2 + 2O2 -> 4H2O");
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "2H2 + O2 -> 2H2O"
+        );
+        assert_eq!(app.common_factor, 2);
+
+        app.set_input("Fe + O2 -> Fe2O3");
+        assert_eq!(app.common_factor, 1);
+    }
+
+    #[test]
+    fn type_and_balance() {
+        let mut app = typed("H2 + O2 -> H2O");
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+        assert!(!app.edited);
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "2H2 + O2 -> 2H2O"
+        );
+
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(press(&mut app, KeyCode::Char('y')), None);
+        assert!(matches!(app.input_mode, InputMode::Copy));
+        assert_eq!(
+            press(&mut app, KeyCode::Char('p')),
+            Some(Action::Copy {
+                text: "2H2 + O2 -> 2H2O".to_string(),
+                format: CopyFormat::Plain
+            })
+        );
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(press(&mut app, KeyCode::Char('q')), None);
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Some(Action::Quit));
+    }
+
+    #[test]
+    fn edits_mark_app() {
+        let mut app = typed("Fe + O2 -> Fe2O3");
+        press(&mut app, KeyCode::Left);
+        assert!(!app.edited);
+        press(&mut app, KeyCode::Backspace);
+        assert!(app.edited);
+        app.update_eq();
+        assert_eq!(app.input, "Fe + O2 -> Fe23");
+        assert!(matches!(app.output, Some(Err(_))));
+    }
+
+    #[test]
+    fn nothing_to_copy() {
+        let mut app = typed("Fe + S8 -> Fe2O3");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(press(&mut app, KeyCode::Char('y')), None);
+        assert!(matches!(&app.status, Some(Status::Warning(s)) if s == "Nothing to copy"));
+    }
+
+    #[test]
+    fn clear_input() {
+        let mut app = typed("H2");
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        app.update_eq();
+        assert!(app.input.is_empty());
+        assert!(app.output.is_none());
+    }
+
+    #[test]
+    fn toggle_help() {
+        let mut app = App::default();
+        assert!(app.show_help);
+        press(&mut app, KeyCode::Char('?'));
+        assert!(!app.show_help);
+        press(&mut app, KeyCode::Char('?'));
+        assert!(app.show_help);
+
+        // typed as normal text while editing
+        let app = typed("?");
+        assert!(app.show_help);
+        assert_eq!(app.input, "?");
+    }
+
+    #[test]
+    fn help_screen() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::F(1));
+        assert!(matches!(app.input_mode, InputMode::Help));
+        // any key closes it without doing anything else
+        assert_eq!(press(&mut app, KeyCode::Char('q')), None);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+
+        press(&mut app, KeyCode::Char('h'));
+        assert!(matches!(app.input_mode, InputMode::Help));
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn prefilled() {
+        let mut app = App::default();
+        app.set_input("H₂ + O2 -> H2O");
+        assert_eq!(app.cursor, 14);
+        assert!(!app.edited);
+        // subscripts are read as numbers
+        assert!(matches!(app.output, Some(Ok(_))));
+
+        app.set_input("H2 + O2 => H2O");
+        assert!(matches!(app.output, Some(Err(_))));
+        app.set_input("H2 + O2 -> H2O");
+        assert!(matches!(app.output, Some(Ok(_))));
+    }
+
+    #[test]
+    fn recall_history() {
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('i'));
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.history.entries(), ["H2 + O2 -> H2O", "2H2 + O2 -> H2O"]);
+
+        press(&mut app, KeyCode::Char('i'));
+        app.set_input("Fe");
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+        assert_eq!(app.cursor, 14);
+        assert!(matches!(app.output, Some(Ok(_))));
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.input, "Fe");
+    }
+
+    #[test]
+    fn balance_in_background() {
+        let mut app = App {
+            worker: Some(Worker::spawn()),
+            ..Default::default()
+        };
+        app.set_input("H2 + O2 -> H2O");
+        while app.balancing {
+            app.on_tick();
+        }
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "2H2 + O2 -> 2H2O"
+        );
+        assert!(!app.on_tick());
+    }
+
+    fn ctrl(app: &mut App, c: char) -> Option<Action> {
+        let mut key = key(KeyCode::Char(c), KeyEventKind::Press);
+        key.modifiers = KeyModifiers::CONTROL;
+        handle_key(app, key)
+    }
+
+    #[test]
+    fn open_editor() {
+        let mut app = typed("H2 + O2");
+        assert_eq!(ctrl(&mut app, 'o'), Some(Action::OpenEditor));
+        // not typed into the input
+        assert_eq!(app.input, "H2 + O2");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(ctrl(&mut app, 'o'), Some(Action::OpenEditor));
+        // ones are still toggled without ctrl
+        press(&mut app, KeyCode::Char('o'));
+        assert!(app.explicit_ones);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn suspend() {
+        let mut app = typed("H2 + O2");
+        // still undo while editing
+        assert_eq!(ctrl(&mut app, 'z'), None);
+        assert_ne!(app.input, "H2 + O2");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(ctrl(&mut app, 'z'), Some(Action::Suspend));
+    }
+
+    #[test]
+    fn undo_words() {
+        let mut app = typed("Fe + O2");
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "Fe + ");
+        assert!(app.edited);
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "Fe ");
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "");
+        assert_eq!(app.cursor, 0);
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "");
+
+        ctrl(&mut app, 'y');
+        assert_eq!(app.input, "Fe ");
+        ctrl(&mut app, 'r');
+        assert_eq!(app.input, "Fe + ");
+        app.update_eq();
+
+        // a new edit drops what could be redone
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        ctrl(&mut app, 'y');
+        assert_eq!(app.input, "Fe ");
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "Fe + ");
+        assert_eq!(app.cursor, 5);
+    }
+
+    #[test]
+    fn undo_after_moving() {
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Char('2'));
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "2H2 + O2 -> H2O");
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+        app.update_eq();
+        assert!(matches!(app.output, Some(Ok(_))));
+    }
+
+    #[test]
+    fn max_input() {
+        let mut app = App {
+            max_input: 5,
+            ..Default::default()
+        };
+        app.set_input("H2 + O2 -> H2O");
+        assert_eq!(app.input, "H2 + ");
+        assert_eq!(app.cursor, 5);
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+
+        app.status = None;
+        app.input_mode = InputMode::Editing;
+        press(&mut app, KeyCode::Char('O'));
+        assert_eq!(app.input, "H2 + ");
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+
+        // inputs that got past the limit aren't balanced
+        app.input = "H2 + O2 -> H2O".to_string();
+        app.update_eq();
+        assert!(matches!(app.output, Some(Err(Error::TooLong(5)))));
+    }
+
+    #[test]
+    fn paste_at_cursor() {
+        let mut app = typed("H2 + -> H2O");
+        app.cursor = 5;
+        handle_paste(&mut app, "O2 \n");
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+        assert_eq!(app.cursor, 8);
+        // one paste is one undo
+        press(&mut app, KeyCode::Char(' '));
+        app.undo();
+        app.undo();
+        assert_eq!(app.input, "H2 + -> H2O");
+
+        let mut app = App {
+            max_input: 8,
+            input_mode: InputMode::Editing,
+            ..Default::default()
+        };
+        handle_paste(&mut app, "H2 + O2 -> H2O");
+        assert_eq!(app.input, "H2 + O2 ");
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+
+        // the search gets it while picking an example
+        let mut app = App {
+            input_mode: InputMode::Examples,
+            ..Default::default()
+        };
+        handle_paste(&mut app, "rust\n");
+        assert_eq!(app.example_query, "rust");
+        assert!(app.input.is_empty());
+    }
+
+    #[test]
+    fn confirm_quit() {
+        let mut app = App::default();
+        app.set_input("H2");
+        assert_eq!(press(&mut app, KeyCode::Char('q')), None);
+        assert!(app.confirming_quit.is_some());
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+        // releasing the key doesn't cancel
+        let release = key(KeyCode::Char('q'), KeyEventKind::Release);
+        assert_eq!(handle_key(&mut app, release), None);
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Some(Action::Quit));
+
+        // anything else cancels
+        let mut app = App::default();
+        app.set_input("H2");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(press(&mut app, KeyCode::Char('z')), None);
+        assert!(app.confirming_quit.is_none());
+        assert!(app.status.is_none());
+        assert_eq!(press(&mut app, KeyCode::Char('q')), None);
+
+        // so does waiting too long
+        app.confirming_quit = Some(Instant::now() - QUIT_TIMEOUT);
+        assert!(app.on_tick());
+        assert!(app.confirming_quit.is_none());
+        assert!(app.status.is_none());
+        assert_eq!(press(&mut app, KeyCode::Char('q')), None);
+
+        // quit straight away without an equation, or when turned off
+        assert_eq!(
+            press(&mut App::default(), KeyCode::Char('q')),
+            Some(Action::Quit)
+        );
+        let mut app = App {
+            confirm_quit: false,
+            ..Default::default()
+        };
+        app.set_input("H2");
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Some(Action::Quit));
+
+        // ctrl-c never asks
+        let mut ctrl_c = key(KeyCode::Char('c'), KeyEventKind::Press);
+        ctrl_c.modifiers = KeyModifiers::CONTROL;
+        assert_eq!(handle_key(&mut App::default(), ctrl_c), Some(Action::Quit));
+    }
+
+    #[test]
+    fn copy_menu() {
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('y'));
+        // other keys do nothing
+        assert_eq!(press(&mut app, KeyCode::Char('q')), None);
+        assert_eq!(
+            press(&mut app, KeyCode::Char('u')),
+            Some(Action::Copy {
+                text: "2H₂ + O₂ → 2H₂O".to_string(),
+                format: CopyFormat::Unicode
+            })
+        );
+
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(press(&mut app, KeyCode::Esc), None);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn copy_keys() {
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(
+            press(&mut app, KeyCode::Char('Y')),
+            Some(Action::Copy {
+                text: "H2 + O2 -> H2O".to_string(),
+                format: CopyFormat::Input
+            })
+        );
+        assert_eq!(
+            press(&mut app, KeyCode::Char('L')),
+            Some(Action::Copy {
+                text: r"2\mathrm{H_{2}} + \mathrm{O_{2}} \rightarrow 2\mathrm{H_{2}O}".to_string(),
+                format: CopyFormat::Latex
+            })
+        );
+        assert_eq!(
+            press(&mut app, KeyCode::Char('U')),
+            Some(Action::Copy {
+                text: "2H₂ + O₂ → 2H₂O".to_string(),
+                format: CopyFormat::Unicode
+            })
+        );
+    }
+
+    #[test]
+    fn copy_without_output() {
+        let mut app = App::default();
+        for c in ['y', 'Y', 'L', 'U'] {
+            app.status = None;
+            assert_eq!(press(&mut app, KeyCode::Char(c)), None);
+            assert!(matches!(&app.status, Some(Status::Warning(s)) if s == "Nothing to copy"));
+            assert!(matches!(app.input_mode, InputMode::Normal));
+        }
+
+        // the input can still be copied when it doesn't balance
+        app.set_input("H2 + O2 -> ");
+        assert_eq!(press(&mut app, KeyCode::Char('U')), None);
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('Y')),
+            Some(Action::Copy { .. })
+        ));
+    }
+
+    #[test]
+    fn edit_balanced() {
+        let mut app = typed("Fe + O2 -> Fe2O3");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.input, "4Fe + 3O2 -> 2Fe2O3");
+        assert_eq!(app.cursor, 19);
+        assert!(matches!(app.input_mode, InputMode::Editing));
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "4Fe + 3O2 -> 2Fe2O3"
+        );
+        // it can be undone
+        ctrl(&mut app, 'z');
+        assert_eq!(app.input, "Fe + O2 -> Fe2O3");
+
+        let mut app = typed("Fe + S8 -> Fe2O3");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.input, "Fe + S8 -> Fe2O3");
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert!(matches!(&app.status, Some(Status::Warning(s)) if s == "Nothing balanced to edit"));
+    }
+
+    #[test]
+    fn redraw() {
+        let mut ctrl_l = key(KeyCode::Char('l'), KeyEventKind::Press);
+        ctrl_l.modifiers = KeyModifiers::CONTROL;
+        let mut app = App::default();
+        assert_eq!(handle_key(&mut app, ctrl_l), Some(Action::Redraw));
+        // and doesn't type an `l` while editing
+        press(&mut app, KeyCode::Char('i'));
+        assert_eq!(handle_key(&mut app, ctrl_l), Some(Action::Redraw));
+        assert_eq!(app.input, "");
+        assert!(matches!(app.input_mode, InputMode::Editing));
+    }
+
+    #[test]
+    fn quiz() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('Q'));
+        let question = app.quiz.as_ref().unwrap().example.equation;
+        assert_eq!(app.input, question);
+        assert!(matches!(app.input_mode, InputMode::Editing));
+        // the answer isn't given away
+        assert!(app.output.is_none());
+
+        // some examples are balanced as they are, so change it to be sure it's wrong
+        app.set_input(&format!("7{question}"));
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(&app.status, Some(Status::Warning(s)) if s.starts_with("Not balanced")));
+        let mut ctrl_a = key(KeyCode::Char('a'), KeyEventKind::Press);
+        ctrl_a.modifiers = KeyModifiers::CONTROL;
+        handle_key(&mut app, ctrl_a);
+        let answer = app.quiz.as_ref().unwrap().answer.clone();
+        assert!(matches!(&app.status, Some(Status::Info(s)) if s.starts_with(&answer)));
+
+        // enter moves on to another question
+        press(&mut app, KeyCode::Enter);
+        let quiz = app.quiz.as_ref().unwrap();
+        assert_ne!(quiz.example.equation, question);
+        assert_eq!(app.input, quiz.example.equation);
+        assert!(!quiz.solved);
+        assert_eq!((quiz.right, quiz.finished), (0, 1));
+
+        // which scores when answered without looking
+        let answer = quiz.answer.clone();
+        app.set_input(&answer);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(&app.status, Some(Status::Info(s)) if s.starts_with("Correct")));
+        let quiz = app.quiz.as_ref().unwrap();
+        assert_eq!((quiz.right, quiz.finished), (1, 2));
+
+        // stopping balances whatever is typed
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('Q'));
+        assert!(app.quiz.is_none());
+        assert!(matches!(&app.status, Some(Status::Info(s)) if s == "Quiz over, 1/2 right"));
+        assert!(matches!(app.output, Some(Ok(_))));
+    }
+
+    #[test]
+    fn pick_example() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('E'));
+        assert!(matches!(app.input_mode, InputMode::Examples));
+        // keys that do something in normal mode are typed into the search
+        for c in "neutralq".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.example_query, "neutralq");
+        assert_eq!(press(&mut app, KeyCode::Backspace), None);
+        assert_eq!(app.example_query, "neutral");
+
+        // choosing stays within the matches
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.example_selected, 0);
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Down);
+        }
+        assert_eq!(app.example_selected, 2);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.input, "H2SO4 + NaOH -> Na2SO4 + H2O");
+
+        // cancelling leaves the input alone
+        press(&mut app, KeyCode::Char('E'));
+        press(&mut app, KeyCode::Char('h'));
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.input, "H2SO4 + NaOH -> Na2SO4 + H2O");
+
+        // nothing to pick
+        press(&mut app, KeyCode::Char('E'));
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Char('q'));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Examples));
+    }
+
+    #[test]
+    fn reverse() {
+        let mut app = typed("2H2 + O2 -> 2H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('S'));
+        assert_eq!(app.input, "2H2O -> 2H2 + O2");
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "2H2O -> 2H2 + O2"
+        );
+        // and back again
+        press(&mut app, KeyCode::Char('S'));
+        assert_eq!(app.input, "2H2 + O2 -> 2H2O");
+
+        app.set_input("H2 + O2");
+        press(&mut app, KeyCode::Char('S'));
+        assert_eq!(app.input, "H2 + O2");
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+    }
+
+    #[test]
+    fn toggle_redox() {
+        let mut app = typed("Cu + NO3^- -> Cu^2+ + NO2");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.output.as_ref().unwrap().is_err());
+        press(&mut app, KeyCode::Char('R'));
+        assert_eq!(app.redox, Some(Medium::Acidic));
+        assert_eq!(
+            app.balanced_from(),
+            "Cu + 2NO3^- + 4H^+ -> Cu^2+ + 2NO2 + 2H2O"
+        );
+        assert_eq!(
+            app.last_balanced.as_ref().unwrap().input,
+            app.balanced_from()
+        );
+        press(&mut app, KeyCode::Char('R'));
+        assert_eq!(app.redox, Some(Medium::Basic));
+        assert_eq!(
+            app.balanced_from(),
+            "Cu + 2NO3^- + 2H2O -> Cu^2+ + 2NO2 + 4OH^-"
+        );
+        // back to balancing as written
+        press(&mut app, KeyCode::Char('R'));
+        assert_eq!(app.redox, None);
+        assert!(app.half_reactions.is_none());
+        assert_eq!(app.balanced_from(), app.input);
+    }
+
+    #[test]
+    fn auto_capitalize() {
+        let mut app = App {
+            auto_capitalize: true,
+            ..Default::default()
+        };
+        press(&mut app, KeyCode::Char('i'));
+        for c in "2na + cl2 -> 2nacl".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        app.update_eq();
+        assert_eq!(app.input, "2Na + Cl2 -> 2NaCl");
+        assert_eq!(app.cursor, app.input.chars().count());
+        assert!(app.output.as_ref().unwrap().is_ok());
+        // off by default
+        let app = typed("na + cl2 -> nacl");
+        assert_eq!(app.input, "na + cl2 -> nacl");
+    }
+
+    #[test]
+    fn convert() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('c'));
+        assert!(matches!(app.input_mode, InputMode::Convert));
+        assert_eq!(app.converted(), None);
+        for c in "18 g H2O".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.converted(), Some(Ok("0.9992 mol".to_string())));
+        // no formula yet
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Backspace);
+        }
+        assert!(matches!(app.converted(), Some(Err(_))));
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn save() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('w'));
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('w'));
+        assert!(matches!(app.input_mode, InputMode::Save));
+        for _ in 0.."equations.txt".len() {
+            press(&mut app, KeyCode::Backspace);
+        }
+        // nowhere to save it yet
+        assert_eq!(press(&mut app, KeyCode::Enter), None);
+        assert!(matches!(app.input_mode, InputMode::Save));
+        handle_paste(&mut app, "out.txt");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(
+            press(&mut app, KeyCode::Enter),
+            Some(Action::Save {
+                text: "2H2 + O2 -> 2H2O".to_string(),
+                path: PathBuf::from("out.txt"),
+                append: false,
+            })
+        );
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        // the same file is offered next time
+        press(&mut app, KeyCode::Char('w'));
+        assert_eq!(app.save_path, "out.txt");
+    }
+
+    #[test]
+    fn load() {
+        let dir = std::env::temp_dir().join(format!("balance-tui-load-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("equations.txt");
+        fs::write(&path, "H2 + O2 -> H2O\n\nFe + O2 -> Fe2O3\n").unwrap();
+
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('>'));
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+        press(&mut app, KeyCode::Char('l'));
+        assert!(matches!(app.input_mode, InputMode::Load));
+        app.load_path.clear();
+        handle_paste(&mut app, &path.display().to_string());
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+        assert!(app.output.as_ref().is_some_and(Result::is_ok));
+        assert!(
+            matches!(&app.status, Some(Status::Info(s)) if s.starts_with("Equation 1 of 2 from "))
+        );
+
+        // the blank line is skipped and it stops at the ends
+        press(&mut app, KeyCode::Char('>'));
+        press(&mut app, KeyCode::Char('>'));
+        assert_eq!(app.input, "Fe + O2 -> Fe2O3");
+        press(&mut app, KeyCode::Char('<'));
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+
+        fs::write(&path, "\n").unwrap();
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Enter);
+        assert!(
+            matches!(&app.status, Some(Status::Warning(s)) if s.starts_with("No equations in "))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(&app.status, Some(Status::Error(s)) if s.starts_with("Couldn't read ")));
+        // what was loaded before is kept
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+    }
+
+    #[test]
+    fn transcript() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('T'));
+        assert!(matches!(app.input_mode, InputMode::Transcript));
+        assert_eq!(press(&mut app, KeyCode::Char('p')), None);
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        // not entered yet, but still in it
+        app.set_input("Fe + O2 -> Fe2O3");
+        press(&mut app, KeyCode::Char('T'));
+        // an unknown format is ignored
+        assert_eq!(press(&mut app, KeyCode::Char('x')), None);
+        assert!(matches!(app.input_mode, InputMode::Transcript));
+        assert_eq!(
+            press(&mut app, KeyCode::Char('p')),
+            Some(Action::CopyTranscript {
+                text: "2H2 + O2 -> 2H2O\n4Fe + 3O2 -> 2Fe2O3\n".to_string(),
+                format: Format::Plain,
+            })
+        );
+        press(&mut app, KeyCode::Char('T'));
+        let Some(Action::Export { report, path }) = press(&mut app, KeyCode::Char('C')) else {
+            panic!("C doesn't save the transcript");
+        };
+        assert_eq!(path, PathBuf::from("transcript.csv"));
+        assert!(report.starts_with("input,balanced,ok\nH2 + O2 -> H2O,2H2 + O2 -> 2H2O,true\n"));
+    }
+
+    #[test]
+    fn favorites() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('*'));
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+        press(&mut app, KeyCode::Char('f'));
+        assert!(matches!(app.input_mode, InputMode::Normal));
+
+        app.set_input("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Char('*'));
+        app.set_input("C + O2 -> CO2");
+        press(&mut app, KeyCode::Char('*'));
+        assert!(app.is_favorite());
+        assert_eq!(app.favorites, ["H2 + O2 -> H2O", "C + O2 -> CO2"]);
+
+        press(&mut app, KeyCode::Char('f'));
+        assert!(matches!(app.input_mode, InputMode::Favorites));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.favorite_selected, 1);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.input, "H2 + O2 -> H2O");
+
+        // starring it again takes it off
+        press(&mut app, KeyCode::Char('*'));
+        assert_eq!(app.favorites, ["C + O2 -> CO2"]);
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Delete);
+        assert!(app.favorites.is_empty());
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn settings() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char(','));
+        assert!(matches!(app.input_mode, InputMode::Settings));
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.theme, Theme::Light);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.show_help);
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Down);
+        }
+        assert_eq!(Setting::ALL[app.setting_selected], Setting::ExplicitOnes);
+        press(&mut app, KeyCode::Char(' '));
+        assert!(app.explicit_ones);
+        assert!(app.settings_changed);
+        // letters don't do anything here
+        press(&mut app, KeyCode::Char('q'));
+        assert!(matches!(app.input_mode, InputMode::Settings));
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn stoichiometry() {
+        // nothing to work from yet
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('s'));
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert!(matches!(app.status, Some(Status::Warning(_))));
+
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('s'));
+        assert!(matches!(app.input_mode, InputMode::Stoich));
+        assert_eq!(app.stoich_yield(), None);
+        for c in "4x".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.stoich_inputs[0], ("4".to_string(), Unit::Grams));
+        let y = app.stoich_yield().unwrap().unwrap();
+        assert_eq!(y.amounts[0].grams, Some(4.0));
+        assert_eq!(stoich::round(y.amounts[2].grams.unwrap()), "35.74");
+        assert_eq!(y.limiting, None);
+
+        // half a mole of oxygen runs out before 4g of hydrogen
+        press(&mut app, KeyCode::Down);
+        for c in "0.5".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.stoich_inputs[1], ("0.5".to_string(), Unit::Moles));
+        let y = app.stoich_yield().unwrap().unwrap();
+        assert_eq!(y.limiting, Some(1));
+        assert_eq!(y.amounts[2].moles, 1.0);
+
+        // a product typed with them is how much was made
+        assert_eq!(app.stoich_percent_yield(), None);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('2'));
+        assert_eq!(app.stoich_yield().unwrap().unwrap().limiting, Some(1));
+        let p = app.stoich_percent_yield().unwrap().unwrap();
+        assert_eq!(stoich::round(p.theoretical), "18.02");
+        assert_eq!(stoich::round(p.percent), "11.1");
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.stoich_selected, 2);
+        press(&mut app, KeyCode::Backspace);
+        assert!(app.stoich_yield().unwrap().is_ok());
+        assert_eq!(app.stoich_percent_yield(), None);
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+
+        // opened again, it starts over
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.stoich_yield(), None);
+    }
+
+    #[test]
+    fn accept_suggestion() {
+        let mut app = typed("mg + o2 -> mgo");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.suggestion().as_deref(), Some("Mg + O2 -> MgO"));
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.input, "Mg + O2 -> MgO");
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "2Mg + O2 -> 2MgO"
+        );
+        assert_eq!(app.suggestion(), None);
+
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.input, "Mg + O2 -> MgO");
+        assert!(matches!(&app.status, Some(Status::Warning(s)) if s == "Nothing to suggest"));
+    }
+
+    #[test]
+    fn periodic_table() {
+        let mut app = typed("Fe + ");
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('p'));
+        assert!(matches!(app.input_mode, InputMode::Table));
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.table_selected, 10);
+        // the arrows don't reach the input or history while it's open
+        assert_eq!(app.input, "Fe + ");
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+
+        // it opens where it was left, and enter types the symbol
+        press(&mut app, KeyCode::Char('p'));
+        press(&mut app, KeyCode::Left);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input, "Fe + F");
+        assert!(matches!(app.input_mode, InputMode::Editing));
+
+        // typing finds an element by symbol or name
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('p'));
+        for c in "sod".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.table_selected, 11);
+        // nothing matches, so it stays put
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.table_selected, 11);
+        for _ in 0..4 {
+            press(&mut app, KeyCode::Backspace);
+        }
+        press(&mut app, KeyCode::Char('K'));
+        assert_eq!(app.table_selected, 19);
+        press(&mut app, KeyCode::Right);
+        assert_eq!((app.table_selected, app.table_query.as_str()), (20, ""));
+    }
+
+    #[test]
+    fn complete_combustion() {
+        let mut app = typed("C3H8 + O2 ->");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.combustion().as_deref(), Some("C3H8 + O2 -> CO2 + H2O"));
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.input, "C3H8 + O2 -> CO2 + H2O");
+        assert_eq!(
+            app.output.as_ref().unwrap().as_ref().unwrap().equation(),
+            "C3H8 + 5O2 -> 3CO2 + 4H2O"
+        );
+        assert_eq!(app.combustion(), None);
+    }
+
+    #[test]
+    fn last_balanced() {
+        let mut app = typed("H2 + O2 -> H2O");
+        press(&mut app, KeyCode::Backspace);
+        app.update_eq();
+        assert!(matches!(app.output, Some(Err(_))));
+        // the title stays on the last one that balanced
+        assert_eq!(
+            app.window_title().unwrap(),
+            "balance-tui — 2H2 + O2 -> 2H2O"
+        );
+        let last = app.last_balanced.unwrap();
+        assert_eq!(last.input, "H2 + O2 -> H2O");
+        assert_eq!(last.output.unwrap().equation(), "2H2 + O2 -> 2H2O");
+    }
+
+    #[test]
+    fn multibyte_cursor() {
+        let mut app = typed("H2 → H₂");
+        assert_eq!(app.cursor, 7);
+        assert_eq!(app.cursor_column(80), 8);
+
+        app.move_cursor(-3);
+        app.insert('x');
+        assert_eq!(app.input, "H2 →x H₂");
+        assert_eq!(app.cursor_column(80), 6);
+
+        app.backspace();
+        app.backspace();
+        assert_eq!(app.input, "H2  H₂");
+        assert_eq!(app.cursor_column(80), 4);
+    }
+
+    #[test]
+    fn wide_cursor() {
+        // full width characters take two columns each
+        let app = typed("Ｈ２");
+        assert_eq!(app.cursor_column(80), 5);
+    }
+
+    #[test]
+    fn long_input_scrolls() {
+        let mut app = typed(&"H2O".repeat(100));
+        assert_eq!(app.input_scroll(20), 282);
+        assert_eq!(app.cursor_column(20), 19);
+
+        press(&mut app, KeyCode::Home);
+        assert_eq!(app.input_scroll(20), 0);
+        assert_eq!(app.cursor_column(20), 1);
+    }
+}
@@ -0,0 +1,238 @@
+use chem_eq::{balance::EquationBalancer, error::BalanceError, Compound, Equation};
+
+use crate::{
+    charge, chem,
+    error::Error,
+    hydrate, matrix,
+    normalize::{normalize, tidy},
+};
+
+/// Parse and balance an equation
+pub fn balance(input: &str) -> Result<Equation, Error> {
+    balance_reduced(input).map(|(eq, _)| eq)
+}
+
+/// Parse and balance an equation with the smallest whole coefficients, also
+/// returning the common factor they were divided by, 1 if there wasn't one
+///
+/// Eg: `4H2 + 2O2 -> 4H2O` is already balanced, but becomes `2H2 + O2 -> 2H2O`
+pub fn balance_reduced(input: &str) -> Result<(Equation, usize), Error> {
+    hydrate::check(input)?;
+    let eq = parse(input).inspect_err(|e| tracing::info!(input, error = ?e, "parse failed"))?;
+    tracing::debug!(input, compounds = eq.num_compounds(), "parsed");
+    let charges = charge::charges(input);
+    // electrons are all there is to balance them against
+    let electrons = eq.iter_compounds().filter(|c| chem::is_electron(c)).count();
+    if electrons > 0 && charges.iter().filter(|&&c| c != 0).count() == electrons {
+        return Err(Error::UnchargedElectrons);
+    }
+    let balanced = if charges.iter().any(|&c| c != 0) && charges.len() == eq.num_compounds() {
+        balance_charged(&eq, &charges)
+    } else {
+        EquationBalancer::new(&eq).balance()
+    }
+    .inspect_err(|e| tracing::info!(input, error = ?e, "balance failed"))?;
+    let (eq, factor) = reduce(balanced);
+    tracing::info!(input, output = eq.equation(), factor, "balanced");
+    Ok((eq, factor))
+}
+
+/// Parse an equation like chem-eq does, but with electrons, written `e^-` or
+/// `e-`. chem-eq has no element for them, so the rest is parsed without them
+/// and they're put back as compounds of no elements. Symbols that aren't
+/// elements are pointed out rather than left to fail to parse
+pub fn parse(input: &str) -> Result<Equation, Error> {
+    if let Some(unknown) = chem::unknown_element(input) {
+        return Err(Error::UnknownElement(unknown));
+    }
+    let tidied = tidy(input);
+    let mut sides: [Vec<&str>; 2] = [Vec::new(), Vec::new()];
+    let mut arrow = None;
+    // the coefficient and place on its side of each electron
+    let mut electrons: Vec<(bool, usize, usize)> = Vec::new();
+    for token in tidied.split(' ') {
+        match token {
+            "+" => {}
+            "->" | "<-" | "<->" => arrow = Some(token),
+            compound => {
+                let side = usize::from(arrow.is_some());
+                let digits = compound
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(compound.len());
+                if charge::is_electron(&compound[digits..]) {
+                    let n = compound[..digits].parse().unwrap_or(1);
+                    let place =
+                        sides[side].len() + electrons.iter().filter(|e| e.0 == (side == 0)).count();
+                    electrons.push((side == 0, place, n));
+                } else {
+                    sides[side].push(compound);
+                }
+            }
+        }
+    }
+    if electrons.is_empty() {
+        return Ok(Equation::new(&normalize(input))?);
+    }
+    let [left, right] = sides;
+    let rest = format!(
+        "{} {} {}",
+        left.join(" + "),
+        arrow.unwrap_or_default(),
+        right.join(" + ")
+    );
+    Ok(with_electrons(
+        Equation::new(&normalize(&rest))?,
+        &electrons,
+    ))
+}
+
+/// Put electrons into a parsed equation, each given by whether it's on the
+/// left, its place on that side and its coefficient
+fn with_electrons(mut eq: Equation, electrons: &[(bool, usize, usize)]) -> Equation {
+    for &(left, place, coefficient) in electrons {
+        let side = if left { eq.left_mut() } else { eq.right_mut() };
+        let electron = Compound {
+            coefficient,
+            ..Default::default()
+        };
+        side.insert(place.min(side.len()), electron);
+    }
+    eq
+}
+
+/// Balance an equation of ions, keeping the charge the same on both sides as
+/// well as the atoms. chem-eq has no charges, so this solves for them itself
+fn balance_charged(eq: &Equation, charges: &[i64]) -> Result<Equation, BalanceError> {
+    let compounds: Vec<_> = eq.iter_compounds().collect();
+    // products are negated, so a balanced equation adds up to 0
+    let sign = |i: usize| if i < eq.left().len() { 1 } else { -1 };
+    let mut rows: Vec<Vec<i128>> = eq
+        .uniq_elements()
+        .into_iter()
+        .map(|el| {
+            compounds
+                .iter()
+                .enumerate()
+                .map(|(i, c)| sign(i) * chem::element_counts(c).get(el).map_or(0, |&n| n as i128))
+                .collect()
+        })
+        .collect();
+    rows.push(
+        charges
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| sign(i) * i128::from(c))
+            .collect(),
+    );
+    let coefficients = matrix::null_vector(rows)
+        .filter(|x| x.iter().all(|&n| n > 0))
+        .ok_or(BalanceError::Infeasable)?;
+    Ok(with_coefficients(
+        eq,
+        coefficients.into_iter().map(|n| n as usize),
+    ))
+}
+
+/// Divide the coefficients of a balanced equation by their greatest common divisor
+pub fn reduce(eq: Equation) -> (Equation, usize) {
+    let factor = eq.iter_compounds().map(|c| c.coefficient).fold(0, gcd);
+    if factor <= 1 {
+        return (eq, 1);
+    }
+
+    let reduced = with_coefficients(&eq, eq.iter_compounds().map(|c| c.coefficient / factor));
+    (reduced, factor)
+}
+
+/// Rebuild an equation with new coefficients, rewriting the text so it prints
+/// with them. Electrons aren't in the text, they're put back afterwards
+fn with_coefficients(eq: &Equation, coefficients: impl Iterator<Item = usize>) -> Equation {
+    let left_len = eq.left().len();
+    let mut electrons = Vec::new();
+    let mut sides: [Vec<String>; 2] = [Vec::new(), Vec::new()];
+    let compounds = eq
+        .iter_compounds()
+        .zip(coefficients)
+        .zip(chem::formulas(eq));
+    for (i, ((cmp, n), f)) in compounds.enumerate() {
+        let (side, place) = if i < left_len {
+            (0, i)
+        } else {
+            (1, i - left_len)
+        };
+        if chem::is_electron(cmp) {
+            electrons.push((side == 0, place, n));
+            continue;
+        }
+        sides[side].push(match n {
+            1 => f,
+            n => format!("{n}{f}"),
+        });
+    }
+    let text = format!(
+        "{} {} {}",
+        sides[0].join(" + "),
+        eq.direction(),
+        sides[1].join(" + ")
+    );
+    let rebuilt = Equation::new(&text).expect("rebuilt equation is parsed from a valid one");
+    with_electrons(rebuilt, &electrons)
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_factor() {
+        let (eq, factor) = balance_reduced("4H2 + 2O2 -> 4H2O").unwrap();
+        assert_eq!(eq.equation(), "2H2 + O2 -> 2H2O");
+        assert_eq!(factor, 2);
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [2, 1, 2]);
+    }
+
+    #[test]
+    fn already_smallest() {
+        let (eq, factor) = balance_reduced("Fe + O2 -> Fe2O3").unwrap();
+        assert_eq!(eq.equation(), "4Fe + 3O2 -> 2Fe2O3");
+        assert_eq!(factor, 1);
+    }
+
+    #[test]
+    fn electrons() {
+        let (eq, _) = balance_reduced("Fe^3+ + e- -> Fe^2+").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [1, 1, 1]);
+        assert!(chem::is_electron(&eq.left()[1]));
+
+        let eq = balance("MnO4^- + H^+ + e^- -> Mn^2+ + H2O").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [1, 8, 5, 1, 4]);
+        // on the right, with coefficients typed
+        let eq = balance("2Cu -> 2Cu^2+ + 4e-").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [1, 1, 2]);
+        assert!(chem::is_electron(&eq.right()[1]));
+
+        // nothing charged for them to balance
+        assert!(matches!(
+            balance("Na -> Na + e-"),
+            Err(Error::UnchargedElectrons)
+        ));
+    }
+
+    #[test]
+    fn keeps_states() {
+        let eq = balance("4Na(s) + 2Cl2(g) -> 4NaCl(s)").unwrap();
+        assert_eq!(eq.equation(), "2Na(s) + Cl2(g) -> 2NaCl(s)");
+    }
+}
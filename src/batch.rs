@@ -0,0 +1,170 @@
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chem_eq::Equation;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use crate::{
+    balance::balance,
+    chem::canonicalize,
+    error::Error,
+    normalize::normalize,
+    redox::{self, Medium},
+};
+
+/// The outcome of balancing one line of a batch
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub input: String,
+    pub output: Result<Equation, Error>,
+}
+
+impl BatchResult {
+    pub fn new(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            output: balance(input),
+        }
+    }
+
+    /// Balance by the half-reaction method. The input becomes the balanced
+    /// equation, so the water, ions and electrons it added are written out
+    pub fn redox(input: &str, medium: Medium) -> Self {
+        match redox::balance(input, medium) {
+            Ok(r) => Self {
+                output: Equation::new(&normalize(&r.equation)).map_err(Error::from),
+                input: r.equation,
+            },
+            Err(e) => Self {
+                input: input.to_string(),
+                output: Err(e.into()),
+            },
+        }
+    }
+}
+
+/// Keep the first of results that balanced to the same equation, however they
+/// were written. Errors are only the same as one with the same input
+pub fn dedupe(results: Vec<BatchResult>) -> Vec<BatchResult> {
+    let mut seen = HashSet::new();
+    results
+        .into_iter()
+        .filter(|res| {
+            let key = match &res.output {
+                Ok(eq) => canonicalize(eq),
+                Err(_) => format!("error: {}", res.input),
+            };
+            seen.insert(key)
+        })
+        .collect()
+}
+
+/// Get the lines of a batch file which contain an equation
+pub fn equations(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Balance every equation on its own thread, keeping the results in the same
+/// order as the input
+pub fn balance_all(lines: &[&str], redox: Option<Medium>) -> Vec<BatchResult> {
+    lines.par_iter().map(|l| balance_line(l, redox)).collect()
+}
+
+/// Balance every equation one after another on the current thread
+pub fn balance_all_serial(lines: &[&str], redox: Option<Medium>) -> Vec<BatchResult> {
+    lines.iter().map(|l| balance_line(l, redox)).collect()
+}
+
+/// A pool of `jobs` threads to balance batches with
+pub fn pool(jobs: usize) -> Result<ThreadPool, ThreadPoolBuildError> {
+    ThreadPoolBuilder::new().num_threads(jobs).build()
+}
+
+/// Balance every equation on the threads of `pool`
+pub fn balance_all_in(
+    pool: &ThreadPool,
+    lines: &[&str],
+    redox: Option<Medium>,
+) -> Vec<BatchResult> {
+    pool.install(|| balance_all(lines, redox))
+}
+
+/// Balance a line of a batch, by half-reactions in `redox` when it's given
+fn balance_line(line: &str, redox: Option<Medium>) -> BatchResult {
+    match redox {
+        Some(medium) => BatchResult::redox(line, medium),
+        None => BatchResult::new(line),
+    }
+}
+
+/// Write an equation to `path` as a line of a batch file, after the lines
+/// already there if `append` or in place of them
+pub fn save_line(path: &Path, line: &str, append: bool) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    // so it doesn't run on from a last line without a newline
+    let run_on = append
+        && fs::read_to_string(path).is_ok_and(|text| !text.is_empty() && !text.ends_with('\n'));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    if run_on {
+        writeln!(file)?;
+    }
+    writeln!(file, "{line}")
+}
+
+/// A path as it's typed, with a leading `~` for the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving() {
+        let dir = std::env::temp_dir().join(format!("balance-tui-save-{}", std::process::id()));
+        let path = dir.join("equations.txt");
+        save_line(&path, "2H2 + O2 -> 2H2O", true).unwrap();
+        fs::write(&path, "2H2 + O2 -> 2H2O").unwrap();
+        save_line(&path, "4Fe + 3O2 -> 2Fe2O3", true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "2H2 + O2 -> 2H2O\n4Fe + 3O2 -> 2Fe2O3\n"
+        );
+        assert_eq!(equations(&fs::read_to_string(&path).unwrap()).len(), 2);
+
+        save_line(&path, "N2 + 3H2 -> 2NH3", false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "N2 + 3H2 -> 2NH3\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn home() {
+        assert_eq!(expand_home("out.txt"), PathBuf::from("out.txt"));
+        assert_eq!(expand_home("~user/out.txt"), PathBuf::from("~user/out.txt"));
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_home("~/out.txt"), home.join("out.txt"));
+        }
+    }
+}
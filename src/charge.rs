@@ -0,0 +1,241 @@
+use crate::normalize::tidy;
+
+/// Split the state of matter off the end of a compound, eg. `NaCl(aq)` is
+/// `NaCl` and `(aq)`. Brackets starting with a capital are part of the formula
+pub fn split_state(compound: &str) -> (&str, &str) {
+    match compound.rfind('(') {
+        Some(i) if compound[i + 1..].starts_with(|c: char| c.is_lowercase()) => {
+            compound.split_at(i)
+        }
+        _ => (compound, ""),
+    }
+}
+
+/// Split the charge off a compound, eg. `Fe^3+(aq)` is `Fe`, 3 and `(aq)`.
+/// Charges are written after a `^`, like `^2+`, `^-2` or `^-`, or as a single
+/// sign on the end like `Cl-`, as `Fe3+` would be three irons
+pub fn split(compound: &str) -> (String, i64, &str) {
+    let (core, state) = split_state(compound);
+    if let Some((body, charge)) = core.split_once('^') {
+        return match parse(charge) {
+            Some(charge) => (body.to_string(), charge, state),
+            None => (compound.to_string(), 0, ""),
+        };
+    }
+    let body = core.trim_end_matches(['+', '-']);
+    match &core[body.len()..] {
+        "+" if !body.is_empty() => (body.to_string(), 1, state),
+        "-" if !body.is_empty() => (body.to_string(), -1, state),
+        _ => (compound.to_string(), 0, ""),
+    }
+}
+
+/// Whether a compound without its coefficient is an electron, `e^-` or `e-`
+pub fn is_electron(compound: &str) -> bool {
+    matches!(split(compound), (body, -1, _) if body == "e")
+}
+
+/// Read a charge written after a `^`, the sign before or after the number
+fn parse(charge: &str) -> Option<i64> {
+    let digits = |s: &str| match s {
+        "" => Some(1),
+        s => s.parse::<i64>().ok().filter(|n| *n > 0),
+    };
+    if let Some(n) = charge.strip_suffix('+') {
+        digits(n)
+    } else if let Some(n) = charge.strip_suffix('-') {
+        digits(n).map(|n| -n)
+    } else if let Some(n) = charge.strip_prefix('+') {
+        digits(n)
+    } else {
+        digits(charge.strip_prefix('-')?).map(|n| -n)
+    }
+}
+
+/// Take the charges off every compound, so chem-eq can parse what's left.
+/// Only compounds separated by spaces are looked at
+pub fn strip(equation: &str) -> String {
+    equation
+        .split(' ')
+        .map(|token| match token {
+            "+" | "->" | "<-" | "<->" => token.to_string(),
+            compound => {
+                let (body, _, state) = split(compound);
+                body + state
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The charge of each compound in an equation, in order. Only compounds
+/// separated by spaces are looked at, the same as [`strip`]
+pub fn charges(input: &str) -> Vec<i64> {
+    compounds(&tidy(input))
+        .into_iter()
+        .map(|c| split(c).1)
+        .collect()
+}
+
+/// The net charge of each side of an equation as it's written, counting the
+/// coefficients. `None` when every compound is neutral
+pub fn net(input: &str) -> Option<(i64, i64)> {
+    let tidied = tidy(input);
+    let mut sides = [0, 0];
+    let mut side = 0;
+    let mut charged = false;
+    for token in tidied.split(' ') {
+        match token {
+            "+" => {}
+            "->" | "<-" | "<->" => side = 1,
+            compound => {
+                let digits = compound
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(compound.len());
+                let coefficient = compound[..digits].parse::<i64>().unwrap_or(1);
+                let charge = split(&compound[digits..]).1;
+                charged |= charge != 0;
+                sides[side] += coefficient * charge;
+            }
+        }
+    }
+    charged.then_some((sides[0], sides[1]))
+}
+
+/// Write a charge the way chemists do, eg. `2+` or `-`
+pub fn format(charge: i64) -> String {
+    let sign = if charge < 0 { '-' } else { '+' };
+    match charge.abs() {
+        1 => sign.to_string(),
+        n => format!("{n}{sign}"),
+    }
+}
+
+/// Write the net charge of a side, which unlike an ion's can be 0
+pub fn format_net(charge: i64) -> String {
+    match charge {
+        0 => "0".to_string(),
+        charge => format(charge),
+    }
+}
+
+/// The superscript of a digit or sign, which unlike subscripts aren't all in one block
+pub fn superscript(c: char) -> char {
+    match c {
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '+' => '⁺',
+        '-' => '⁻',
+        c => c
+            .to_digit(10)
+            .and_then(|d| char::from_u32('⁰' as u32 + d))
+            .unwrap_or(c),
+    }
+}
+
+/// The digit or sign a superscript is of, eg. `3` for `³`
+pub fn from_superscript(c: char) -> Option<char> {
+    match c {
+        '¹' => Some('1'),
+        '²' => Some('2'),
+        '³' => Some('3'),
+        '⁺' => Some('+'),
+        '⁻' => Some('-'),
+        '⁰'..='⁹' => char::from_digit(c as u32 - '⁰' as u32, 10),
+        _ => None,
+    }
+}
+
+/// Write the charge of each compound in an equation as superscripts, eg.
+/// `Fe^3+` as `Fe³⁺`. Everything else is left as it is
+pub fn superscript_charges(equation: &str) -> String {
+    equation
+        .split(' ')
+        .map(|token| match split(token) {
+            (_, 0, _) => token.to_string(),
+            (body, charge, state) => {
+                let charge: String = format(charge).chars().map(superscript).collect();
+                body + &charge + state
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The compounds of a tidied equation separated by spaces
+pub fn compounds(equation: &str) -> Vec<&str> {
+    equation
+        .split(' ')
+        .filter(|t| !matches!(*t, "+" | "->" | "<-" | "<->" | ""))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::balance;
+
+    #[test]
+    fn splits() {
+        assert_eq!(split("Fe^2+"), ("Fe".to_string(), 2, ""));
+        assert_eq!(split("Fe^+2"), ("Fe".to_string(), 2, ""));
+        assert_eq!(split("MnO4^-"), ("MnO4".to_string(), -1, ""));
+        assert_eq!(split("SO4^2-(aq)"), ("SO4".to_string(), -2, "(aq)"));
+        assert_eq!(split("Cl-"), ("Cl".to_string(), -1, ""));
+        assert_eq!(split("NH4+(aq)"), ("NH4".to_string(), 1, "(aq)"));
+        // neutral
+        assert_eq!(split("H2O(l)"), ("H2O(l)".to_string(), 0, ""));
+        assert_eq!(split("Fe^x"), ("Fe^x".to_string(), 0, ""));
+        assert_eq!(split("+"), ("+".to_string(), 0, ""));
+        assert_eq!(split_state("NaCl(aq)"), ("NaCl", "(aq)"));
+        assert_eq!(split_state("Fe(OH)3(s)"), ("Fe(OH)3", "(s)"));
+        assert_eq!(split_state("Ca(OH)2"), ("Ca(OH)2", ""));
+    }
+
+    #[test]
+    fn strips() {
+        assert_eq!(
+            strip("Fe^2+ + MnO4^- + H^+ -> Fe^3+ + Mn^2+ + H2O"),
+            "Fe + MnO4 + H -> Fe + Mn + H2O"
+        );
+        assert_eq!(strip("2H2 + O2 -> 2H2O"), "2H2 + O2 -> 2H2O");
+        assert_eq!(charges("Na+ + Cl- -> NaCl"), [1, -1, 0]);
+    }
+
+    #[test]
+    fn net_charge() {
+        assert_eq!(net("Fe^3+ + Cu -> Fe^2+ + Cu^2+"), Some((3, 4)));
+        assert_eq!(net("2Fe^3+ + Cu -> 2Fe^2+ + Cu^2+"), Some((6, 6)));
+        assert_eq!(net("2H2 + O2 -> 2H2O"), None);
+        assert_eq!(format(2), "2+");
+        assert_eq!(format(-1), "-");
+        assert_eq!(format_net(0), "0");
+    }
+
+    #[test]
+    fn superscripts() {
+        assert_eq!(
+            superscript_charges("5Fe^2+ + MnO4^- + 8H^+ -> 5Fe^3+ + Mn^2+ + 4H2O"),
+            "5Fe²⁺ + MnO4⁻ + 8H⁺ -> 5Fe³⁺ + Mn²⁺ + 4H2O"
+        );
+        assert_eq!(superscript_charges("SO4^2-(aq) + Na+"), "SO4²⁻(aq) + Na⁺");
+        assert_eq!(superscript_charges("2H2 + O2 -> 2H2O"), "2H2 + O2 -> 2H2O");
+        for c in "0123456789+-".chars() {
+            assert_eq!(from_superscript(superscript(c)), Some(c));
+        }
+        assert_eq!(from_superscript('2'), None);
+    }
+
+    #[test]
+    fn balances() {
+        let eq = balance("Fe^2+ + MnO4^- + H^+ -> Fe^3+ + Mn^2+ + H2O").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [5, 1, 8, 5, 1, 4]);
+        let eq = balance("Fe^3+ + Cu -> Fe^2+ + Cu^2+").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [2, 1, 2, 1]);
+        // atoms balance, but the charge can't
+        assert!(balance("Na+ -> Na").is_err());
+    }
+}
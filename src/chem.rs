@@ -0,0 +1,378 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+use chem_eq::{Compound, Direction, Equation};
+
+use crate::{
+    balance, charge,
+    elements::Info,
+    matrix::gcd,
+    normalize::{normalize, tidy},
+};
+
+/// Get the formula of each compound as written in the equation, without
+/// coefficients. Electrons aren't in chem-eq's text, so they're `e`
+///
+/// Eg: `2Fe2O3` becomes `Fe2O3`
+pub fn formulas(eq: &Equation) -> Vec<String> {
+    let mut written = eq
+        .equation()
+        .split_whitespace()
+        .filter(|c| !matches!(*c, "+" | "<-" | "<->" | "->"))
+        .map(|c| {
+            c.trim_start_matches(|c: char| c.is_ascii_digit())
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .into_iter();
+    let electrons = eq.iter_compounds().filter(|c| is_electron(c)).count();
+    if written.len() + electrons == eq.num_compounds() {
+        return eq
+            .iter_compounds()
+            .map(|c| {
+                if is_electron(c) {
+                    "e".to_string()
+                } else {
+                    written.next().unwrap_or_default()
+                }
+            })
+            .collect();
+    }
+
+    // the text wasn't separated by spaces, so rebuild it from the elements instead
+    eq.iter_compounds()
+        .map(|c| {
+            c.elements
+                .iter()
+                .map(|e| match e.count {
+                    1 => e.symbol().to_string(),
+                    n => format!("{}{n}", e.symbol()),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The formula of each compound like [`formulas`], but written the way it was
+/// typed in `input`, with any charges and hydrates. A compound that's changed
+/// since `eq` was balanced from `input` is left as it is in `eq`
+pub fn typed_formulas(input: &str, eq: &Equation) -> Vec<String> {
+    let tidied = tidy(input);
+    let typed = charge::compounds(&tidied);
+    let formulas = formulas(eq);
+    if typed.len() != formulas.len() {
+        return formulas;
+    }
+    formulas
+        .into_iter()
+        .zip(typed)
+        .map(|(f, typed)| {
+            let typed = typed.trim_start_matches(|c: char| c.is_ascii_digit());
+            if normalize(typed) == f {
+                typed.to_string()
+            } else {
+                f
+            }
+        })
+        .collect()
+}
+
+/// Whether a compound is an electron, which is a compound of no elements as
+/// chem-eq can't parse one
+pub fn is_electron(cmp: &Compound) -> bool {
+    cmp.elements.is_empty()
+}
+
+/// Count how many of each element are in one unit of a compound
+pub fn element_counts(cmp: &Compound) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for el in &cmp.elements {
+        *counts.entry(el.symbol()).or_default() += el.count;
+    }
+    counts
+}
+
+/// The size of an equation: how many compounds are on each side and how many
+/// different elements are in it. Electrons aren't counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub reactants: usize,
+    pub products: usize,
+    pub elements: usize,
+}
+
+/// Count the compounds and elements in an equation, with the reactants on the
+/// right when it's written backwards
+pub fn counts(eq: &Equation) -> Counts {
+    let compounds = |side: &[Compound]| side.iter().filter(|c| !is_electron(c)).count();
+    let (left, right) = (compounds(eq.left()), compounds(eq.right()));
+    let (reactants, products) = match eq.direction() {
+        Direction::Left => (right, left),
+        _ => (left, right),
+    };
+    let elements = eq
+        .iter_compounds()
+        .flat_map(|c| c.elements.iter().map(|e| e.symbol()))
+        .collect::<BTreeSet<_>>()
+        .len();
+    Counts {
+        reactants,
+        products,
+        elements,
+    }
+}
+
+/// Get the molar mass of a compound in g/mol
+pub fn molar_mass(cmp: &Compound) -> f64 {
+    // folded from 0 so an electron weighs 0 rather than -0
+    cmp.elements
+        .iter()
+        .fold(0.0, |mass, e| mass + Info::from(**e).mass * e.count as f64)
+}
+
+/// Write an equation the same way however it was typed, to tell when two are
+/// the same reaction. Each side's compounds are sorted, coefficients are
+/// divided down as far as they go and a reaction written right to left is
+/// turned around, so `O2 + 2H2 = 2H2O` and `2H2 + O2 -> 2H2O` are both
+/// `2H2 + O2 -> 2H2O`
+pub fn canonicalize(eq: &Equation) -> String {
+    let divisor = eq
+        .iter_compounds()
+        .fold(0, |a, c| gcd(a, c.coefficient as i128))
+        .max(1) as usize;
+    let mut compounds = eq.iter_compounds().zip(formulas(eq)).map(|(c, formula)| {
+        let coefficient = c.coefficient / divisor;
+        (formula, coefficient)
+    });
+    let mut side = |n: usize| {
+        let mut side: Vec<_> = compounds.by_ref().take(n).collect();
+        side.sort();
+        side.into_iter()
+            .map(|(formula, coefficient)| match coefficient {
+                1 => formula,
+                n => format!("{n}{formula}"),
+            })
+            .collect::<Vec<_>>()
+            .join(" + ")
+    };
+    let left = side(eq.left().len());
+    let right = side(eq.right().len());
+    match eq.direction() {
+        Direction::Right => format!("{left} -> {right}"),
+        Direction::Left => format!("{right} -> {left}"),
+        // either side could be first
+        Direction::Reversible => {
+            let (first, second) = if left <= right {
+                (left, right)
+            } else {
+                (right, left)
+            };
+            format!("{first} <-> {second}")
+        }
+    }
+}
+
+/// Swap the sides of an equation as it was typed, keeping its coefficients,
+/// charges and states. The arrow stays pointing the same way, so the reaction
+/// goes the other way round unless it's reversible. `None` if it can't be parsed
+///
+/// Eg: `2H2 + O2 -> 2H2O` becomes `2H2O -> 2H2 + O2`
+pub fn reverse(input: &str) -> Option<String> {
+    let eq = balance::parse(input).ok()?;
+    let mut compounds = eq
+        .iter_compounds()
+        .zip(typed_formulas(input, &eq))
+        .map(|(c, formula)| match c.coefficient {
+            1 => formula,
+            n => format!("{n}{formula}"),
+        });
+    let left: Vec<String> = compounds.by_ref().take(eq.left().len()).collect();
+    let right: Vec<String> = compounds.collect();
+    Some(format!(
+        "{} {} {}",
+        right.join(" + "),
+        eq.direction(),
+        left.join(" + ")
+    ))
+}
+
+/// Elements stood in for symbols that aren't elements by [`stand_in_unknown`].
+/// They're too heavy to be in a real equation, and their symbols aren't words
+const STAND_INS: &[&str] = &[
+    "Og", "Ts", "Lv", "Mc", "Fl", "Nh", "Cn", "Rg", "Ds", "Mt", "Hs", "Bh", "Sg", "Db", "Rf", "Lr",
+];
+
+/// A symbol typed in a formula that isn't on the periodic table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownElement {
+    pub symbol: String,
+    /// Which compound it's in, counting from 1 across both sides
+    pub compound: usize,
+    /// Where it is in the input, in bytes
+    pub range: Range<usize>,
+}
+
+/// Find the first symbol in `input` that isn't an element, eg. `Xz` in
+/// `Xz2O + H2`. A symbol is a capital and the lowercase letters after it, so
+/// states and electrons aren't mistaken for one
+pub fn unknown_element(input: &str) -> Option<UnknownElement> {
+    input
+        .char_indices()
+        .filter(|(_, c)| c.is_ascii_uppercase())
+        .map(|(start, _)| {
+            let len = input[start + 1..]
+                .find(|c: char| !c.is_ascii_lowercase())
+                .map_or(input.len() - start, |n| n + 1);
+            start..start + len
+        })
+        .find(|range| !mendeleev::Element::iter().any(|el| el.symbol() == &input[range.clone()]))
+        .map(|range| UnknownElement {
+            symbol: input[range.clone()].to_string(),
+            compound: charge::compounds(&tidy(&input[..range.end])).len(),
+            range,
+        })
+}
+
+/// Put an element that's never in a real equation in place of each symbol
+/// that isn't an element, eg. `M` in `M + O2 -> MO`, so it can be balanced.
+/// Returns the new input with each stand-in and the symbol it replaced, to put
+/// back with [`restore_unknown`]
+pub fn stand_in_unknown(input: &str) -> (String, Vec<(&'static str, String)>) {
+    let mut out = input.to_string();
+    let mut free = STAND_INS.iter().filter(|s| !input.contains(*s));
+    let mut stand_ins: Vec<(&'static str, String)> = Vec::new();
+    while let Some(unknown) = unknown_element(&out) {
+        let stand_in = match stand_ins.iter().find(|(_, s)| *s == unknown.symbol) {
+            Some((stand_in, _)) => *stand_in,
+            None => match free.next() {
+                Some(stand_in) => {
+                    stand_ins.push((stand_in, unknown.symbol));
+                    stand_in
+                }
+                // far more than any real equation has, it's left to fail
+                None => break,
+            },
+        };
+        out.replace_range(unknown.range, stand_in);
+    }
+    (out, stand_ins)
+}
+
+/// Put back the symbols [`stand_in_unknown`] replaced in text written from
+/// the equation it made
+pub fn restore_unknown(text: &str, stand_ins: &[(&'static str, String)]) -> String {
+    stand_ins
+        .iter()
+        .fold(text.to_string(), |text, (stand_in, symbol)| {
+            text.replace(stand_in, symbol)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(input: &str) -> String {
+        canonicalize(&Equation::new(&normalize(input)).unwrap())
+    }
+
+    #[test]
+    fn canonical_forms() {
+        assert_eq!(canonical("O2 + 2H2 = 2H2O"), canonical("2H2 + O2 = 2H2O"));
+        assert_eq!(canonical("O2 + 2H2 = 2H2O"), "2H2 + O2 -> 2H2O");
+        // spacing, arrows and which way round it's written
+        assert_eq!(canonical("  O2+2H2 → 2H2O"), "2H2 + O2 -> 2H2O");
+        assert_eq!(canonical("2H2O <- O2 + 2H2"), "2H2 + O2 -> 2H2O");
+        assert_eq!(canonical("4H2 + 2O2 -> 4H2O"), "2H2 + O2 -> 2H2O");
+        assert_eq!(canonical("NH3 <-> N2 + 3H2"), canonical("3H2 + N2 <-> NH3"));
+        // different reactions stay different
+        assert_ne!(canonical("H2 + O2 -> H2O"), canonical("2H2 + O2 -> 2H2O"));
+        assert_ne!(canonical("H2O -> H2 + O2"), canonical("H2 + O2 -> H2O"));
+    }
+
+    #[test]
+    fn counting() {
+        let counts = |input| counts(&balance::parse(input).unwrap());
+        assert_eq!(
+            counts("H2 + O2 -> H2O"),
+            Counts {
+                reactants: 2,
+                products: 1,
+                elements: 2,
+            }
+        );
+        assert_eq!(
+            counts("H2O <- H2 + O2"),
+            Counts {
+                reactants: 2,
+                products: 1,
+                elements: 2,
+            }
+        );
+        // electrons are neither compounds nor elements
+        assert_eq!(
+            counts("Cu -> Cu^2+ + 2e-"),
+            Counts {
+                reactants: 1,
+                products: 1,
+                elements: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_elements() {
+        assert_eq!(
+            unknown_element("H2 + O2 -> Xz2O"),
+            Some(UnknownElement {
+                symbol: "Xz".to_string(),
+                compound: 3,
+                range: 11..13,
+            })
+        );
+        assert_eq!(
+            unknown_element("NaCL(aq)").map(|u| u.symbol),
+            Some("L".to_string())
+        );
+        // states, electrons and lowercase typos are left alone
+        assert_eq!(unknown_element("Fe^3+ + e^- -> Fe^2+(aq)"), None);
+        assert_eq!(unknown_element("na + cl2 -> nacl"), None);
+    }
+
+    #[test]
+    fn stand_ins() {
+        let (input, stand_ins) = stand_in_unknown("M + HX -> MX2 + H2");
+        assert_eq!(input, "Og + HTs -> OgTs2 + H2");
+        let eq = balance::balance(&input).unwrap();
+        assert_eq!(
+            restore_unknown(eq.equation(), &stand_ins),
+            "M + 2HX -> MX2 + H2"
+        );
+        // ones already typed aren't used
+        let (input, _) = stand_in_unknown("Og + M");
+        assert_eq!(input, "Og + Ts");
+    }
+
+    #[test]
+    fn reversed() {
+        assert_eq!(
+            reverse("2H2 + O2 -> 2H2O").as_deref(),
+            Some("2H2O -> 2H2 + O2")
+        );
+        assert_eq!(
+            reverse("  CaCO3(s) → CaO(s) +   CO2(g)").as_deref(),
+            Some("CaO(s) + CO2(g) -> CaCO3(s)")
+        );
+        assert_eq!(
+            reverse("N2 + 3H2 <-> 2NH3").as_deref(),
+            Some("2NH3 <-> N2 + 3H2")
+        );
+        assert_eq!(
+            reverse("Cu + 2Ag^+ -> Cu^2+ + 2Ag").as_deref(),
+            Some("Cu^2+ + 2Ag -> Cu + 2Ag^+")
+        );
+        assert_eq!(reverse("H2 + O2"), None);
+    }
+}
@@ -0,0 +1,296 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chem_eq::{Compound, Direction, Equation};
+use serde::Serialize;
+
+use crate::{
+    charge,
+    chem::{element_counts, is_electron},
+    normalize::normalize,
+};
+
+/// Elements that aren't metals, everything else is treated as one
+const NONMETALS: &[&str] = &[
+    "H", "He", "B", "C", "N", "O", "F", "Ne", "Si", "P", "S", "Cl", "Ar", "As", "Se", "Br", "Kr",
+    "Te", "I", "Xe", "At", "Rn",
+];
+
+/// The kind of reaction an equation is, by the shape of its compounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionType {
+    /// Two or more reactants make one product, eg. `2H2 + O2 -> 2H2O`
+    Synthesis,
+    /// One reactant breaks down into several, eg. `2H2O2 -> 2H2O + O2`
+    Decomposition,
+    /// An element takes another's place in a compound, eg. `Zn + 2HCl -> ZnCl2 + H2`
+    SingleReplacement,
+    /// Two compounds swap partners, eg. `AgNO3 + NaCl -> AgCl + NaNO3`
+    DoubleReplacement,
+    /// A hydrocarbon burns in oxygen, eg. `CH4 + 2O2 -> CO2 + 2H2O`
+    Combustion,
+    /// An acid and a base make a salt and water, eg. `HCl + NaOH -> NaCl + H2O`
+    Neutralization,
+    /// Electrons are gained or lost, eg. `Fe^3+ + e^- -> Fe^2+`
+    HalfReaction,
+    /// None of the above, or more than one could be meant
+    Unclassified,
+}
+
+impl fmt::Display for ReactionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Synthesis => "synthesis",
+            Self::Decomposition => "decomposition",
+            Self::SingleReplacement => "single replacement",
+            Self::DoubleReplacement => "double replacement",
+            Self::Combustion => "combustion",
+            Self::Neutralization => "neutralization",
+            Self::HalfReaction => "half-reaction",
+            Self::Unclassified => "unclassified",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Work out what kind of reaction an equation is. Coefficients don't matter,
+/// so it can be balanced or not. Combustion and neutralization are checked
+/// first, as they'd otherwise look like replacements
+pub fn classify(eq: &Equation) -> ReactionType {
+    if eq.iter_compounds().any(is_electron) {
+        return ReactionType::HalfReaction;
+    }
+    let (reactants, products) = match eq.direction() {
+        Direction::Left => (eq.right(), eq.left()),
+        _ => (eq.left(), eq.right()),
+    };
+    let reactants: Vec<_> = reactants.iter().map(element_counts).collect();
+    let products: Vec<_> = products.iter().map(element_counts).collect();
+
+    if is_combustion(&reactants, &products) {
+        return ReactionType::Combustion;
+    }
+    if is_neutralization(&reactants, &products) {
+        return ReactionType::Neutralization;
+    }
+    let free = |side: &[Counts]| side.iter().filter(|c| c.len() == 1).count();
+    match (reactants.as_slice(), products.as_slice()) {
+        ([_, _, ..], [_]) => ReactionType::Synthesis,
+        ([_], [_, _, ..]) => ReactionType::Decomposition,
+        ([_, _], [_, _]) => match (free(&reactants), free(&products)) {
+            // the element that went in has to be a different one to what came out
+            (1, 1)
+                if !reactants
+                    .iter()
+                    .any(|r| r.len() == 1 && products.contains(r)) =>
+            {
+                ReactionType::SingleReplacement
+            }
+            (0, 0) if swapped(&reactants, &products) => ReactionType::DoubleReplacement,
+            _ => ReactionType::Unclassified,
+        },
+        _ => ReactionType::Unclassified,
+    }
+}
+
+/// How many of each element are in a compound
+type Counts = BTreeMap<&'static str, usize>;
+
+fn is(counts: &Counts, formula: &[(&str, usize)]) -> bool {
+    counts.len() == formula.len() && formula.iter().all(|(e, n)| counts.get(e) == Some(n))
+}
+
+fn is_water(counts: &Counts) -> bool {
+    is(counts, &[("H", 2), ("O", 1)])
+}
+
+fn has_metal(counts: &Counts) -> bool {
+    counts.keys().any(|e| !NONMETALS.contains(e))
+}
+
+/// A hydrocarbon, or a carbohydrate or alcohol, and oxygen make carbon
+/// dioxide and water
+fn is_combustion(reactants: &[Counts], products: &[Counts]) -> bool {
+    let fuel = |c: &Counts| {
+        c.contains_key("C")
+            && c.contains_key("H")
+            && c.keys().all(|e| matches!(*e, "C" | "H" | "O"))
+    };
+    let oxygen = |c: &Counts| is(c, &[("O", 2)]);
+    let burnt = |c: &Counts| is(c, &[("C", 1), ("O", 2)]) || is_water(c);
+    matches!(reactants, [a, b] if (fuel(a) && oxygen(b)) || (oxygen(a) && fuel(b)))
+        && products.iter().all(burnt)
+        && products.iter().any(is_water)
+        && !products.iter().all(is_water)
+}
+
+/// What an equation with only its reactants can be finished as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    /// A fuel burning in oxygen makes carbon dioxide and water
+    Combustion,
+}
+
+impl Completion {
+    /// Write the products of `input`, if its reactants fit. `None` otherwise
+    pub fn complete(self, input: &str) -> Option<String> {
+        match self {
+            Self::Combustion => complete_combustion(input),
+        }
+    }
+}
+
+impl FromStr for Completion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "combustion" => Ok(Self::Combustion),
+            _ => Err(format!("unknown completion {s:?}, expected combustion")),
+        }
+    }
+}
+
+/// Finish a fuel and oxygen with nothing after the arrow, or no arrow at all,
+/// as burning to carbon dioxide and water, eg. `CH4 + O2 ->` becomes
+/// `CH4 + O2 -> CO2 + H2O`. `None` unless the reactants are exactly that
+pub fn complete_combustion(input: &str) -> Option<String> {
+    let normalized = normalize(input);
+    let trimmed = normalized.trim();
+    let reactants = trimmed.strip_suffix("->").unwrap_or(trimmed).trim_end();
+    if reactants.contains("->") || reactants.contains("<-") {
+        return None;
+    }
+    let counts = reactants
+        .split('+')
+        .map(|c| {
+            let (formula, _) =
+                charge::split_state(c.trim().trim_start_matches(|c: char| c.is_ascii_digit()));
+            Compound::parse(formula).ok().map(|c| element_counts(&c))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let products = [
+        Counts::from([("C", 1), ("O", 2)]),
+        Counts::from([("H", 2), ("O", 1)]),
+    ];
+    is_combustion(&counts, &products).then(|| format!("{reactants} -> CO2 + H2O"))
+}
+
+/// An acid and a hydroxide make a salt and water
+fn is_neutralization(reactants: &[Counts], products: &[Counts]) -> bool {
+    // hydrogen and nonmetals, like `HCl` or `H2SO4`
+    let acid = |c: &Counts| c.contains_key("H") && !has_metal(c) && !is_water(c) && c.len() > 1;
+    // a metal with as many oxygens as hydrogens, like `NaOH` or `Ca(OH)2`
+    let base = |c: &Counts| {
+        has_metal(c)
+            && c.get("O").is_some_and(|o| c.get("H") == Some(o))
+            && c.keys().filter(|e| !NONMETALS.contains(e)).count() == c.len() - 2
+    };
+    let salt = |c: &Counts| has_metal(c) && !base(c);
+    matches!(reactants, [a, b] if (acid(a) && base(b)) || (base(a) && acid(b)))
+        && matches!(products, [a, b] if (salt(a) && is_water(b)) || (is_water(a) && salt(b)))
+}
+
+/// Each product has something only the first reactant had and something only
+/// the second had, as when `AB + CD` makes `AD + CB`
+fn swapped(reactants: &[Counts], products: &[Counts]) -> bool {
+    let [first, second] = reactants else {
+        return false;
+    };
+    let only = |a: &Counts, b: &Counts, product: &Counts| {
+        product
+            .keys()
+            .any(|e| a.contains_key(e) && !b.contains_key(e))
+    };
+    products
+        .iter()
+        .all(|p| only(first, second, p) && only(second, first, p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalize::normalize;
+
+    #[test]
+    fn combustion() {
+        let cases = [
+            ("CH4 + O2 ->", Some("CH4 + O2 -> CO2 + H2O")),
+            ("O2 + C2H5OH", Some("O2 + C2H5OH -> CO2 + H2O")),
+            ("C3H8(g) + O2(g) =  ", Some("C3H8(g) + O2(g) -> CO2 + H2O")),
+            ("C6H12O6+O2→", Some("C6H12O6+O2 -> CO2 + H2O")),
+            // already has products, or isn't a fuel and oxygen
+            ("CH4 + O2 -> CO2", None),
+            ("C + O2 ->", None),
+            ("H2 + O2 ->", None),
+            ("CH4 + Cl2 ->", None),
+            ("CH4 + O2 + N2 ->", None),
+            ("CH4 ->", None),
+            ("", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(complete_combustion(input).as_deref(), expected, "{input}");
+        }
+        assert_eq!("combustion".parse(), Ok(Completion::Combustion));
+        assert!("fire".parse::<Completion>().is_err());
+    }
+
+    #[test]
+    fn reactions() {
+        use ReactionType::*;
+        let cases = [
+            ("H2 + O2 -> H2O", Synthesis),
+            ("Na + Cl2 -> NaCl", Synthesis),
+            ("Fe + O2 -> Fe2O3", Synthesis),
+            ("N2 + H2 -> NH3", Synthesis),
+            ("CaO + H2O -> Ca(OH)2", Synthesis),
+            ("C + O2 -> CO2", Synthesis),
+            ("H2O2 -> H2O + O2", Decomposition),
+            ("CaCO3 -> CaO + CO2", Decomposition),
+            ("KClO3 -> KCl + O2", Decomposition),
+            ("H2O -> H2 + O2", Decomposition),
+            ("NaHCO3 -> Na2CO3 + H2O + CO2", Decomposition),
+            ("Zn + HCl -> ZnCl2 + H2", SingleReplacement),
+            ("Fe + CuSO4 -> FeSO4 + Cu", SingleReplacement),
+            ("Cl2 + NaBr -> NaCl + Br2", SingleReplacement),
+            ("Mg + H2O -> Mg(OH)2 + H2", SingleReplacement),
+            ("Cu + AgNO3 -> Cu(NO3)2 + Ag", SingleReplacement),
+            ("AgNO3 + NaCl -> AgCl + NaNO3", DoubleReplacement),
+            ("BaCl2 + Na2SO4 -> BaSO4 + NaCl", DoubleReplacement),
+            ("Pb(NO3)2 + KI -> PbI2 + KNO3", DoubleReplacement),
+            ("FeCl3 + NaOH -> Fe(OH)3 + NaCl", DoubleReplacement),
+            ("CH4 + O2 -> CO2 + H2O", Combustion),
+            ("C3H8 + O2 -> CO2 + H2O", Combustion),
+            ("O2 + C6H12O6 -> CO2 + H2O", Combustion),
+            ("C2H6O + O2 -> CO2 + H2O", Combustion),
+            ("HCl + NaOH -> NaCl + H2O", Neutralization),
+            ("H2SO4 + KOH -> K2SO4 + H2O", Neutralization),
+            ("Ca(OH)2 + HNO3 -> Ca(NO3)2 + H2O", Neutralization),
+            // written backwards
+            ("H2O <- H2 + O2", Synthesis),
+            ("CH4 + Cl2 -> CH3Cl + HCl", Unclassified),
+            ("Fe2O3 + CO -> Fe + CO2", Unclassified),
+            ("Cu + HNO3 -> Cu(NO3)2 + NO + H2O", Unclassified),
+            ("KMnO4 + HCl -> KCl + MnCl2 + Cl2 + H2O", Unclassified),
+            ("N2 + O2 <-> N2 + O2", Unclassified),
+        ];
+        for (input, expected) in cases {
+            let eq = Equation::new(&normalize(input)).unwrap();
+            assert_eq!(classify(&eq), expected, "{input}");
+        }
+        let eq = crate::balance::parse("Fe^3+ + e^- -> Fe^2+").unwrap();
+        assert_eq!(classify(&eq), HalfReaction);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            ReactionType::SingleReplacement.to_string(),
+            "single replacement"
+        );
+        assert_eq!(
+            serde_json::to_string(&ReactionType::DoubleReplacement).unwrap(),
+            r#""double_replacement""#
+        );
+    }
+}
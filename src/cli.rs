@@ -1,12 +1,188 @@
-use bpaf::Bpaf;
-use chem_eq::Equation;
+use std::{ffi::OsString, path::PathBuf};
+
+use balance_tui::{
+    classify::Completion,
+    format::Format,
+    redox::Medium,
+    stoich::{Conversion, Given, Givens, Pair},
+};
+use bpaf::{Args, Bpaf, ParseFailure};
 
 /// Balance a chemical equation.
 ///
 /// Run without args to open a tui.
+///
+/// Exits with 2 if the equation couldn't be parsed, 3 if it couldn't be balanced
+/// and 1 for any other error. With --verify, 4 if the coefficients balance it but
+/// can be made smaller and 5 if they don't balance it.
 #[derive(Debug, Clone, Bpaf)]
 #[bpaf(version, options)]
 pub struct ChemArgs {
-    #[bpaf(positional, optional)]
-    pub equation: Option<Equation>,
+    /// Balance every equation in a file, one per line. Use `-` to read stdin
+    #[bpaf(short, long, argument("PATH"))]
+    pub input: Option<PathBuf>,
+    /// Balance each line of stdin as it's read and answer with a line of json,
+    /// until stdin is closed. For editors and other programs to talk to
+    pub serve: bool,
+    /// Balance every equation in a file, then again each time it's saved until ctrl-c
+    #[bpaf(argument("PATH"))]
+    pub watch: Option<PathBuf>,
+    /// Maximum number of threads used to balance `--input` or `--watch`
+    #[bpaf(
+        short,
+        long,
+        argument("N"),
+        guard(at_least_one, "there has to be at least one job"),
+        optional
+    )]
+    pub jobs: Option<usize>,
+    /// How to print results: plain, json, markdown or csv
+    #[bpaf(argument("FORMAT"), fallback(Format::Plain))]
+    pub format: Format,
+    /// Print results as json, one object per line. Same as `--format json`
+    pub json: bool,
+    /// Only check that equations can be balanced. Prints nothing on success
+    pub check: bool,
+    /// Check the coefficients EQUATION is written with instead of balancing it,
+    /// and print whether they balance it in lowest terms
+    pub verify: bool,
+    /// Swap the reactants and products of EQUATION before balancing it
+    pub reverse: bool,
+    /// Write the products of EQUATION when it only has reactants. `combustion`
+    /// finishes a fuel and O2 as making CO2 and H2O
+    #[bpaf(argument("KIND"))]
+    pub complete: Option<Completion>,
+    /// Balance EQUATION even with symbols that aren't elements, like M or X
+    /// standing in for any metal or halogen. They're balanced as elements of
+    /// their own and written back into the balanced equation
+    pub allow_unknown_elements: bool,
+    /// With --input, print each reaction once however many ways it's written
+    pub unique: bool,
+    /// Balance redox equations by half-reactions in an acidic or basic solution,
+    /// adding the water, H^+ or OH^- and electrons they need
+    #[bpaf(argument("MEDIUM"))]
+    pub redox: Option<Medium>,
+    /// With --redox, print the two half-reactions before the equation
+    pub half_reactions: bool,
+    /// Write the coefficients as fractions, with one mole of the first reactant,
+    /// eg. `C2H6 + 7/2 O2 -> 2CO2 + 3H2O`
+    pub fractional: bool,
+    /// With --fractional, have one mole of FORMULA instead of the first reactant
+    #[bpaf(argument("FORMULA"))]
+    pub per: Option<String>,
+    /// Print the equilibrium constant expression of a reversible equation,
+    /// as text and as LaTeX
+    pub kc: bool,
+    /// Print the net ionic equation, with the strong electrolytes in solution
+    /// split into ions and the spectator ions taken out
+    pub net_ionic: bool,
+    /// Print the oxidation state of each element after the equation, and which
+    /// were oxidized and reduced
+    pub oxidation: bool,
+    /// Print how much of every compound reacts or is made, given an amount of
+    /// one of them in grams or moles, like `H2=4g` or `O2=0.5mol`
+    #[bpaf(argument("GIVEN"))]
+    pub given: Option<Given>,
+    /// Print which reactant runs out first, how much of everything reacts or
+    /// is made and what's left over, given amounts of the reactants like
+    /// `H2=10g,O2=10g`
+    #[bpaf(argument("AMOUNTS"))]
+    pub amounts: Option<Givens>,
+    /// With --given or --amounts, how much of a product was actually made,
+    /// like `H2O=9g`, to print the theoretical and percent yield
+    #[bpaf(argument("GIVEN"))]
+    pub actual: Option<Given>,
+    /// Print the simplest whole number mole ratio of two compounds in the
+    /// balanced equation, like `H2:H2O`
+    #[bpaf(argument("A:B"))]
+    pub ratio: Option<Pair>,
+    /// Convert grams of a compound to moles or moles to grams and exit, eg.
+    /// `18 g H2O` or `0.5 mol O2`
+    #[bpaf(argument("AMOUNT"))]
+    pub convert: Option<Conversion>,
+    /// Print how much of the mass of FORMULA is each element, in percent, and exit
+    #[bpaf(argument("FORMULA"))]
+    pub percent_composition: Option<String>,
+    /// Copy with the terminal's OSC 52 escape sequence instead of the system clipboard.
+    /// Used automatically when the system clipboard isn't available
+    pub osc52: bool,
+    /// Open EQUATION in the tui instead of printing it
+    pub tui: bool,
+    /// Start the tui with the equation on the clipboard
+    pub paste: bool,
+    /// Practice balancing well known reactions by hand in the tui
+    pub quiz: bool,
+    /// Open the tui where the session saved with ctrl-s left off
+    pub resume: bool,
+    /// Write a markdown report of each equation, balanced as text and LaTeX with
+    /// the molar masses, and exit. Reports the equations from --input,
+    /// EQUATION with any --given or --amounts, or else the saved session
+    #[bpaf(argument("PATH"))]
+    pub export: Option<PathBuf>,
+    /// Don't print the last balanced equation when the tui exits
+    pub no_print_on_exit: bool,
+    /// Append each equation balanced and what happened to it to a log file
+    #[bpaf(argument("PATH"))]
+    pub log: Option<PathBuf>,
+    /// Show how long each equation took to balance in the tui
+    pub debug: bool,
+    /// Start the tui in editing mode, ready to type an equation
+    #[bpaf(short, long)]
+    pub edit: bool,
+    #[bpaf(positional("EQUATION"), optional)]
+    pub equation: Option<String>,
+}
+
+/// Parse the command line, printing help or an error and exiting like
+/// `chem_args().run()` would
+pub fn parse() -> ChemArgs {
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    match parse_from(&args) {
+        Ok(args) => args,
+        Err(ParseFailure::Stdout(msg)) => {
+            print!("{msg}");
+            std::process::exit(0);
+        }
+        Err(ParseFailure::Stderr(msg)) => {
+            eprintln!("{msg}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// bpaf only reports a bad value like `--format weird` when nothing's left
+/// unparsed in front of it, so with EQUATION first it blames EQUATION as an
+/// unknown command instead. Parsing again with that word last gets the real
+/// error, whatever order the arguments came in
+fn parse_from(args: &[OsString]) -> Result<ChemArgs, ParseFailure> {
+    let err = match chem_args().run_inner(Args::from(args)) {
+        Err(ParseFailure::Stderr(msg)) => msg,
+        res => return res,
+    };
+    let word = match unknown_command(&err) {
+        Some(word) => word,
+        None => return Err(ParseFailure::Stderr(err)),
+    };
+    let mut moved: Vec<OsString> = args.iter().filter(|a| **a != *word).cloned().collect();
+    if moved.len() + 1 != args.len() {
+        return Err(ParseFailure::Stderr(err));
+    }
+    moved.push(word.into());
+    match chem_args().run_inner(Args::from(moved.as_slice())) {
+        Err(ParseFailure::Stderr(msg)) if unknown_command(&msg).is_some() => {
+            Err(ParseFailure::Stderr(err))
+        }
+        res => res,
+    }
+}
+
+/// The word in bpaf's "No such command: `word`" error
+fn unknown_command(err: &str) -> Option<&str> {
+    err.strip_prefix("No such command: `")?
+        .split_once('`')
+        .map(|(word, _)| word)
+}
+
+fn at_least_one(jobs: &usize) -> bool {
+    *jobs > 0
 }
@@ -0,0 +1,187 @@
+use std::{
+    env, fmt,
+    io::{self, Write},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Most terminals ignore OSC 52 sequences longer than this
+const OSC52_MAX_LEN: usize = 100_000;
+
+/// Copies text to the system clipboard, falling back to the terminal's
+/// clipboard with an OSC 52 escape sequence. The fallback is what makes
+/// copying work over ssh.
+#[derive(Default)]
+pub struct Clipboard {
+    /// Connected on first use, as connecting fails on headless machines
+    system: Option<arboard::Clipboard>,
+    /// Skip the system clipboard and always use OSC 52
+    force_osc52: bool,
+    /// Copy to the X11 primary selection, for pasting with middle click
+    #[cfg_attr(
+        not(all(
+            unix,
+            not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+        )),
+        allow(dead_code)
+    )]
+    primary: bool,
+}
+
+/// How text was copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    System,
+    Primary,
+    Osc52,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::System => write!(f, "system clipboard"),
+            Self::Primary => write!(f, "primary selection"),
+            Self::Osc52 => write!(f, "OSC 52"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CopyError {
+    /// The text is too long to send with OSC 52
+    TooLarge(usize),
+    Io(io::Error),
+}
+
+impl fmt::Display for CopyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge(len) => write!(
+                f,
+                "{len} bytes is too large for OSC 52 (max {OSC52_MAX_LEN} bytes)"
+            ),
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<io::Error> for CopyError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl Clipboard {
+    /// With `primary`, copy to the primary selection instead of the clipboard
+    /// where there is one. It's ignored on windows and macos
+    pub fn new(force_osc52: bool, primary: bool) -> Self {
+        Self {
+            system: None,
+            force_osc52,
+            primary,
+        }
+    }
+
+    /// Copy text, returning which backend was used
+    pub fn copy(&mut self, text: &str) -> Result<Backend, CopyError> {
+        if !self.force_osc52 {
+            #[cfg(all(
+                unix,
+                not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+            ))]
+            if self.primary && self.copy_primary(text).is_ok() {
+                return Ok(Backend::Primary);
+            }
+            if self.copy_system(text).is_ok() {
+                return Ok(Backend::System);
+            }
+        }
+        let mut stdout = io::stdout();
+        stdout.write_all(osc52_sequence(text, env::var_os("TMUX").is_some())?.as_bytes())?;
+        stdout.flush()?;
+        Ok(Backend::Osc52)
+    }
+
+    /// Get the first line of text on the system clipboard. Few terminals allow
+    /// reading their clipboard with OSC 52, so there is no fallback
+    pub fn paste(&mut self) -> Option<String> {
+        let text = self.system().ok()?.get_text().ok()?;
+        text.lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .map(str::to_string)
+    }
+
+    fn copy_system(&mut self, text: &str) -> Result<(), arboard::Error> {
+        self.system()?.set_text(text)
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    fn copy_primary(&mut self, text: &str) -> Result<(), arboard::Error> {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+
+        self.system()?
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text)
+    }
+
+    fn system(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        if self.system.is_none() {
+            self.system = Some(arboard::Clipboard::new()?);
+        }
+        Ok(self.system.as_mut().expect("connected above"))
+    }
+}
+
+/// Build the escape sequence asking the terminal to set its clipboard. Inside
+/// tmux the sequence is wrapped so tmux passes it through to the outer terminal.
+fn osc52_sequence(text: &str, tmux: bool) -> Result<String, CopyError> {
+    let encoded = STANDARD.encode(text);
+    if encoded.len() > OSC52_MAX_LEN {
+        return Err(CopyError::TooLarge(encoded.len()));
+    }
+    let seq = format!("\x1b]52;c;{encoded}\x07");
+    if tmux {
+        // escapes inside the passthrough have to be doubled
+        Ok(format!(
+            "\x1bPtmux;{}\x1b\\",
+            seq.replace('\x1b', "\x1b\x1b")
+        ))
+    } else {
+        Ok(seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_plain() {
+        assert_eq!(
+            osc52_sequence("2H2 + O2 -> 2H2O", false).unwrap(),
+            "\x1b]52;c;MkgyICsgTzIgLT4gMkgyTw==\x07"
+        );
+    }
+
+    #[test]
+    fn osc52_tmux() {
+        assert_eq!(
+            osc52_sequence("2H2 + O2 -> 2H2O", true).unwrap(),
+            "\x1bPtmux;\x1b\x1b]52;c;MkgyICsgTzIgLT4gMkgyTw==\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn osc52_too_large() {
+        let text = "H".repeat(OSC52_MAX_LEN);
+        assert!(matches!(
+            osc52_sequence(&text, false),
+            Err(CopyError::TooLarge(_))
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+use std::{fmt, fs, io, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{app::DEFAULT_MAX_INPUT, theme::Theme};
+
+/// Settings read from `config.toml` in the user's config directory. Anything
+/// missing, including the file itself, uses the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub history: HistoryConfig,
+    pub tui: TuiConfig,
+    pub clipboard: ClipboardConfig,
+    pub session: SessionConfig,
+}
+
+/// ```toml
+/// [session]
+/// # open the session saved with ctrl-s when the tui starts, same as `--resume`
+/// restore = false
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    pub restore: bool,
+}
+
+/// ```toml
+/// [clipboard]
+/// # copy to the primary selection (middle click paste) on linux and bsd
+/// primary_selection = false
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    pub primary_selection: bool,
+}
+
+/// ```toml
+/// [history]
+/// # save equations between sessions
+/// enabled = true
+/// # most equations to remember, 0 remembers none
+/// max_entries = 100
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    pub max_entries: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 100,
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// Whether history is read from and written to disk
+    pub const fn persist(&self) -> bool {
+        self.enabled && self.max_entries > 0
+    }
+}
+
+/// ```toml
+/// [tui]
+/// # how often the tui updates without input, in milliseconds
+/// tick_rate_ms = 250
+/// # longest equation that can be typed or pasted, in characters
+/// max_input_len = 2000
+/// # press q twice to quit when an equation is typed in
+/// confirm_quit = true
+/// # write coefficients of 1, eg. `2H2 + 1O2 -> 2H2O`, toggled with `o`
+/// explicit_ones = false
+/// # show how long balancing took in the status line, same as `--debug`
+/// show_timing = false
+/// # fix the capitals of element symbols while typing, eg. `na` to `Na`
+/// auto_capitalize = false
+/// # show the keys at the bottom of the screen, toggled with `?`
+/// show_help = true
+/// # write the numbers in formulas as subscripts, eg. `2H₂ + O₂ -> 2H₂O`
+/// subscripts = false
+/// # put the last balanced equation in the terminal's title
+/// set_title = true
+/// # colours for a "dark" or "light" background
+/// theme = "dark"
+/// ```
+///
+/// `confirm_quit`, `explicit_ones`, `show_help`, `subscripts` and `theme` can
+/// also be changed in the tui with `,`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TuiConfig {
+    pub tick_rate_ms: u64,
+    pub max_input_len: usize,
+    pub confirm_quit: bool,
+    pub explicit_ones: bool,
+    pub show_timing: bool,
+    pub auto_capitalize: bool,
+    pub show_help: bool,
+    pub subscripts: bool,
+    pub set_title: bool,
+    pub theme: Theme,
+}
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate_ms: 250,
+            max_input_len: DEFAULT_MAX_INPUT,
+            confirm_quit: true,
+            explicit_ones: false,
+            show_timing: false,
+            auto_capitalize: false,
+            show_help: true,
+            subscripts: false,
+            set_title: true,
+            theme: Theme::Dark,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    /// The file couldn't be read to write the settings into it
+    Edit(toml_edit::TomlError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Toml(e) => write!(f, "{e}"),
+            Self::Edit(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl TuiConfig {
+    /// Write the settings changed in the tui into the config file `text`,
+    /// keeping everything else in it as it was, comments included
+    pub fn write_settings(&self, text: &str) -> Result<String, ConfigError> {
+        let mut doc: toml_edit::DocumentMut = text.parse().map_err(ConfigError::Edit)?;
+        let tui = doc
+            .entry("tui")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(|| ConfigError::Io(io::Error::other("`tui` isn't a table")))?;
+        for (key, value) in [
+            ("confirm_quit", self.confirm_quit),
+            ("explicit_ones", self.explicit_ones),
+            ("show_help", self.show_help),
+            ("subscripts", self.subscripts),
+        ] {
+            tui.insert(key, toml_edit::value(value));
+        }
+        tui.insert("theme", toml_edit::value(self.theme.name()));
+        Ok(doc.to_string())
+    }
+
+    /// Save the settings changed in the tui to the config file, creating it
+    /// if there isn't one
+    pub fn save_settings(&self) -> Result<(), ConfigError> {
+        let Some(path) = Config::path() else {
+            return Err(ConfigError::Io(io::Error::other(
+                "there's no config directory",
+            )));
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(ConfigError::Io(e)),
+        };
+        let text = self.write_settings(&text)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(ConfigError::Io)?;
+        }
+        fs::write(path, text).map_err(ConfigError::Io)
+    }
+}
+
+impl Config {
+    /// Read the config file, or use the defaults if there isn't one
+    pub fn load() -> Result<Self, ConfigError> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::Io(e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(ConfigError::Toml)
+    }
+
+    /// Where the config file is, eg. `~/.config/balance-tui/config.toml`
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("balance-tui").join("config.toml"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn partial() {
+        let config = Config::parse("[history]\nmax_entries = 5").unwrap();
+        assert_eq!(
+            config.history,
+            HistoryConfig {
+                enabled: true,
+                max_entries: 5
+            }
+        );
+        assert!(config.history.persist());
+
+        let config = Config::parse("[history]\nmax_entries = 0").unwrap();
+        assert!(!config.history.persist());
+        let config = Config::parse("[history]\nenabled = false").unwrap();
+        assert!(!config.history.persist());
+    }
+
+    #[test]
+    fn tick_rate() {
+        let config = Config::parse("[tui]\ntick_rate_ms = 50").unwrap();
+        assert_eq!(config.tui.tick_rate_ms, 50);
+        assert_eq!(config.history, HistoryConfig::default());
+    }
+
+    #[test]
+    fn explicit_ones() {
+        assert!(!Config::default().tui.explicit_ones);
+        let config = Config::parse("[tui]\nexplicit_ones = true").unwrap();
+        assert!(config.tui.explicit_ones);
+    }
+
+    #[test]
+    fn clipboard() {
+        let config = Config::parse("[clipboard]\nprimary_selection = true").unwrap();
+        assert!(config.clipboard.primary_selection);
+        assert!(!Config::default().clipboard.primary_selection);
+    }
+
+    #[test]
+    fn session() {
+        let config = Config::parse("[session]\nrestore = true").unwrap();
+        assert!(config.session.restore);
+        assert!(!Config::default().session.restore);
+    }
+
+    #[test]
+    fn write_settings() {
+        let tui = TuiConfig {
+            confirm_quit: false,
+            subscripts: true,
+            theme: Theme::Light,
+            ..Default::default()
+        };
+        let text = "# mine\n[history]\nenabled = false\n\n[tui]\ntick_rate_ms = 50 # fast\nconfirm_quit = true\n";
+        let written = tui.write_settings(text).unwrap();
+        assert!(written.starts_with("# mine\n[history]\nenabled = false\n"));
+        assert!(written.contains("tick_rate_ms = 50 # fast\n"), "{written}");
+        let config = Config::parse(&written).unwrap();
+        assert!(!config.history.enabled);
+        assert_eq!(config.tui.tick_rate_ms, 50);
+        assert!(!config.tui.confirm_quit);
+        assert!(config.tui.subscripts);
+        assert!(config.tui.show_help);
+        assert_eq!(config.tui.theme, Theme::Light);
+
+        // there's no file yet
+        let config = Config::parse(&tui.write_settings("").unwrap()).unwrap();
+        assert_eq!(config.tui, tui);
+        assert!(tui.write_settings("tui = 1").is_err());
+        assert!(tui.write_settings("[tui").is_err());
+    }
+
+    #[test]
+    fn theme() {
+        assert_eq!(Config::default().tui.theme, Theme::Dark);
+        let config = Config::parse("[tui]\ntheme = \"light\"").unwrap();
+        assert_eq!(config.tui.theme, Theme::Light);
+        assert!(Config::parse("[tui]\ntheme = \"blue\"").is_err());
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert!(Config::parse("[history]\nmax = 5").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use chem_eq::Compound;
+
+use crate::{chem::element_counts, matrix::rank, normalize::normalize};
+
+/// Why an equation can't be balanced, in terms that say what to fix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// Elements that are only on one side of the arrow, which no coefficients can fix
+    OneSided {
+        left: Vec<&'static str>,
+        right: Vec<&'static str>,
+    },
+    /// Every element is on both sides, but there are more independent element
+    /// counts to match than compounds to scale, so only coefficients of 0 work
+    OverDetermined { elements: usize },
+}
+
+/// Work out why the compounds on each side of an equation can't be balanced.
+/// `None` when there's no reason this can find, and the balancer's error is all there is
+pub fn diagnose(left: &[Compound], right: &[Compound]) -> Option<Diagnosis> {
+    let left_elements = elements(left);
+    let right_elements = elements(right);
+    if left_elements != right_elements {
+        return Some(Diagnosis::OneSided {
+            left: left_elements.difference(&right_elements).copied().collect(),
+            right: right_elements.difference(&left_elements).copied().collect(),
+        });
+    }
+
+    // one row per element of how many are in each compound, balanced when
+    // the coefficients are in the null space, which is empty at full rank
+    let compounds: Vec<&Compound> = left.iter().chain(right).collect();
+    let matrix: Vec<Vec<i128>> = left_elements
+        .iter()
+        .map(|el| {
+            compounds
+                .iter()
+                .map(|c| element_counts(c).get(el).map_or(0, |&n| n as i128))
+                .collect()
+        })
+        .collect();
+    (rank(matrix) == compounds.len()).then_some(Diagnosis::OverDetermined {
+        elements: left_elements.len(),
+    })
+}
+
+/// Split an equation into the compounds on each side, without the checks
+/// [`chem_eq::Equation::new`] does, so equations it rejects can be diagnosed
+pub fn parse_sides(input: &str) -> Option<(Vec<Compound>, Vec<Compound>)> {
+    let input = normalize(input);
+    // `<->` contains the other arrows, so it's looked for first
+    let (left, right) = ["<->", "->", "<-"]
+        .iter()
+        .find_map(|arrow| input.split_once(arrow))?;
+    let side = |text: &str| -> Option<Vec<Compound>> {
+        text.split('+')
+            .map(|c| {
+                // an electron is left as `e` once its charge is taken off
+                let c = c.trim();
+                match c.split_at(c.find(|c: char| !c.is_ascii_digit())?) {
+                    (n, "e") => Some(Compound {
+                        coefficient: n.parse().unwrap_or(1),
+                        ..Default::default()
+                    }),
+                    _ => Compound::parse(c).ok(),
+                }
+            })
+            .collect()
+    };
+    Some((side(left)?, side(right)?))
+}
+
+fn elements(compounds: &[Compound]) -> BTreeSet<&'static str> {
+    compounds
+        .iter()
+        .flat_map(|c| &c.elements)
+        .map(|e| e.symbol())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnose_str(input: &str) -> Option<Diagnosis> {
+        let (left, right) = parse_sides(input).unwrap();
+        diagnose(&left, &right)
+    }
+
+    #[test]
+    fn one_sided() {
+        assert_eq!(
+            diagnose_str("Fe + S8 -> Fe2O3"),
+            Some(Diagnosis::OneSided {
+                left: vec!["S"],
+                right: vec!["O"],
+            })
+        );
+        assert_eq!(
+            diagnose_str("NaCl -> Na"),
+            Some(Diagnosis::OneSided {
+                left: vec!["Cl"],
+                right: vec![],
+            })
+        );
+        // sorted, with each element once
+        assert_eq!(
+            diagnose_str("H2 -> H2O + CO2 + N2O"),
+            Some(Diagnosis::OneSided {
+                left: vec![],
+                right: vec!["C", "N", "O"],
+            })
+        );
+    }
+
+    #[test]
+    fn over_determined() {
+        assert_eq!(
+            diagnose_str("H2O -> H2O2"),
+            Some(Diagnosis::OverDetermined { elements: 2 })
+        );
+        assert_eq!(
+            diagnose_str("H2O + CO2 -> H2CO4"),
+            Some(Diagnosis::OverDetermined { elements: 3 })
+        );
+    }
+
+    #[test]
+    fn balanceable() {
+        assert_eq!(diagnose_str("Fe + O2 -> Fe2O3"), None);
+        assert_eq!(diagnose_str("2H2 + O2 <-> 2H2O"), None);
+        assert_eq!(diagnose_str("H2(g) + O2(g) -> H2O(l)"), None);
+    }
+
+    #[test]
+    fn unparsable() {
+        assert!(parse_sides("Fe + O2").is_none());
+        assert!(parse_sides("Fe + O2 -> ").is_none());
+        assert!(parse_sides("Fe + Xx -> FeO").is_none());
+    }
+
+    #[test]
+    fn electrons() {
+        let (left, right) = parse_sides("Cu -> Cu^2+ + 2e-").unwrap();
+        assert_eq!((left.len(), right.len()), (1, 2));
+        assert!(right[1].elements.is_empty());
+        assert_eq!(right[1].coefficient, 2);
+    }
+}
@@ -0,0 +1,66 @@
+use std::{env, fs, io, process::Command};
+
+/// The editor to open, from `$VISUAL` then `$EDITOR`, or `vi` if neither is set
+pub fn command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Write `text` to a temporary file, open it in `editor` and read it back
+/// once the editor exits. `editor` can have arguments, eg. `code --wait`
+pub fn edit(editor: &str, text: &str) -> io::Result<String> {
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no editor is set"))?;
+    let path = env::temp_dir().join(format!("cmbl-{}.txt", std::process::id()));
+    fs::write(&path, format!("{text}\n"))?;
+
+    let edited = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .and_then(|status| match status.success() {
+            true => fs::read_to_string(&path),
+            false => Err(io::Error::other(format!("it exited with {status}"))),
+        });
+    // it's only a copy, so there's nothing to do if it can't be removed
+    let _ = fs::remove_file(&path);
+    edited
+}
+
+/// Put the lines of an edited equation back on one line, separated by spaces
+pub fn join_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joining() {
+        assert_eq!(
+            join_lines("C6H12O6 + O2\n  -> CO2\n\n+ H2O\n"),
+            "C6H12O6 + O2 -> CO2 + H2O"
+        );
+        assert_eq!(join_lines("\n  \n"), "");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn editing() {
+        // an editor that saves without changing anything
+        assert_eq!(edit("true", "H2 + O2").unwrap(), "H2 + O2\n");
+        assert!(edit("false", "H2 + O2").is_err());
+        assert!(edit("cmbl-no-such-editor", "H2 + O2").is_err());
+        assert!(edit("  ", "H2 + O2").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use mendeleev::{Element, OxidationStateCategory};
+
+/// What's worth knowing about an element while balancing, from the periodic
+/// table compiled in with mendeleev
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Info {
+    pub symbol: &'static str,
+    pub name: &'static str,
+    pub number: u32,
+    /// Standard atomic weight, in g/mol
+    pub mass: f64,
+    /// The states it's most often in, lowest first. Empty for the noble
+    /// gases and the heaviest elements
+    pub oxidation_states: &'static [i8],
+}
+
+impl From<Element> for Info {
+    fn from(el: Element) -> Self {
+        Self {
+            symbol: el.symbol(),
+            name: el.name(),
+            number: el.atomic_number(),
+            mass: f64::from(el.atomic_weight()),
+            oxidation_states: el.oxidation_states(OxidationStateCategory::Main),
+        }
+    }
+}
+
+impl Info {
+    /// The common oxidation states written with signs, eg. `+2, +3`
+    pub fn states_text(&self) -> String {
+        if self.oxidation_states.is_empty() {
+            return "none".to_string();
+        }
+        self.oxidation_states
+            .iter()
+            .map(|s| match s {
+                1.. => format!("+{s}"),
+                _ => s.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The element with this symbol, eg. `Fe`
+pub fn by_symbol(symbol: &str) -> Option<Info> {
+    Element::iter()
+        .find(|el| el.symbol() == symbol)
+        .map(Info::from)
+}
+
+/// Find an element by what's typed, its symbol whatever the case or the start
+/// of its name, eg. `fe`, `Fe` or `iro` for iron
+pub fn search(query: &str) -> Option<Info> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    Element::iter()
+        .find(|el| el.symbol().to_lowercase() == query)
+        .or_else(|| Element::iter().find(|el| el.name().to_lowercase().starts_with(&query)))
+        .map(Info::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info() {
+        let iron = by_symbol("Fe").unwrap();
+        assert_eq!((iron.name, iron.number), ("Iron", 26));
+        assert!((iron.mass - 55.845).abs() < 1e-9);
+        assert_eq!(iron.states_text(), "+2, +3");
+        assert_eq!(by_symbol("N").unwrap().states_text(), "-3, +3, +5");
+        assert_eq!(by_symbol("Ne").unwrap().states_text(), "none");
+        assert_eq!(by_symbol("Xz"), None);
+    }
+
+    #[test]
+    fn searching() {
+        let symbol = |q| search(q).map(|i| i.symbol);
+        assert_eq!(symbol("fe"), Some("Fe"));
+        assert_eq!(symbol("CL"), Some("Cl"));
+        // a symbol is preferred to a name starting the same
+        assert_eq!(symbol("c"), Some("C"));
+        assert_eq!(symbol("sod"), Some("Na"));
+        assert_eq!(symbol("xyz"), None);
+        assert_eq!(symbol(""), None);
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use chem_eq::{Direction, Equation, State};
+
+use crate::{charge, chem, format::latex_formula};
+
+/// The concentration of a compound in an equilibrium expression, raised to
+/// the power of its coefficient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub formula: String,
+    pub power: usize,
+}
+
+/// The equilibrium constant expression of a reversible reaction, the
+/// products' concentrations over the reactants'. Pure solids and liquids
+/// aren't in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kc {
+    pub products: Vec<Term>,
+    pub reactants: Vec<Term>,
+}
+
+/// Write the equilibrium constant expression of a balanced equation, `input`
+/// is what it was balanced from. `None` unless the reaction is reversible
+pub fn kc(input: &str, eq: &Equation) -> Option<Kc> {
+    if *eq.direction() != Direction::Reversible {
+        return None;
+    }
+    let mut terms = eq
+        .iter_compounds()
+        .zip(chem::typed_formulas(input, eq))
+        .map(|(cmp, formula)| {
+            let pure = matches!(cmp.state, Some(State::Solid | State::Liquid));
+            (!pure).then(|| Term {
+                formula: charge::split_state(&formula).0.to_string(),
+                power: cmp.coefficient,
+            })
+        });
+    let reactants = terms.by_ref().take(eq.left().len()).flatten().collect();
+    let products = terms.flatten().collect();
+    Some(Kc {
+        products,
+        reactants,
+    })
+}
+
+impl Kc {
+    /// Write the expression as LaTeX math, eg. `K_c = \frac{[\mathrm{NH_{3}}]^{2}}{...}`
+    pub fn latex(&self) -> String {
+        let side = |terms: &[Term]| -> String {
+            terms
+                .iter()
+                .map(|t| match t.power {
+                    1 => format!("[{}]", latex_formula(&t.formula)),
+                    n => format!("[{}]^{{{n}}}", latex_formula(&t.formula)),
+                })
+                .collect()
+        };
+        let top = match side(&self.products) {
+            s if s.is_empty() => "1".to_string(),
+            s => s,
+        };
+        match side(&self.reactants) {
+            bottom if bottom.is_empty() => format!("K_c = {top}"),
+            bottom => format!(r"K_c = \frac{{{top}}}{{{bottom}}}"),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.power {
+            1 => write!(f, "[{}]", self.formula),
+            n => write!(f, "[{}]^{n}", self.formula),
+        }
+    }
+}
+
+impl fmt::Display for Kc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |terms: &[Term]| terms.iter().map(Term::to_string).collect::<String>();
+        write!(f, "Kc = ")?;
+        match self.products.as_slice() {
+            [] => write!(f, "1")?,
+            products => write!(f, "{}", side(products))?,
+        }
+        match self.reactants.as_slice() {
+            [] => Ok(()),
+            [one] => write!(f, " / {one}"),
+            reactants => write!(f, " / ({})", side(reactants)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::balance;
+
+    fn kc_str(input: &str) -> Option<String> {
+        let eq = balance(input).unwrap();
+        kc(input, &eq).map(|kc| kc.to_string())
+    }
+
+    #[test]
+    fn exponents() {
+        assert_eq!(
+            kc_str("N2 + H2 <-> NH3").as_deref(),
+            Some("Kc = [NH3]^2 / ([N2][H2]^3)")
+        );
+        assert_eq!(
+            kc_str("N2O4 <-> NO2").as_deref(),
+            Some("Kc = [NO2]^2 / [N2O4]")
+        );
+        assert_eq!(
+            kc_str("Fe^3+ + SCN^- <-> FeSCN^2+").as_deref(),
+            Some("Kc = [FeSCN^2+] / ([Fe^3+][SCN^-])")
+        );
+        // only reversible reactions have one
+        assert_eq!(kc_str("N2 + H2 -> NH3"), None);
+        assert_eq!(kc_str("NH3 <- N2 + H2"), None);
+    }
+
+    #[test]
+    fn states() {
+        // solids and liquids are left out, gases and solutions aren't
+        assert_eq!(
+            kc_str("CaCO3(s) <-> CaO(s) + CO2(g)").as_deref(),
+            Some("Kc = [CO2]")
+        );
+        assert_eq!(
+            kc_str("AgCl(s) <-> Ag^+(aq) + Cl^-(aq)").as_deref(),
+            Some("Kc = [Ag^+][Cl^-]")
+        );
+        assert_eq!(
+            kc_str("H2O(l) <-> H^+(aq) + OH^-(aq)").as_deref(),
+            Some("Kc = [H^+][OH^-]")
+        );
+        assert_eq!(
+            kc_str("CO2(g) + H2O(l) <-> H2CO3(aq)").as_deref(),
+            Some("Kc = [H2CO3] / [CO2]")
+        );
+        assert_eq!(
+            kc_str("CaO(s) + CO2(g) <-> CaCO3(s)").as_deref(),
+            Some("Kc = 1 / [CO2]")
+        );
+    }
+
+    #[test]
+    fn latex() {
+        let input = "N2 + H2 <-> NH3";
+        let eq = balance(input).unwrap();
+        assert_eq!(
+            kc(input, &eq).unwrap().latex(),
+            r"K_c = \frac{[\mathrm{NH_{3}}]^{2}}{[\mathrm{N_{2}}][\mathrm{H_{2}}]^{3}}"
+        );
+        let input = "CaCO3(s) <-> CaO(s) + CO2(g)";
+        let eq = balance(input).unwrap();
+        assert_eq!(kc(input, &eq).unwrap().latex(), r"K_c = [\mathrm{CO_{2}}]");
+    }
+}
@@ -0,0 +1,128 @@
+use chem_eq::error::{BalanceError, EquationError};
+
+use crate::{chem::UnknownElement, redox::RedoxError};
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    Eq(EquationError),
+    Balance(BalanceError),
+    /// The input is longer than the tui will balance, in characters
+    TooLong(usize),
+    /// A hydrate bound into another, eg. `A·B·C`, written as it was typed
+    NestedHydrate(String),
+    /// Balancing by half-reactions failed
+    Redox(RedoxError),
+    /// Electrons were written, but nothing else is charged to balance them
+    UnchargedElectrons,
+    /// A symbol in a formula isn't on the periodic table
+    UnknownElement(UnknownElement),
+}
+
+impl Error {
+    /// The code the cli exits with after this error
+    pub const fn exit_code(&self) -> u8 {
+        match self {
+            Self::Eq(EquationError::ParsingError(_) | EquationError::TooMuchInput(_))
+            | Self::TooLong(_)
+            | Self::NestedHydrate(_)
+            | Self::UnknownElement(_) => 2,
+            Self::Eq(EquationError::IncorrectEquation)
+            | Self::Balance(_)
+            | Self::UnchargedElectrons => 3,
+            Self::Redox(e) => e.exit_code(),
+        }
+    }
+}
+
+/// A short message shown by both the cli and the tui. The details from chem-eq
+/// are available through [`std::error::Error::source`]
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Eq(EquationError::ParsingError(_)) => write!(f, "Couldn't parse equation"),
+            Self::Eq(EquationError::IncorrectEquation)
+            | Self::Balance(BalanceError::InvalidEquation) => write!(f, "Equation is not valid"),
+            Self::Eq(EquationError::TooMuchInput(s)) => write!(f, "Too much input: {s}"),
+            Self::Balance(BalanceError::Infeasable) => write!(f, "Equation could not be balanced"),
+            Self::TooLong(max) => write!(f, "Equation is longer than {max} characters"),
+            Self::Redox(e) => write!(f, "{e}"),
+            Self::UnchargedElectrons => write!(
+                f,
+                "Electrons need charged ions to balance against, write charges like Fe^3+"
+            ),
+            Self::NestedHydrate(c) => {
+                write!(f, "{c} is a hydrate of a hydrate, write it with one · or *")
+            }
+            Self::UnknownElement(u) => {
+                write!(f, "Unknown element {} in compound {}", u.symbol, u.compound)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Eq(e) => Some(e),
+            Self::Balance(e) => Some(e),
+            Self::TooLong(_)
+            | Self::NestedHydrate(_)
+            | Self::Redox(_)
+            | Self::UnchargedElectrons
+            | Self::UnknownElement(_) => None,
+        }
+    }
+}
+
+impl From<EquationError> for Error {
+    fn from(e: EquationError) -> Self {
+        Self::Eq(e)
+    }
+}
+
+impl From<BalanceError> for Error {
+    fn from(e: BalanceError) -> Self {
+        Self::Balance(e)
+    }
+}
+
+impl From<RedoxError> for Error {
+    fn from(e: RedoxError) -> Self {
+        Self::Redox(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use crate::balance::balance;
+
+    #[test]
+    fn parse_message() {
+        let e = balance("Fe + O2 => Fe2O3").unwrap_err();
+        assert_eq!(e.to_string(), "Couldn't parse equation");
+        assert_eq!(e.exit_code(), 2);
+        // the parser's details are kept as the source
+        let source = e.source().unwrap().to_string();
+        assert!(source.starts_with("couldn't parse the equation"));
+    }
+
+    #[test]
+    fn unknown_element_message() {
+        let e = balance("Fe + O2 -> FeXz").unwrap_err();
+        assert_eq!(e.to_string(), "Unknown element Xz in compound 3");
+        assert_eq!(e.exit_code(), 2);
+    }
+
+    #[test]
+    fn balance_message() {
+        let e = balance("H2O -> H2O2").unwrap_err();
+        assert_eq!(e.to_string(), "Equation could not be balanced");
+        assert_eq!(e.exit_code(), 3);
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "equation could not be balanced"
+        );
+    }
+}
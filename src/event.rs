@@ -0,0 +1,243 @@
+use std::{io, time::Duration};
+
+use crossterm::event::{self, Event, MouseButton, MouseEventKind};
+
+use crate::app::{handle_key, handle_paste, Action, App, Focus};
+
+/// Where the tui gets its events from, so the loop can be tested without a terminal
+pub trait Events {
+    /// Wait up to `timeout` for an event to be ready
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+    /// Get the next event, blocking until there is one
+    fn read(&mut self) -> io::Result<Event>;
+}
+
+/// Events from the real terminal
+#[derive(Debug, Default)]
+pub struct TerminalEvents;
+
+impl Events for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+}
+
+/// What happened while handling events
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Update {
+    /// The app changed and has to be drawn again
+    pub redraw: bool,
+    /// The terminal was resized
+    pub resized: bool,
+    /// Things only the terminal can do, in the order they were asked for
+    pub actions: Vec<Action>,
+}
+
+/// Wait up to `timeout` for events, then handle every queued one. The equation
+/// is balanced once at the end, so holding down a key only balances once per
+/// frame. Stops early when asked to quit.
+pub fn handle_events(
+    app: &mut App,
+    events: &mut impl Events,
+    timeout: Duration,
+) -> io::Result<Update> {
+    let mut update = Update::default();
+    let mut timeout = timeout;
+    while events.poll(timeout)? {
+        // only wait for the first event
+        timeout = Duration::ZERO;
+        match events.read()? {
+            Event::Key(key) => {
+                update.redraw = true;
+                if let Some(action) = handle_key(app, key) {
+                    let quit = action == Action::Quit;
+                    update.actions.push(action);
+                    if quit {
+                        break;
+                    }
+                }
+            }
+            Event::Paste(text) => {
+                handle_paste(app, &text);
+                update.redraw = true;
+            }
+            Event::Resize(..) => {
+                update.redraw = true;
+                update.resized = true;
+            }
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    app.click(mouse.column, mouse.row);
+                    update.redraw = true;
+                }
+                // only the output scrolls
+                MouseEventKind::ScrollUp if app.focus == Focus::Output => {
+                    app.scroll_output(-1);
+                    update.redraw = true;
+                }
+                MouseEventKind::ScrollDown if app.focus == Focus::Output => {
+                    app.scroll_output(1);
+                    update.redraw = true;
+                }
+                _ => {}
+            },
+            // mouse movement and focus changes don't change anything
+            _ => {}
+        }
+    }
+    if app.edited {
+        app.update_eq();
+    }
+    Ok(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+
+    use super::*;
+    use crate::{
+        app::{InputMode, OutputView},
+        format::CopyFormat,
+    };
+
+    /// Events queued up front, as if they all arrived at once
+    #[derive(Default)]
+    struct FakeEvents(VecDeque<Event>);
+
+    impl Events for FakeEvents {
+        fn poll(&mut self, _timeout: Duration) -> io::Result<bool> {
+            Ok(!self.0.is_empty())
+        }
+
+        fn read(&mut self) -> io::Result<Event> {
+            Ok(self.0.pop_front().expect("polled first"))
+        }
+    }
+
+    fn keys(codes: impl IntoIterator<Item = KeyCode>) -> FakeEvents {
+        FakeEvents(
+            codes
+                .into_iter()
+                .map(|c| Event::Key(KeyEvent::from(c)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn nothing_happened() {
+        let mut app = App::default();
+        let update = handle_events(&mut app, &mut FakeEvents::default(), Duration::ZERO).unwrap();
+        assert_eq!(update, Update::default());
+
+        // moving the mouse doesn't need a redraw
+        let mut events = FakeEvents(VecDeque::from([Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 0,
+            row: 0,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        })]));
+        let update = handle_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert!(!update.redraw);
+    }
+
+    #[test]
+    fn balance_once() {
+        let mut app = App::default();
+        let mut events = keys("iH2 + O2 -> H2O".chars().map(KeyCode::Char));
+        let update = handle_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert!(update.redraw);
+        assert!(update.actions.is_empty());
+        assert!(!app.edited);
+        assert_eq!(app.output.unwrap().unwrap().equation(), "2H2 + O2 -> 2H2O");
+    }
+
+    #[test]
+    fn paste() {
+        let mut app = App::default();
+        let mut events = FakeEvents(VecDeque::from([Event::Paste(
+            "Fe + O2\r\n-> Fe2O3\n".to_string(),
+        )]));
+        let update = handle_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert!(update.redraw);
+        assert!(matches!(app.input_mode, InputMode::Editing));
+        assert_eq!(app.input, "Fe + O2 -> Fe2O3");
+        assert_eq!(
+            app.output.unwrap().unwrap().equation(),
+            "4Fe + 3O2 -> 2Fe2O3"
+        );
+    }
+
+    #[test]
+    fn actions_in_order() {
+        let mut app = App::default();
+        app.set_input("H2 + O2 -> H2O");
+        let mut events = keys([
+            KeyCode::Char('y'),
+            KeyCode::Char('l'),
+            KeyCode::Char('q'),
+            KeyCode::Char('q'),
+            KeyCode::Char('i'),
+        ]);
+        let update = handle_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert_eq!(
+            update.actions,
+            [
+                Action::Copy {
+                    text: r"2\mathrm{H_{2}} + \mathrm{O_{2}} \rightarrow 2\mathrm{H_{2}O}"
+                        .to_string(),
+                    format: CopyFormat::Latex
+                },
+                Action::Quit
+            ]
+        );
+        // nothing after quitting is handled
+        assert_eq!(events.0.len(), 1);
+    }
+
+    #[test]
+    fn mouse_wheel() {
+        let wheel = |kind| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column: 0,
+                row: 0,
+                modifiers: crossterm::event::KeyModifiers::NONE,
+            })
+        };
+        let mut app = App::default();
+        app.output_view.set(OutputView {
+            lines: 4,
+            height: 1,
+        });
+        // only scrolls the output when it's focused
+        let mut events = FakeEvents(VecDeque::from([wheel(MouseEventKind::ScrollDown)]));
+        let update = handle_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert!(!update.redraw);
+        assert_eq!(app.output_scroll, 0);
+
+        app.focus = Focus::Output;
+        let mut events = FakeEvents(VecDeque::from([
+            wheel(MouseEventKind::ScrollDown),
+            wheel(MouseEventKind::ScrollDown),
+            wheel(MouseEventKind::ScrollUp),
+        ]));
+        let update = handle_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert!(update.redraw);
+        assert_eq!(app.output_scroll, 1);
+    }
+
+    #[test]
+    fn resize() {
+        let mut app = App::default();
+        let mut events = FakeEvents(VecDeque::from([Event::Resize(80, 24)]));
+        let update = handle_events(&mut app, &mut events, Duration::ZERO).unwrap();
+        assert!(update.redraw && update.resized);
+    }
+}
@@ -0,0 +1,161 @@
+/// A well known reaction, written unbalanced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Example {
+    pub name: &'static str,
+    pub equation: &'static str,
+}
+
+const fn example(name: &'static str, equation: &'static str) -> Example {
+    Example { name, equation }
+}
+
+/// Classic reactions to demo the balancer or practice with
+pub const EXAMPLES: &[Example] = &[
+    example("Combustion of methane", "CH4 + O2 -> CO2 + H2O"),
+    example("Combustion of propane", "C3H8 + O2 -> CO2 + H2O"),
+    example("Combustion of octane", "C8H18 + O2 -> CO2 + H2O"),
+    example("Combustion of ethanol", "C2H6O + O2 -> CO2 + H2O"),
+    example("Cellular respiration", "C6H12O6 + O2 -> CO2 + H2O"),
+    example("Photosynthesis", "CO2 + H2O -> C6H12O6 + O2"),
+    example("Fermentation", "C6H12O6 -> C2H6O + CO2"),
+    example("Rusting of iron", "Fe + O2 -> Fe2O3"),
+    example("Thermite", "Al + Fe2O3 -> Al2O3 + Fe"),
+    example("Blast furnace", "Fe2O3 + CO -> Fe + CO2"),
+    example("Synthesis of water", "H2 + O2 -> H2O"),
+    example("Haber process", "N2 + H2 <-> NH3"),
+    example("Ostwald process", "NH3 + O2 -> NO + H2O"),
+    example("Burning magnesium", "Mg + O2 -> MgO"),
+    example("Sodium in chlorine", "Na + Cl2 -> NaCl"),
+    example("Sodium in water", "Na + H2O -> NaOH + H2"),
+    example("Zinc in hydrochloric acid", "Zn + HCl -> ZnCl2 + H2"),
+    example("Copper in silver nitrate", "Cu + AgNO3 -> Cu(NO3)2 + Ag"),
+    example("Silver tarnishing", "Ag + H2S + O2 -> Ag2S + H2O"),
+    example("Decomposition of hydrogen peroxide", "H2O2 -> H2O + O2"),
+    example("Decomposition of potassium chlorate", "KClO3 -> KCl + O2"),
+    example("Calcination of limestone", "CaCO3 -> CaO + CO2"),
+    example(
+        "Neutralization of hydrochloric acid",
+        "HCl + NaOH -> NaCl + H2O",
+    ),
+    example(
+        "Neutralization of sulfuric acid",
+        "H2SO4 + NaOH -> Na2SO4 + H2O",
+    ),
+    example(
+        "Neutralization of phosphoric acid",
+        "Ca(OH)2 + H3PO4 -> Ca3(PO4)2 + H2O",
+    ),
+    example(
+        "Baking soda and vinegar",
+        "NaHCO3 + CH3COOH -> CH3COONa + H2O + CO2",
+    ),
+    example("Golden rain", "Pb(NO3)2 + KI -> PbI2 + KNO3"),
+    example("Chlorination of methane", "CH4 + Cl2 -> CH3Cl + HCl"),
+];
+
+/// Find the examples whose name or equation fuzzily match `query`, best first.
+/// Every example matches an empty query
+pub fn search(query: &str) -> Vec<&'static Example> {
+    let mut matches: Vec<(usize, &Example)> = EXAMPLES
+        .iter()
+        .filter_map(|e| {
+            let score = fuzzy_score(query, e.name)
+                .into_iter()
+                .chain(fuzzy_score(query, e.equation))
+                .min()?;
+            Some((score, e))
+        })
+        .collect();
+    // stable, so equally good matches stay in the order they're listed
+    matches.sort_by_key(|(score, _)| *score);
+    matches.into_iter().map(|(_, e)| e).collect()
+}
+
+/// How well `query` matches `text`, ignoring case: `None` unless every character
+/// of the query is in the text in order, otherwise the number of characters
+/// skipped between them, so lower is better
+fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let query = query.to_lowercase();
+    let text = text.to_lowercase();
+    // start the match at each place the first character is, keeping the best
+    let first = query.chars().next()?;
+    text.char_indices()
+        .filter(|(_, c)| *c == first)
+        .filter_map(|(start, _)| {
+            let mut skipped = 0;
+            let mut rest = text[start..].chars();
+            for q in query.chars() {
+                loop {
+                    let c = rest.next()?;
+                    if c == q {
+                        break;
+                    }
+                    skipped += 1;
+                }
+            }
+            Some(skipped)
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::balance;
+
+    #[test]
+    fn examples_balance() {
+        for e in EXAMPLES {
+            assert!(balance(e.equation).is_ok(), "{} doesn't balance", e.name);
+        }
+    }
+
+    #[test]
+    fn empty_query() {
+        assert_eq!(search("").len(), EXAMPLES.len());
+        assert_eq!(search("")[0].name, "Combustion of methane");
+    }
+
+    #[test]
+    fn by_name() {
+        let found = search("thermite");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].equation, "Al + Fe2O3 -> Al2O3 + Fe");
+        // case and gaps don't matter
+        assert_eq!(search("RUSTIRON")[0].name, "Rusting of iron");
+    }
+
+    #[test]
+    fn by_formula() {
+        let names: Vec<&str> = search("NaOH").iter().map(|e| e.name).collect();
+        // the reactions with sodium hydroxide in them come before spread out matches
+        assert_eq!(
+            names[..3],
+            [
+                "Sodium in water",
+                "Neutralization of hydrochloric acid",
+                "Neutralization of sulfuric acid"
+            ]
+        );
+    }
+
+    #[test]
+    fn closest_first() {
+        // `haber` is in one name as is, the rest only have the letters spread out
+        assert_eq!(search("haber")[0].name, "Haber process");
+        assert!(search("qqq").is_empty());
+    }
+
+    #[test]
+    fn scores() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("abc", "abc"), Some(0));
+        assert_eq!(fuzzy_score("ac", "abc"), Some(1));
+        // the closer of two starts is used
+        assert_eq!(fuzzy_score("ac", "a...abc"), Some(1));
+        assert_eq!(fuzzy_score("ca", "abc"), None);
+    }
+}
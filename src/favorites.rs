@@ -0,0 +1,53 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Read the favorite equations saved at `path`, one to a line. A missing file
+/// is no favorites
+pub fn load(path: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the favorites to `path`, one to a line
+pub fn save(path: &Path, favorites: &[String]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text: String = favorites.iter().map(|f| format!("{f}\n")).collect();
+    fs::write(path, text)
+}
+
+/// Where favorites are saved, next to the history, eg.
+/// `~/.local/share/balance-tui/favorites`
+pub fn path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("balance-tui").join("favorites"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let dir =
+            std::env::temp_dir().join(format!("balance-tui-favorites-{}", std::process::id()));
+        let path = dir.join("favorites");
+        assert!(load(&path).unwrap().is_empty());
+
+        let favorites = ["H2 + O2 -> H2O".to_string(), "C + O2 -> CO2".to_string()];
+        save(&path, &favorites).unwrap();
+        assert_eq!(load(&path).unwrap(), favorites);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
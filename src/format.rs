@@ -0,0 +1,742 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chem_eq::{Direction, Equation};
+use serde::Serialize;
+
+use crate::{
+    batch::BatchResult,
+    charge, chem,
+    classify::{classify, ReactionType},
+    hydrate,
+    matrix::gcd,
+    oxidation,
+};
+
+/// How the cli prints results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Plain,
+    Json,
+    Markdown,
+    Csv,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plain => write!(f, "plain text"),
+            Self::Json => write!(f, "json"),
+            Self::Markdown => write!(f, "markdown"),
+            Self::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!(
+                "unknown format {s:?}, expected one of plain, json, markdown or csv"
+            )),
+        }
+    }
+}
+
+/// Format results, each on their own line
+pub fn format_results(format: Format, results: &[BatchResult]) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Markdown => {
+            out.push_str("| Input | Balanced | Status |\n");
+            out.push_str("| --- | --- | --- |\n");
+        }
+        Format::Csv => out.push_str("input,balanced,ok\n"),
+        Format::Plain | Format::Json => {}
+    }
+    for res in results {
+        let line = match format {
+            Format::Plain => plain(res),
+            Format::Json => json(res),
+            Format::Markdown => markdown_row(res),
+            Format::Csv => csv_row(res),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+fn plain(res: &BatchResult) -> String {
+    match &res.output {
+        Ok(eq) => plain_equation(&res.input, eq, false),
+        Err(e) => format!("error: {}: {e}", res.input),
+    }
+}
+
+fn markdown_row(res: &BatchResult) -> String {
+    let (balanced, status) = match &res.output {
+        Ok(eq) => (plain_equation(&res.input, eq, false), "ok".to_string()),
+        Err(e) => (String::new(), e.to_string()),
+    };
+    format!(
+        "| {} | {} | {} |",
+        markdown_escape(&res.input),
+        markdown_escape(&balanced),
+        markdown_escape(&status)
+    )
+}
+
+/// Escape characters markdown would read as formatting or as the end of a
+/// table cell, and put newlines on one line, so `s` shows as typed in a table
+/// cell or a heading
+pub fn markdown_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '|' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push(' '),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn csv_row(res: &BatchResult) -> String {
+    let balanced = res
+        .output
+        .as_ref()
+        .map(|eq| plain_equation(&res.input, eq, false));
+    format!(
+        "{},{},{}",
+        csv_escape(&res.input),
+        csv_escape(balanced.as_deref().unwrap_or_default()),
+        balanced.is_ok()
+    )
+}
+
+/// Quote a csv field if it contains a separator, quote or newline
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn json(res: &BatchResult) -> String {
+    let json = match &res.output {
+        Ok(eq) => JsonResult::Balanced(JsonEquation::new(&res.input, eq)),
+        Err(e) => JsonResult::Error {
+            input: &res.input,
+            error: e.to_string(),
+        },
+    };
+    serde_json::to_string(&json).expect("json results are always serializable")
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum JsonResult<'a> {
+    Balanced(JsonEquation<'a>),
+    Error { input: &'a str, error: String },
+}
+
+/// How a balanced equation is copied from the tui
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// The input as it was typed
+    Input,
+    Plain,
+    Latex,
+    Unicode,
+    Json,
+}
+
+impl fmt::Display for CopyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Input => write!(f, "input"),
+            Self::Plain => write!(f, "plain text"),
+            Self::Latex => write!(f, "LaTeX"),
+            Self::Unicode => write!(f, "unicode"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// A coefficient divided by another, in its lowest terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fraction {
+    pub num: usize,
+    pub den: usize,
+}
+
+impl Fraction {
+    pub fn new(num: usize, den: usize) -> Self {
+        let divisor = (gcd(num as i128, den as i128) as usize).max(1);
+        Self {
+            num: num / divisor,
+            den: den / divisor,
+        }
+    }
+}
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.den {
+            1 => write!(f, "{}", self.num),
+            den => write!(f, "{}/{den}", self.num),
+        }
+    }
+}
+
+/// Divide every coefficient by the one of the compound at `reference`, so
+/// the equation is for one mole of it, eg. `C2H6 + 7/2 O2 -> 2CO2 + 3H2O`
+pub fn fractions(eq: &Equation, reference: usize) -> Vec<Fraction> {
+    let den = eq
+        .iter_compounds()
+        .nth(reference)
+        .map_or(1, |cmp| cmp.coefficient);
+    eq.iter_compounds()
+        .map(|cmp| Fraction::new(cmp.coefficient, den))
+        .collect()
+}
+
+/// How the coefficients of an equation are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coefficients {
+    /// Whole numbers, with the 1s too if `ones` is set
+    Whole { ones: bool },
+    /// Fractions with one of the compound at this index, see [`fractions`]
+    PerMole(usize),
+}
+
+impl Coefficients {
+    /// Write the coefficient of each compound, an empty string where it's
+    /// left out. Fractions are set apart from the formula after them
+    fn write(self, eq: &Equation, latex: bool) -> Vec<String> {
+        match self {
+            Self::Whole { ones } => eq
+                .iter_compounds()
+                .map(|cmp| match cmp.coefficient {
+                    1 if !ones => String::new(),
+                    n => n.to_string(),
+                })
+                .collect(),
+            Self::PerMole(reference) => fractions(eq, reference)
+                .into_iter()
+                .map(|f| match (f.num, f.den) {
+                    (1, 1) => String::new(),
+                    (num, 1) => num.to_string(),
+                    (num, den) if latex => format!(r"\tfrac{{{num}}}{{{den}}}"),
+                    (num, den) => format!("{num}/{den} "),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Write a balanced equation to be copied, `input` is what it was balanced
+/// from. With `ones`, coefficients of 1 are written out too
+pub fn copy_text(format: CopyFormat, input: &str, eq: &Equation, ones: bool) -> String {
+    copy_with(format, input, eq, Coefficients::Whole { ones })
+}
+
+/// Write a balanced equation to be copied with fractional coefficients, so
+/// there's one of the compound at `reference`. Json is always whole numbers
+pub fn fractional_text(format: CopyFormat, input: &str, eq: &Equation, reference: usize) -> String {
+    copy_with(format, input, eq, Coefficients::PerMole(reference))
+}
+
+fn copy_with(format: CopyFormat, input: &str, eq: &Equation, coefficients: Coefficients) -> String {
+    match format {
+        CopyFormat::Input => input.to_string(),
+        CopyFormat::Plain => plain_with(input, eq, coefficients),
+        CopyFormat::Latex => latex(input, eq, coefficients),
+        CopyFormat::Unicode => unicode(input, eq, coefficients),
+        CopyFormat::Json => serde_json::to_string(&JsonEquation::new(input, eq))
+            .expect("json results are always serializable"),
+    }
+}
+
+/// Write an equation as text, eg. `2H2 + O2 -> 2H2O`, or `2H2 + 1O2 -> 2H2O` with `ones`.
+/// Hydrates are written as they were in `input`
+pub fn plain_equation(input: &str, eq: &Equation, ones: bool) -> String {
+    plain_with(input, eq, Coefficients::Whole { ones })
+}
+
+fn plain_with(input: &str, eq: &Equation, coefficients: Coefficients) -> String {
+    let coefficients = coefficients.write(eq, false);
+    write_equation(
+        input,
+        eq,
+        &eq.direction().to_string(),
+        &coefficients,
+        str::to_string,
+    )
+}
+
+/// Write an equation as LaTeX math, eg. `2\mathrm{H_{2}} + \mathrm{O_{2}} \rightarrow 2\mathrm{H_{2}O}`
+fn latex(input: &str, eq: &Equation, coefficients: Coefficients) -> String {
+    let arrow = match eq.direction() {
+        Direction::Left => r"\leftarrow",
+        Direction::Right => r"\rightarrow",
+        Direction::Reversible => r"\rightleftharpoons",
+    };
+    let coefficients = coefficients.write(eq, true);
+    write_equation(input, eq, arrow, &coefficients, latex_formula)
+}
+
+/// Write a formula as LaTeX math, eg. `\mathrm{H_{2}O}` or `\mathrm{Fe}^{3+}`.
+/// A state is written as text, eg. `\mathrm{NaCl}\text{(aq)}`
+pub fn latex_formula(formula: &str) -> String {
+    let (formula, state) = charge::split_state(formula);
+    let (formula, charge, _) = charge::split(formula);
+    let mut out = String::from(r"\mathrm{");
+    let mut chars = formula.chars().peekable();
+    while let Some(c) = chars.next() {
+        if hydrate::SEPARATORS.contains(&c) {
+            // the count of bound water isn't a subscript
+            out.push_str(r"\cdot ");
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                out.push(d);
+            }
+        } else if c.is_ascii_digit() {
+            out.push_str("_{");
+            out.push(c);
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                out.push(d);
+            }
+            out.push('}');
+        } else {
+            out.push(c);
+        }
+    }
+    out.push('}');
+    if charge != 0 {
+        out.push_str(&format!("^{{{}}}", charge::format(charge)));
+    }
+    if !state.is_empty() {
+        out.push_str(&format!(r"\text{{{state}}}"));
+    }
+    out
+}
+
+/// Write an equation with subscript numbers, superscript charges and a real
+/// arrow, eg. `2H₂ + O₂ → 2H₂O`
+fn unicode(input: &str, eq: &Equation, coefficients: Coefficients) -> String {
+    let arrow = match eq.direction() {
+        Direction::Left => "←",
+        Direction::Right => "→",
+        Direction::Reversible => "⇌",
+    };
+    let coefficients = coefficients.write(eq, false);
+    write_equation(input, eq, arrow, &coefficients, |formula| {
+        let (formula, state) = charge::split_state(formula);
+        let (formula, charge, _) = charge::split(formula);
+        let mut out = subscripts(&formula);
+        if charge != 0 {
+            out.extend(charge::format(charge).chars().map(charge::superscript));
+        }
+        out + state
+    })
+}
+
+/// Write the numbers in a formula as subscripts, eg. `H₂O`. A charge has to be
+/// written in superscript first, or it's made a subscript too
+pub fn subscripts(formula: &str) -> String {
+    let mut after_separator = false;
+    formula
+        .chars()
+        .map(|c| match c.to_digit(10) {
+            // the count of bound water isn't a subscript
+            Some(_) if after_separator => c,
+            Some(d) => char::from_u32('₀' as u32 + d).expect("subscript digits are contiguous"),
+            None => {
+                after_separator = hydrate::SEPARATORS.contains(&c);
+                c
+            }
+        })
+        .collect()
+}
+
+/// Join the compounds of an equation, writing each formula with `formula`
+/// after its coefficient
+fn write_equation(
+    input: &str,
+    eq: &Equation,
+    arrow: &str,
+    coefficients: &[String],
+    formula: impl Fn(&str) -> String,
+) -> String {
+    let mut compounds = coefficients
+        .iter()
+        .zip(chem::typed_formulas(input, eq))
+        .map(|(n, f)| format!("{n}{}", formula(&f)));
+    let left: Vec<String> = compounds.by_ref().take(eq.left().len()).collect();
+    let right: Vec<String> = compounds.collect();
+    format!("{} {arrow} {}", left.join(" + "), right.join(" + "))
+}
+
+/// A balanced equation as printed by `--json`
+#[derive(Debug, Serialize)]
+struct JsonEquation<'a> {
+    input: &'a str,
+    equation: String,
+    reaction_type: ReactionType,
+    reactants: Vec<JsonCompound>,
+    products: Vec<JsonCompound>,
+    /// Left out unless it's a redox reaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agents: Option<JsonAgents>,
+}
+
+/// The oxidizing and reducing agents of a redox reaction as printed by `--json`
+#[derive(Debug, Serialize)]
+struct JsonAgents {
+    oxidizing: Vec<String>,
+    reducing: Vec<String>,
+}
+
+impl<'a> JsonEquation<'a> {
+    fn new(input: &'a str, eq: &'a Equation) -> Self {
+        let mut compounds = eq
+            .iter_compounds()
+            .zip(chem::typed_formulas(input, eq))
+            .map(|(cmp, formula)| {
+                let states = oxidation::assign(&formula);
+                JsonCompound {
+                    coefficient: cmp.coefficient,
+                    charge: charge::split(&formula).1,
+                    formula,
+                    elements: chem::element_counts(cmp),
+                    // round off floating point noise
+                    molar_mass: (chem::molar_mass(cmp) * 1000.0).round() / 1000.0,
+                    oxidation_note: states.as_deref().and_then(oxidation::note),
+                    oxidation_states: states.map(|states| {
+                        states
+                            .into_iter()
+                            .map(|(element, state)| JsonState {
+                                element,
+                                state: state.to_string(),
+                            })
+                            .collect()
+                    }),
+                }
+            })
+            .collect::<Vec<_>>();
+        let products = compounds.split_off(eq.left().len());
+
+        Self {
+            input,
+            equation: plain_equation(input, eq, false),
+            reaction_type: classify(eq),
+            reactants: compounds,
+            products,
+            agents: oxidation::agents(input, eq).map(|a| JsonAgents {
+                oxidizing: a.oxidizing,
+                reducing: a.reducing,
+            }),
+        }
+    }
+}
+
+/// One compound of a balanced equation as printed by `--json`
+#[derive(Debug, Serialize)]
+struct JsonCompound {
+    coefficient: usize,
+    formula: String,
+    /// Left out for neutral compounds
+    #[serde(skip_serializing_if = "is_neutral")]
+    charge: i64,
+    elements: BTreeMap<&'static str, usize>,
+    molar_mass: f64,
+    /// Left out when they couldn't be worked out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    oxidation_states: Option<Vec<JsonState>>,
+    /// Why the states are unusual, eg. in a peroxide
+    #[serde(skip_serializing_if = "Option::is_none")]
+    oxidation_note: Option<&'static str>,
+}
+
+/// The oxidation state of one element of a compound as printed by `--json`.
+/// It's a string as it can be a fraction, eg. `+8/3`
+#[derive(Debug, Serialize)]
+struct JsonState {
+    element: &'static str,
+    state: String,
+}
+
+fn is_neutral(charge: &i64) -> bool {
+    *charge == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::balance;
+
+    fn result(input: &str) -> BatchResult {
+        BatchResult {
+            input: input.to_string(),
+            output: balance(input),
+        }
+    }
+
+    #[test]
+    fn json_snapshot() {
+        let out = format_results(Format::Json, &[result("H2 + O2 -> H2O")]);
+        assert_eq!(
+            out,
+            concat!(
+                r#"{"input":"H2 + O2 -> H2O","equation":"2H2 + O2 -> 2H2O","reaction_type":"synthesis","#,
+                r#""reactants":[{"coefficient":2,"formula":"H2","elements":{"H":2},"molar_mass":2.016,"#,
+                r#""oxidation_states":[{"element":"H","state":"0"}]},"#,
+                r#"{"coefficient":1,"formula":"O2","elements":{"O":2},"molar_mass":31.998,"#,
+                r#""oxidation_states":[{"element":"O","state":"0"}]}],"#,
+                r#""products":[{"coefficient":2,"formula":"H2O","elements":{"H":2,"O":1},"molar_mass":18.015,"#,
+                r#""oxidation_states":[{"element":"H","state":"+1"},{"element":"O","state":"-2"}]}],"#,
+                r#""agents":{"oxidizing":["O2"],"reducing":["H2"]}}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn json_error() {
+        let out = format_results(Format::Json, &[result("Fe + S8 -> Fe2O3")]);
+        assert_eq!(
+            out,
+            "{\"input\":\"Fe + S8 -> Fe2O3\",\"error\":\"Equation is not valid\"}\n"
+        );
+    }
+
+    #[test]
+    fn markdown_table() {
+        let out = format_results(
+            Format::Markdown,
+            &[result("H2 + O2 -> H2O"), result("Fe + S8 -> Fe2O3")],
+        );
+        assert_eq!(
+            out,
+            "| Input | Balanced | Status |\n\
+             | --- | --- | --- |\n\
+             | H2 + O2 -> H2O | 2H2 + O2 -> 2H2O | ok |\n\
+             | Fe + S8 -> Fe2O3 |  | Equation is not valid |\n"
+        );
+    }
+
+    #[test]
+    fn markdown_escaping() {
+        assert_eq!(markdown_escape("H2 + O2 -> H2O"), "H2 + O2 -> H2O");
+        assert_eq!(markdown_escape("CuSO4*5H2O"), "CuSO4\\*5H2O");
+        assert_eq!(markdown_escape("`a|b`"), "\\`a\\|b\\`");
+        assert_eq!(markdown_escape("H2\r\nO2"), "H2 O2");
+    }
+
+    #[test]
+    fn csv_rows() {
+        let out = format_results(
+            Format::Csv,
+            &[result("H2 + O2 -> H2O"), result("Fe + S8 -> Fe2O3")],
+        );
+        assert_eq!(
+            out,
+            "input,balanced,ok\n\
+             H2 + O2 -> H2O,2H2 + O2 -> 2H2O,true\n\
+             Fe + S8 -> Fe2O3,,false\n"
+        );
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(csv_escape("H2 + O2 -> H2O"), "H2 + O2 -> H2O");
+        assert_eq!(csv_escape("H2, O2"), "\"H2, O2\"");
+        assert_eq!(csv_escape("say \"H2O\""), "\"say \"\"H2O\"\"\"");
+
+        let out = format_results(Format::Csv, &[result("H2, O2 -> H2O")]);
+        assert!(out.starts_with("input,balanced,ok\n\"H2, O2 -> H2O\",,false"));
+    }
+
+    #[test]
+    fn json_brackets() {
+        let out = format_results(Format::Json, &[result("Ca(OH)2 + HCl -> CaCl2 + H2O")]);
+        assert!(out.contains(
+            r#"{"coefficient":1,"formula":"Ca(OH)2","elements":{"Ca":1,"H":2,"O":2},"molar_mass":74.092,"#
+        ));
+    }
+
+    #[test]
+    fn json_oxidation() {
+        let out = format_results(Format::Json, &[result("H2O2 -> H2O + O2")]);
+        assert!(out.contains(
+            r#""oxidation_states":[{"element":"H","state":"+1"},{"element":"O","state":"-1"}],"oxidation_note":"peroxide, O is -1"}"#
+        ));
+        // states that can't be worked out are left out
+        let out = format_results(Format::Json, &[result("CuFe -> Cu + Fe")]);
+        assert!(
+            out.contains(r#""formula":"CuFe","elements":{"Cu":1,"Fe":1},"molar_mass":119.391}"#)
+        );
+    }
+
+    #[test]
+    fn copy_formats() {
+        let eq = balance("H2 + O2 -> H2O").unwrap();
+        assert_eq!(
+            copy_text(CopyFormat::Plain, "H2 + O2 -> H2O", &eq, false),
+            "2H2 + O2 -> 2H2O"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Unicode, "H2 + O2 -> H2O", &eq, false),
+            "2H₂ + O₂ → 2H₂O"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Latex, "H2 + O2 -> H2O", &eq, false),
+            r"2\mathrm{H_{2}} + \mathrm{O_{2}} \rightarrow 2\mathrm{H_{2}O}"
+        );
+        assert!(copy_text(CopyFormat::Json, "H2 + O2 -> H2O", &eq, false)
+            .starts_with(r#"{"input":"H2 + O2 -> H2O","equation":"2H2 + O2 -> 2H2O","reaction_type":"synthesis","#));
+    }
+
+    #[test]
+    fn copy_groups() {
+        let eq = balance("Ca(OH)2 + H3PO4 <-> Ca3(PO4)2 + H2O").unwrap();
+        assert_eq!(
+            copy_text(CopyFormat::Unicode, "", &eq, false),
+            "3Ca(OH)₂ + 2H₃PO₄ ⇌ Ca₃(PO₄)₂ + 6H₂O"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Latex, "", &eq, false),
+            r"3\mathrm{Ca(OH)_{2}} + 2\mathrm{H_{3}PO_{4}} \rightleftharpoons \mathrm{Ca_{3}(PO_{4})_{2}} + 6\mathrm{H_{2}O}"
+        );
+    }
+
+    #[test]
+    fn explicit_ones() {
+        let eq = balance("H2 + O2 -> H2O").unwrap();
+        assert_eq!(plain_equation("", &eq, true), "2H2 + 1O2 -> 2H2O");
+        assert_eq!(plain_equation("", &eq, false), "2H2 + O2 -> 2H2O");
+        assert_eq!(
+            copy_text(CopyFormat::Unicode, "", &eq, true),
+            "2H₂ + 1O₂ → 2H₂O"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Latex, "", &eq, true),
+            r"2\mathrm{H_{2}} + 1\mathrm{O_{2}} \rightarrow 2\mathrm{H_{2}O}"
+        );
+    }
+
+    #[test]
+    fn hydrates() {
+        let input = "CuSO4·5H2O -> CuSO4 + H2O";
+        let eq = balance(input).unwrap();
+        assert_eq!(
+            plain_equation(input, &eq, false),
+            "CuSO4·5H2O -> CuSO4 + 5H2O"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Unicode, input, &eq, false),
+            "CuSO₄·5H₂O → CuSO₄ + 5H₂O"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Latex, input, &eq, false),
+            r"\mathrm{CuSO_{4}\cdot 5H_{2}O} \rightarrow \mathrm{CuSO_{4}} + 5\mathrm{H_{2}O}"
+        );
+        let results = [result("2CuSO4*5H2O -> CuSO4 + H2O")];
+        assert_eq!(
+            format_results(Format::Plain, &results),
+            "CuSO4*5H2O -> CuSO4 + 5H2O\n"
+        );
+    }
+
+    #[test]
+    fn charges() {
+        let input = "Fe^3+ + Cu -> Fe^2+ + Cu^2+(aq)";
+        let eq = balance(input).unwrap();
+        assert_eq!(
+            plain_equation(input, &eq, false),
+            "2Fe^3+ + Cu -> 2Fe^2+ + Cu^2+(aq)"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Unicode, input, &eq, false),
+            "2Fe³⁺ + Cu → 2Fe²⁺ + Cu²⁺(aq)"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Latex, input, &eq, false),
+            r"2\mathrm{Fe}^{3+} + \mathrm{Cu} \rightarrow 2\mathrm{Fe}^{2+} + \mathrm{Cu}^{2+}\text{(aq)}"
+        );
+        let json = copy_text(CopyFormat::Json, input, &eq, false);
+        assert!(json.contains(r#""formula":"Fe^3+","charge":3"#));
+        // neutral compounds have no charge
+        assert!(json.contains(r#""formula":"Cu","elements""#));
+        let eq = balance("Cl- + NH4^+ -> NH4Cl").unwrap();
+        assert_eq!(
+            copy_text(CopyFormat::Unicode, "Cl- + NH4^+ -> NH4Cl", &eq, false),
+            "Cl⁻ + NH₄⁺ → NH₄Cl"
+        );
+    }
+
+    #[test]
+    fn states() {
+        let input = "NaCl(aq) + AgNO3(aq) -> AgCl(s) + NaNO3(aq)";
+        let eq = balance(input).unwrap();
+        assert_eq!(plain_equation(input, &eq, false), input);
+        assert_eq!(
+            copy_text(CopyFormat::Unicode, input, &eq, false),
+            "NaCl(aq) + AgNO₃(aq) → AgCl(s) + NaNO₃(aq)"
+        );
+        assert_eq!(
+            copy_text(CopyFormat::Latex, input, &eq, false),
+            r"\mathrm{NaCl}\text{(aq)} + \mathrm{AgNO_{3}}\text{(aq)} \rightarrow \mathrm{AgCl}\text{(s)} + \mathrm{NaNO_{3}}\text{(aq)}"
+        );
+        // brackets in the formula aren't a state
+        assert_eq!(
+            latex_formula("Fe(OH)3(s)"),
+            r"\mathrm{Fe(OH)_{3}}\text{(s)}"
+        );
+    }
+
+    #[test]
+    fn subscript_numbers() {
+        assert_eq!(subscripts("C6H12O6"), "C₆H₁₂O₆");
+        assert_eq!(subscripts("CuSO4*5H2O"), "CuSO₄*5H₂O");
+        assert_eq!(subscripts("SO4²⁻"), "SO₄²⁻");
+    }
+
+    #[test]
+    fn fractional() {
+        let input = "C2H6 + O2 -> CO2 + H2O";
+        let eq = balance(input).unwrap();
+        assert_eq!(
+            fractional_text(CopyFormat::Plain, input, &eq, 0),
+            "C2H6 + 7/2 O2 -> 2CO2 + 3H2O"
+        );
+        assert_eq!(
+            fractional_text(CopyFormat::Latex, input, &eq, 0),
+            r"\mathrm{C_{2}H_{6}} + \tfrac{7}{2}\mathrm{O_{2}} \rightarrow 2\mathrm{CO_{2}} + 3\mathrm{H_{2}O}"
+        );
+        assert_eq!(
+            fractional_text(CopyFormat::Unicode, input, &eq, 2),
+            "1/2 C₂H₆ + 7/4 O₂ → CO₂ + 3/2 H₂O"
+        );
+        // whole numbers are left as they are
+        let eq = balance("H2 + O2 -> H2O").unwrap();
+        assert_eq!(
+            fractional_text(CopyFormat::Plain, "H2 + O2 -> H2O", &eq, 0),
+            "H2 + 1/2 O2 -> H2O"
+        );
+        assert_eq!(Fraction::new(6, 4).to_string(), "3/2");
+        assert_eq!(Fraction::new(4, 2).to_string(), "2");
+    }
+}
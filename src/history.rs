@@ -0,0 +1,211 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{balance, chem::canonicalize};
+
+/// Equations entered in the tui, oldest first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<String>,
+    /// How many of the entries, from the start, were loaded rather than
+    /// entered since
+    loaded: usize,
+    /// Most entries to keep, dropping the oldest
+    max: usize,
+    /// Entry being shown while stepping through the history
+    pos: Option<usize>,
+    /// Input from before stepping through the history, restored after the newest entry
+    draft: String,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl History {
+    pub const fn new(max: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            loaded: 0,
+            max,
+            pos: None,
+            draft: String::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// The entries entered since the history was loaded, oldest first
+    pub fn this_session(&self) -> &[String] {
+        &self.entries[self.loaded..]
+    }
+
+    /// Remember an equation, unless it's empty. One that's already remembered,
+    /// however it was written, moves to the end as it's typed this time
+    pub fn push(&mut self, entry: &str) {
+        self.pos = None;
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return;
+        }
+        let new = key(entry);
+        let moved = self.entries[..self.loaded]
+            .iter()
+            .filter(|e| key(e) == new)
+            .count();
+        self.loaded -= moved;
+        self.entries.retain(|e| key(e) != new);
+        self.entries.push(entry.to_string());
+        self.truncate();
+    }
+
+    /// Step back to an older entry. `current` is kept so it can be restored by
+    /// [`History::newer`]
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let pos = match self.pos {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(pos) => pos.saturating_sub(1),
+        };
+        self.pos = Some(pos);
+        Some(&self.entries[pos])
+    }
+
+    /// Step forward to a newer entry, ending at the input from before
+    /// stepping through the history
+    pub fn newer(&mut self) -> Option<&str> {
+        let pos = self.pos? + 1;
+        if pos < self.entries.len() {
+            self.pos = Some(pos);
+            Some(&self.entries[pos])
+        } else {
+            self.pos = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Read the history saved at `path`. A missing file is an empty history
+    pub fn load(path: &Path, max: usize) -> io::Result<Self> {
+        let mut history = Self::new(max);
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                history.entries = text.lines().map(str::to_string).collect();
+                history.truncate();
+                history.loaded = history.entries.len();
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(history)
+    }
+
+    /// Write every entry to `path`, one per line
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = self.entries.join("\n");
+        text.push('\n');
+        fs::write(path, text)
+    }
+
+    /// Where history is saved, eg. `~/.local/share/balance-tui/history`
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("balance-tui").join("history"))
+    }
+
+    fn truncate(&mut self) {
+        let extra = self.entries.len().saturating_sub(self.max);
+        self.entries.drain(..extra);
+        self.loaded = self.loaded.saturating_sub(extra);
+    }
+}
+
+/// What two entries have in common when they're the same equation. Entries
+/// that don't parse are only the same as themselves
+fn key(entry: &str) -> String {
+    match balance::parse(entry) {
+        Ok(eq) => canonicalize(&eq),
+        Err(_) => entry.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push() {
+        let mut history = History::new(2);
+        history.push("H2 + O2 -> H2O");
+        history.push("H2 + O2 -> H2O");
+        history.push("  ");
+        assert_eq!(history.entries(), ["H2 + O2 -> H2O"]);
+
+        history.push("Fe + O2 -> Fe2O3");
+        history.push("C + O2 -> CO2");
+        assert_eq!(history.entries(), ["Fe + O2 -> Fe2O3", "C + O2 -> CO2"]);
+
+        // the same equation written another way moves to the end
+        history.push("2H2 + O2 = 2H2O");
+        history.push("O2 + 2H2 -> 2H2O");
+        assert_eq!(history.entries(), ["C + O2 -> CO2", "O2 + 2H2 -> 2H2O"]);
+        history.push("C+O2->CO2");
+        assert_eq!(history.entries(), ["O2 + 2H2 -> 2H2O", "C+O2->CO2"]);
+
+        let mut history = History::new(0);
+        history.push("C + O2 -> CO2");
+        assert!(history.entries().is_empty());
+    }
+
+    #[test]
+    fn navigate() {
+        let mut history = History::default();
+        assert_eq!(history.older("draft"), None);
+        history.push("a");
+        history.push("b");
+
+        assert_eq!(history.older("draft"), Some("b"));
+        assert_eq!(history.older("b"), Some("a"));
+        assert_eq!(history.older("a"), Some("a"));
+        assert_eq!(history.newer(), Some("b"));
+        assert_eq!(history.newer(), Some("draft"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("balance-tui-history-{}", std::process::id()));
+        let path = dir.join("history");
+        assert_eq!(History::load(&path, 10).unwrap(), History::new(10));
+
+        let mut history = History::new(10);
+        history.push("H2 + O2 -> H2O");
+        history.push("Fe + O2 -> Fe2O3");
+        history.save(&path).unwrap();
+        let mut loaded = History::load(&path, 10).unwrap();
+        assert_eq!(loaded.entries(), history.entries());
+        assert_eq!(history.this_session().len(), 2);
+        assert!(loaded.this_session().is_empty());
+        // entered again, it's from this session
+        loaded.push("O2 + H2 -> H2O");
+        loaded.push("C + O2 -> CO2");
+        assert_eq!(loaded.this_session(), ["O2 + H2 -> H2O", "C + O2 -> CO2"]);
+        assert_eq!(loaded.entries().len(), 3);
+        assert_eq!(
+            History::load(&path, 1).unwrap().entries(),
+            ["Fe + O2 -> Fe2O3"]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use chem_eq::Compound;
+
+use crate::{error::Error, normalize::tidy};
+
+/// What joins the bound water to the rest of a hydrate, eg. `CuSO4·5H2O`.
+/// A full stop or `*` stand in for the dot where it's hard to type
+pub const SEPARATORS: [char; 3] = ['·', '.', '*'];
+
+/// Rewrite the hydrates in a tidied equation as formulas chem-eq can parse,
+/// with each element once, eg. `CuSO4·5H2O` becomes `CuSO9H10`. chem-eq's
+/// balancer only counts the last of an element in a compound, so brackets like
+/// `CuSO4(H2O)5` wouldn't do. Nested hydrates are left as they are to fail,
+/// [`check`] says why
+pub fn expand(equation: &str) -> String {
+    let mut out = String::with_capacity(equation.len());
+    for (compound, is_compound) in split(equation) {
+        match expand_compound(compound) {
+            Some(expanded) if is_compound => out.push_str(&expanded),
+            _ => out.push_str(compound),
+        }
+    }
+    out
+}
+
+/// Make sure every hydrate in an equation is one compound and one bound part,
+/// as a hydrate of a hydrate can't be written in brackets chem-eq reads
+pub fn check(input: &str) -> Result<(), Error> {
+    let tidied = tidy(input);
+    let nested = split(&tidied)
+        .into_iter()
+        .filter(|(_, is_compound)| *is_compound)
+        .find(|(compound, _)| {
+            let mut depth = 0_usize;
+            let mut separators = 0;
+            for c in compound.chars() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth = depth.saturating_sub(1),
+                    c if SEPARATORS.contains(&c) => {
+                        // one is fine, unless it's bracketed into a bigger compound
+                        separators += if depth == 0 { 1 } else { 2 };
+                    }
+                    _ => {}
+                }
+            }
+            separators > 1
+        });
+    match nested {
+        Some((compound, _)) => Err(Error::NestedHydrate(compound.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Expand one hydrate, `None` if it isn't one or is nested
+fn expand_compound(compound: &str) -> Option<String> {
+    let (main, bound) = compound.split_once(SEPARATORS)?;
+    if bound.contains(SEPARATORS) {
+        return None;
+    }
+    // a state like `(s)` belongs to the whole hydrate
+    let (bound, state) = match bound.rfind('(') {
+        Some(i) if bound[i + 1..].starts_with(|c: char| c.is_lowercase()) => bound.split_at(i),
+        _ => (bound, ""),
+    };
+    let (coefficient, main) = main.split_at(
+        main.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(main.len()),
+    );
+    let digits = bound
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(bound.len());
+    let count = bound[..digits].parse::<usize>().unwrap_or(1);
+
+    // add up each element, in the order they're first seen
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    let main = Compound::parse(main).ok()?;
+    let bound = Compound::parse(&bound[digits..]).ok()?;
+    let elements = main
+        .elements
+        .iter()
+        .map(|e| (e.symbol(), e.count))
+        .chain(bound.elements.iter().map(|e| (e.symbol(), e.count * count)));
+    for (symbol, n) in elements {
+        match counts.iter_mut().find(|(s, _)| *s == symbol) {
+            Some((_, total)) => *total += n,
+            None => counts.push((symbol, n)),
+        }
+    }
+    let formula: String = counts
+        .iter()
+        .map(|(symbol, n)| match n {
+            1 => symbol.to_string(),
+            n => format!("{symbol}{n}"),
+        })
+        .collect();
+    Some(format!("{coefficient}{formula}{state}"))
+}
+
+/// Split a tidied equation into compounds and what's between them, marking
+/// which parts are compounds
+fn split(equation: &str) -> Vec<(&str, bool)> {
+    let mut parts = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+    let mut in_compound = false;
+    for (i, c) in equation.char_indices() {
+        let between = depth == 0 && (c.is_whitespace() || matches!(c, '+' | '-' | '<' | '>'));
+        if between == in_compound {
+            if i > start {
+                parts.push((&equation[start..i], in_compound));
+            }
+            start = i;
+            in_compound = !between;
+        }
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    if start < equation.len() {
+        parts.push((&equation[start..], in_compound));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{balance::balance, chem, normalize::normalize};
+
+    #[test]
+    fn expands() {
+        assert_eq!(expand("CuSO4·5H2O"), "CuSO9H10");
+        assert_eq!(expand("CuSO4*5H2O"), "CuSO9H10");
+        assert_eq!(expand("CuSO4.5H2O"), "CuSO9H10");
+        assert_eq!(expand("2CuSO4·5H2O(s) -> CuSO4"), "2CuSO9H10(s) -> CuSO4");
+        assert_eq!(expand("CaSO4·H2O+H2O"), "CaSO5H2+H2O");
+        assert_eq!(expand("Ca(OH)2·2NH3"), "CaO2H8N2");
+        assert_eq!(expand("H2 + O2 -> H2O"), "H2 + O2 -> H2O");
+        // nested hydrates are left alone
+        assert_eq!(expand("A·B·C"), "A·B·C");
+    }
+
+    #[test]
+    fn balances() {
+        let eq = balance("CuSO4·5H2O -> CuSO4 + H2O").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [1, 1, 5]);
+        // the compound's coefficient multiplies the bound water too
+        let eq = balance("2Na2CO3*10H2O -> Na2CO3 + H2O").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [1, 1, 10]);
+    }
+
+    #[test]
+    fn molar_mass() {
+        // copper(II) sulfate pentahydrate, however the dot is written
+        for formula in ["CuSO4·5H2O", "CuSO4.5H2O", "CuSO4*5H2O"] {
+            let cmp = Compound::parse(&normalize(formula)).unwrap();
+            assert!((chem::molar_mass(&cmp) - 249.68).abs() < 0.01);
+            let counts = chem::element_counts(&cmp);
+            assert_eq!(counts["H"], 10);
+            assert_eq!(counts["O"], 9);
+        }
+    }
+
+    #[test]
+    fn typed() {
+        let input = "CuSO4*5H2O(s) = CuSO4 + H2O";
+        let eq = balance(input).unwrap();
+        assert_eq!(
+            chem::typed_formulas(input, &eq),
+            ["CuSO4*5H2O(s)", "CuSO4", "H2O"]
+        );
+        let eq = balance("H2 + O2 -> H2O").unwrap();
+        assert_eq!(
+            chem::typed_formulas("H2 + O2 -> H2O", &eq),
+            ["H2", "O2", "H2O"]
+        );
+    }
+
+    #[test]
+    fn nested() {
+        assert!(check("CuSO4·5H2O -> CuSO4 + H2O").is_ok());
+        assert!(check("H2 + O2 -> H2O").is_ok());
+        let e = check("A·B·C -> A + B + C").unwrap_err();
+        assert!(matches!(&e, Error::NestedHydrate(c) if c == "A·B·C"));
+        assert!(check("(CuSO4·5H2O)2 -> CuSO4 + H2O").is_err());
+        assert_eq!(
+            balance("Cu·SO4·5H2O -> CuSO4 + H2O")
+                .unwrap_err()
+                .to_string(),
+            "Cu·SO4·5H2O is a hydrate of a hydrate, write it with one · or *"
+        );
+    }
+}
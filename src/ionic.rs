@@ -0,0 +1,370 @@
+use std::fmt;
+
+use chem_eq::{Direction, Equation};
+
+use crate::{charge, chem::typed_formulas, matrix::gcd};
+
+/// Ions made of more than one element
+const POLYATOMIC: &[(&str, i64)] = &[
+    ("C2H3O2", -1),
+    ("CH3COO", -1),
+    ("Cr2O7", -2),
+    ("HCO3", -1),
+    ("HSO4", -1),
+    ("ClO4", -1),
+    ("ClO3", -1),
+    ("MnO4", -1),
+    ("CrO4", -2),
+    ("C2O4", -2),
+    ("SO4", -2),
+    ("SO3", -2),
+    ("NO3", -1),
+    ("NO2", -1),
+    ("PO4", -3),
+    ("CO3", -2),
+    ("SCN", -1),
+    ("NH4", 1),
+    ("OH", -1),
+    ("CN", -1),
+];
+
+/// Elements that are anions on their own at the end of a salt
+const MONATOMIC: &[(&str, i64)] = &[
+    ("F", -1),
+    ("Cl", -1),
+    ("Br", -1),
+    ("I", -1),
+    ("O", -2),
+    ("S", -2),
+    ("N", -3),
+    ("P", -3),
+];
+
+/// Acids that ionize completely in water, the rest stay together
+const STRONG_ACIDS: &[&str] = &["HCl", "HBr", "HI", "HNO3", "H2SO4", "HClO4", "HClO3"];
+
+/// Cations that make every one of their salts soluble
+const ALWAYS_SOLUBLE: &[&str] = &["Li", "Na", "K", "Rb", "Cs", "NH4"];
+
+/// Anions whose salts are soluble, bar the cations listed
+const SOLUBLE_ANIONS: &[(&str, &[&str])] = &[
+    ("NO3", &[]),
+    ("C2H3O2", &[]),
+    ("CH3COO", &[]),
+    ("ClO3", &[]),
+    ("ClO4", &[]),
+    ("HCO3", &[]),
+    ("MnO4", &[]),
+    ("Cl", &["Ag", "Pb", "Hg"]),
+    ("Br", &["Ag", "Pb", "Hg"]),
+    ("I", &["Ag", "Pb", "Hg"]),
+    ("SO4", &["Ba", "Sr", "Ca", "Pb", "Hg"]),
+    ("F", &["Mg", "Ca", "Sr", "Ba", "Pb"]),
+    // only the hydroxides of the heavier group 2 metals dissolve
+    ("OH", &[]),
+];
+
+/// A net ionic equation, what's left once the ions that are the same on both
+/// sides are taken away. Ions are written like `Ag^+(aq)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetIonic {
+    pub left: Vec<(usize, String)>,
+    pub right: Vec<(usize, String)>,
+    pub direction: Direction,
+}
+
+/// A salt, acid or base split into its ions, eg. `Ba(NO3)2` is one `Ba` with
+/// a charge of 2 and two `NO3` with a charge of -1
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Ions<'a> {
+    cation: &'a str,
+    cations: usize,
+    cation_charge: i64,
+    anion: &'a str,
+    anions: usize,
+    anion_charge: i64,
+}
+
+/// Write the net ionic equation of a balanced equation, `input` is what it
+/// was balanced from. Strong electrolytes in solution are split into ions.
+/// Compounds without a state are given one by the solubility rules. `None`
+/// when every ion is a spectator, so there's no net reaction
+pub fn net_ionic(input: &str, eq: &Equation) -> Option<NetIonic> {
+    let mut sides: [Vec<(usize, String)>; 2] = [Vec::new(), Vec::new()];
+    for (i, (cmp, formula)) in eq
+        .iter_compounds()
+        .zip(typed_formulas(input, eq))
+        .enumerate()
+    {
+        let side = &mut sides[usize::from(i >= eq.left().len())];
+        match dissociate(&formula) {
+            Some(ions) => {
+                add(
+                    side,
+                    ions.cations * cmp.coefficient,
+                    ion(ions.cation, ions.cation_charge),
+                );
+                add(
+                    side,
+                    ions.anions * cmp.coefficient,
+                    ion(ions.anion, ions.anion_charge),
+                );
+            }
+            None => add(side, cmp.coefficient, with_state(&formula)),
+        }
+    }
+
+    // spectators are on both sides
+    let [mut left, mut right] = sides;
+    for (count, species) in &mut left {
+        if let Some((other, _)) = right.iter_mut().find(|(_, s)| s == species) {
+            let spectators = (*count).min(*other);
+            *count -= spectators;
+            *other -= spectators;
+        }
+    }
+    left.retain(|(n, _)| *n > 0);
+    right.retain(|(n, _)| *n > 0);
+    if left.is_empty() || right.is_empty() {
+        return None;
+    }
+
+    let divisor = left
+        .iter()
+        .chain(&right)
+        .fold(0, |acc, (n, _)| gcd(acc, *n as i128)) as usize;
+    for (n, _) in left.iter_mut().chain(&mut right) {
+        *n /= divisor;
+    }
+    Some(NetIonic {
+        left,
+        right,
+        direction: eq.direction().clone(),
+    })
+}
+
+/// Count `count` more of `species` on one side
+fn add(side: &mut Vec<(usize, String)>, count: usize, species: String) {
+    match side.iter_mut().find(|(_, s)| *s == species) {
+        Some((n, _)) => *n += count,
+        None => side.push((count, species)),
+    }
+}
+
+/// Write an ion in solution, eg. `SO4^2-(aq)`
+fn ion(formula: &str, charge: i64) -> String {
+    format!("{formula}^{}(aq)", charge::format(charge))
+}
+
+/// A compound that stays together, with a state if it hasn't got one and
+/// one's known
+fn with_state(formula: &str) -> String {
+    let (body, state) = charge::split_state(formula);
+    match state {
+        "" if body == "H2O" => format!("{body}(l)"),
+        "" => match split_ions(body) {
+            Some(ions) if !ions.is_acid() && !soluble(&ions) => format!("{body}(s)"),
+            _ => body.to_string(),
+        },
+        _ => formula.to_string(),
+    }
+}
+
+/// Split a compound into its ions if it's a strong electrolyte in solution.
+/// Without a state, a salt is in solution if the solubility rules say it
+/// dissolves
+fn dissociate(formula: &str) -> Option<Ions<'_>> {
+    let (body, state) = charge::split_state(formula);
+    let ions = split_ions(body)?;
+    let strong = if ions.is_acid() {
+        STRONG_ACIDS.contains(&body)
+    } else {
+        match state {
+            "(aq)" => true,
+            "" => soluble(&ions),
+            _ => false,
+        }
+    };
+    strong.then_some(ions)
+}
+
+/// Split a formula into a cation and an anion, working the cation's charge
+/// out from the anion's, eg. `Fe2(SO4)3` is iron(III) and sulfate
+fn split_ions(formula: &str) -> Option<Ions<'_>> {
+    let (cation, rest) = if let Some(rest) = formula.strip_prefix("(NH4)") {
+        ("NH4", rest)
+    } else if let Some(rest) = formula.strip_prefix("NH4") {
+        ("NH4", rest)
+    } else {
+        let len = formula
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !c.is_ascii_lowercase())
+            .map_or(formula.len(), |(i, _)| i);
+        formula.split_at(len)
+    };
+    if !cation.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let cations = count(&rest[..digits])?;
+    let rest = &rest[digits..];
+
+    let anion_charge = |anion: &str| {
+        POLYATOMIC
+            .iter()
+            .chain(MONATOMIC)
+            .find(|(a, _)| *a == anion)
+            .map(|(_, charge)| *charge)
+            .filter(|c| *c < 0)
+    };
+    // `SO4` is one sulfate, but `Cl3` is three chlorides
+    let (anion, anions) = match rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+        Some((anion, n)) => (anion, count(n)?),
+        None if anion_charge(rest).is_some() => (rest, 1),
+        None => {
+            let body = rest.trim_end_matches(|c: char| c.is_ascii_digit());
+            (body, count(&rest[body.len()..])?)
+        }
+    };
+    let anion_charge = anion_charge(anion)?;
+    // the charges have to cancel out with a whole number on each cation
+    let total = -anion_charge * anions as i64;
+    (total % cations as i64 == 0).then_some(Ions {
+        cation,
+        cations,
+        cation_charge: total / cations as i64,
+        anion,
+        anions,
+        anion_charge,
+    })
+}
+
+/// Read a subscript, where none is 1
+fn count(digits: &str) -> Option<usize> {
+    match digits {
+        "" => Some(1),
+        digits => digits.parse().ok().filter(|n| *n > 0),
+    }
+}
+
+/// Whether a salt dissolves in water, by the usual solubility rules
+fn soluble(ions: &Ions) -> bool {
+    if ALWAYS_SOLUBLE.contains(&ions.cation) {
+        return true;
+    }
+    match SOLUBLE_ANIONS.iter().find(|(a, _)| *a == ions.anion) {
+        Some(("OH", _)) => matches!(ions.cation, "Ca" | "Sr" | "Ba"),
+        Some((_, except)) => !except.contains(&ions.cation),
+        None => false,
+    }
+}
+
+impl Ions<'_> {
+    fn is_acid(&self) -> bool {
+        self.cation == "H"
+    }
+}
+
+impl fmt::Display for NetIonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |species: &[(usize, String)]| {
+            species
+                .iter()
+                .map(|(n, s)| match n {
+                    1 => s.clone(),
+                    n => format!("{n}{s}"),
+                })
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+        write!(
+            f,
+            "{} {} {}",
+            side(&self.left),
+            self.direction,
+            side(&self.right)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::balance;
+
+    fn net_str(input: &str) -> Option<String> {
+        let eq = balance(input).unwrap();
+        net_ionic(input, &eq).map(|n| n.to_string())
+    }
+
+    #[test]
+    fn precipitates() {
+        assert_eq!(
+            net_str("NaCl(aq) + AgNO3(aq) -> AgCl(s) + NaNO3(aq)").as_deref(),
+            Some("Cl^-(aq) + Ag^+(aq) -> AgCl(s)")
+        );
+        assert_eq!(
+            net_str("BaCl2(aq) + Na2SO4(aq) -> BaSO4(s) + NaCl(aq)").as_deref(),
+            Some("Ba^2+(aq) + SO4^2-(aq) -> BaSO4(s)")
+        );
+        // the counts are cut down to the smallest they can be
+        assert_eq!(
+            net_str("Pb(NO3)2(aq) + KI(aq) -> PbI2(s) + KNO3(aq)").as_deref(),
+            Some("Pb^2+(aq) + 2I^-(aq) -> PbI2(s)")
+        );
+        // states can be left for the solubility rules to work out
+        assert_eq!(
+            net_str("FeCl3 + NaOH -> Fe(OH)3 + NaCl").as_deref(),
+            Some("Fe^3+(aq) + 3OH^-(aq) -> Fe(OH)3(s)")
+        );
+    }
+
+    #[test]
+    fn water_and_gas() {
+        assert_eq!(
+            net_str("HCl(aq) + NaOH(aq) -> NaCl(aq) + H2O(l)").as_deref(),
+            Some("H^+(aq) + OH^-(aq) -> H2O(l)")
+        );
+        assert_eq!(
+            net_str("HCl + KOH -> KCl + H2O").as_deref(),
+            Some("H^+(aq) + OH^-(aq) -> H2O(l)")
+        );
+        assert_eq!(
+            net_str("Na2CO3(aq) + HCl(aq) -> NaCl(aq) + H2O(l) + CO2(g)").as_deref(),
+            Some("CO3^2-(aq) + 2H^+(aq) -> H2O(l) + CO2(g)")
+        );
+        // weak acids stay together
+        assert_eq!(
+            net_str("HC2H3O2(aq) + NaOH(aq) -> NaC2H3O2(aq) + H2O(l)").as_deref(),
+            Some("HC2H3O2(aq) + OH^-(aq) -> C2H3O2^-(aq) + H2O(l)")
+        );
+    }
+
+    #[test]
+    fn no_reaction() {
+        assert_eq!(net_str("NaCl(aq) + KNO3(aq) -> NaNO3(aq) + KCl(aq)"), None);
+        assert_eq!(net_str("NH4Cl + NaNO3 -> NH4NO3 + NaCl"), None);
+    }
+
+    #[test]
+    fn ions() {
+        let ions = split_ions("Fe2(SO4)3").unwrap();
+        assert_eq!(
+            (ions.cation, ions.cations, ions.cation_charge),
+            ("Fe", 2, 3)
+        );
+        assert_eq!((ions.anion, ions.anions, ions.anion_charge), ("SO4", 3, -2));
+        let ions = split_ions("(NH4)3PO4").unwrap();
+        assert_eq!(
+            (ions.cation, ions.cations, ions.cation_charge),
+            ("NH4", 3, 1)
+        );
+        assert_eq!(split_ions("CH4"), None);
+        assert_eq!(split_ions("Cl2"), None);
+        assert_eq!(dissociate("H2O(l)"), None);
+        assert!(soluble(&split_ions("Ba(OH)2").unwrap()));
+        assert!(!soluble(&split_ions("Mg(OH)2").unwrap()));
+        assert!(!soluble(&split_ions("CaCO3").unwrap()));
+    }
+}
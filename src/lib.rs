@@ -0,0 +1,50 @@
+//! Balance chemical equations from the command line or a tui.
+//!
+//! The binary only sets up the terminal, everything else lives here so it can
+//! be tested.
+
+pub mod app;
+pub mod balance;
+pub mod batch;
+pub mod charge;
+pub mod chem;
+pub mod classify;
+pub mod clipboard;
+pub mod config;
+pub mod diagnose;
+pub mod editor;
+pub mod elements;
+pub mod equilibrium;
+pub mod error;
+pub mod event;
+pub mod examples;
+pub mod favorites;
+pub mod format;
+pub mod history;
+pub mod hydrate;
+pub mod ionic;
+pub mod matrix;
+pub mod normalize;
+pub mod oxidation;
+pub mod quiz;
+pub mod redox;
+pub mod render;
+pub mod report;
+pub mod serve;
+pub mod session;
+pub mod stoich;
+pub mod suggest;
+pub mod table;
+pub mod tally;
+pub mod theme;
+pub mod title;
+pub mod undo;
+pub mod verify;
+pub mod watch;
+pub mod worker;
+
+pub use crate::{
+    app::{handle_key, App, InputMode},
+    chem::canonicalize,
+    error::Error,
+};
@@ -1,19 +1,496 @@
-use chem_eq::balance::EquationBalancer;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, IsTerminal, Read},
+    path::Path,
+    process::ExitCode,
+    sync::Mutex,
+};
+
+use balance_tui::{
+    app::{App, InputMode},
+    batch::{self, BatchResult},
+    charge, chem,
+    clipboard::Clipboard,
+    config::Config,
+    equilibrium,
+    format::{self, CopyFormat, Format},
+    ionic,
+    normalize::normalize,
+    oxidation, redox,
+    report::{self, Entry},
+    serve,
+    session::Session,
+    stoich::{self, Conversion, StoichError},
+    verify,
+};
+use chem_eq::{Compound, Equation};
+use color_eyre::eyre::WrapErr;
+use rayon::ThreadPool;
 
 mod cli;
 mod ui;
 
-fn main() -> color_eyre::Result<()> {
+fn main() -> color_eyre::Result<ExitCode> {
     // setup
     color_eyre::install()?;
-    let args = cli::chem_args().run();
+    let args = cli::parse();
+    let format = if args.json { Format::Json } else { args.format };
+    if let Some(path) = args.log.as_ref() {
+        init_logging(path)?;
+    }
+
+    if let Some(formula) = args.percent_composition.as_ref() {
+        return Ok(print_composition(formula));
+    }
+    if let Some(conversion) = args.convert.as_ref() {
+        return Ok(print_conversion(conversion));
+    }
+
+    if args.serve {
+        serve::serve(io::stdin().lock(), io::stdout().lock(), args.redox)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(path) = args.export.as_ref() {
+        return export(&args, path);
+    }
+
+    if let Some(path) = args.input.as_ref() {
+        let text = read_input(path)?;
+        let pool = thread_pool(&args)?;
+        let results = balance_text(&args, pool.as_ref(), &text);
+        if args.check {
+            return Ok(check_results(&results));
+        }
+        print!("{}", format::format_results(format, &results));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(path) = args.watch.as_ref() {
+        let pool = thread_pool(&args)?;
+        balance_tui::watch::watch(path, |text| {
+            let results = balance_text(&args, pool.as_ref(), text);
+            print!("{}", format::format_results(format, &results));
+        })
+        .wrap_err_with(|| format!("couldn't watch {}", path.display()))?;
+        // ctrl-c is how it's meant to stop, so that's a success
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(input) = args.equation.as_ref().filter(|_| !args.tui) {
+        // one that can't be completed or parsed is balanced as written for its error
+        let completed = args.complete.and_then(|kind| kind.complete(input));
+        let input = completed.as_ref().unwrap_or(input);
+        let reversed = args.reverse.then(|| chem::reverse(input)).flatten();
+        let input = reversed.as_ref().unwrap_or(input);
+        // placeholders are balanced as elements the equation doesn't have, then put back
+        let (input, stand_ins) = match args.allow_unknown_elements {
+            true => chem::stand_in_unknown(input),
+            false => (input.clone(), Vec::new()),
+        };
+        let input = &input;
+        if args.verify {
+            return Ok(print_verdict(input));
+        }
+        let res = match args.redox {
+            Some(medium) => BatchResult::redox(input, medium),
+            None => BatchResult::new(input),
+        };
+        if args.check {
+            return Ok(check_results(&[res]));
+        }
+        if let Some(medium) = args.redox.filter(|_| args.half_reactions) {
+            if let Ok(halves) = redox::balance(input, medium) {
+                println!("oxidation: {}", halves.oxidation);
+                println!("reduction: {}", halves.reduction);
+            }
+        }
+        match &res.output {
+            Err(e) if format == Format::Plain => eprintln!("error: {e}"),
+            Ok(eq) if args.fractional && format == Format::Plain => {
+                let typed = chem::typed_formulas(&res.input, eq);
+                let reference = match args.per.as_deref() {
+                    Some(per) => stoich::find(&typed, eq, per),
+                    None => Ok(0),
+                };
+                match reference {
+                    Ok(i) => println!(
+                        "{}",
+                        chem::restore_unknown(
+                            &format::fractional_text(CopyFormat::Plain, &res.input, eq, i),
+                            &stand_ins
+                        )
+                    ),
+                    Err(e) => {
+                        eprintln!("error: {e}");
+                        return Ok(ExitCode::FAILURE);
+                    }
+                }
+            }
+            _ => print!(
+                "{}",
+                chem::restore_unknown(
+                    &format::format_results(format, std::slice::from_ref(&res)),
+                    &stand_ins
+                )
+            ),
+        }
+        // the states are plain text, they'd break the other formats
+        if let (Ok(eq), true) = (&res.output, args.oxidation && format == Format::Plain) {
+            print_oxidation(&res.input, eq);
+        }
+        if let (Ok(eq), true) = (&res.output, args.kc && format == Format::Plain) {
+            if let Some(kc) = equilibrium::kc(&res.input, eq) {
+                println!("{kc}");
+                println!("{}", kc.latex());
+            }
+        }
+        if let (Ok(eq), true) = (&res.output, args.net_ionic && format == Format::Plain) {
+            match ionic::net_ionic(&res.input, eq) {
+                Some(net) => println!("{net}"),
+                None => println!("no net reaction"),
+            }
+        }
+        if let (Ok(eq), Some(given)) = (
+            &res.output,
+            args.given.as_ref().filter(|_| format == Format::Plain),
+        ) {
+            match stoich::amounts(&res.input, eq, given) {
+                Ok(amounts) => print!("{}", stoich::table(&amounts)),
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        if let (Ok(eq), Some(givens)) = (
+            &res.output,
+            args.amounts.as_ref().filter(|_| format == Format::Plain),
+        ) {
+            match stoich::limiting(&res.input, eq, &givens.0) {
+                Ok(y) => print!("{}", stoich::report(&y)),
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        if let (Ok(eq), Some(actual)) = (
+            &res.output,
+            args.actual.as_ref().filter(|_| format == Format::Plain),
+        ) {
+            let givens = match (&args.amounts, &args.given) {
+                (Some(givens), _) => givens.0.as_slice(),
+                (None, Some(given)) => std::slice::from_ref(given),
+                (None, None) => {
+                    eprintln!(
+                        "error: --actual needs an amount of a reactant from --given or --amounts"
+                    );
+                    return Ok(ExitCode::FAILURE);
+                }
+            };
+            match stoich::percent_yield(&res.input, eq, givens, actual) {
+                Ok(p) => {
+                    println!(
+                        "theoretical yield: {} {} {}",
+                        stoich::round(p.theoretical),
+                        p.unit,
+                        p.formula
+                    );
+                    println!("percent yield: {}%", stoich::round(p.percent));
+                }
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        if let (Ok(eq), Some(pair)) = (
+            &res.output,
+            args.ratio.as_ref().filter(|_| format == Format::Plain),
+        ) {
+            match stoich::ratio(&res.input, eq, pair) {
+                Ok((a, b)) => println!("{}:{} {a}:{b}", pair.first, pair.second),
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        return Ok(exit_code(&res));
+    }
+
+    // without a terminal the tui would wait forever for keys that never come
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        eprintln!("error: the tui needs a terminal, pass an equation or use `--input -` to read from stdin");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let config = Config::load().wrap_err("couldn't read config file")?;
+    let input_mode = if args.edit {
+        InputMode::Editing
+    } else {
+        InputMode::Normal
+    };
+    let mut app = App {
+        input_mode,
+        max_input: config.tui.max_input_len,
+        confirm_quit: config.tui.confirm_quit,
+        explicit_ones: config.tui.explicit_ones,
+        show_timing: args.debug || config.tui.show_timing,
+        auto_capitalize: config.tui.auto_capitalize,
+        show_help: config.tui.show_help,
+        subscripts: config.tui.subscripts,
+        theme: config.tui.theme,
+        ..Default::default()
+    };
+    if let Some(input) = args.equation.as_ref() {
+        app.set_input(input);
+    } else if args.paste {
+        // an empty clipboard just starts blank
+        if let Some(text) = Clipboard::default().paste() {
+            app.set_input(&text);
+        }
+    }
+    if args.quiz {
+        app.toggle_quiz();
+    }
+    let app = ui::tui(app, &config, args.osc52, args.resume)?;
+
+    // keep the result in the scrollback now the alternate screen is gone
+    if !args.no_print_on_exit {
+        if let Some(res) = app.last_balanced {
+            print!("{}", format::format_results(format, &[res]));
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
 
-    if let Some(eq) = args.equation.as_ref() {
-        println!("{}", EquationBalancer::from(eq).balance()?);
-        return Ok(());
+/// The threads `--jobs` asks to balance batches on, built once up front so a
+/// failure stops straight away instead of on every change to a watched file.
+/// `None` uses the current thread for one job, otherwise rayon's own threads
+fn thread_pool(args: &cli::ChemArgs) -> color_eyre::Result<Option<ThreadPool>> {
+    match args.jobs {
+        Some(jobs) if jobs > 1 => batch::pool(jobs)
+            .map(Some)
+            .wrap_err_with(|| format!("couldn't start {jobs} threads to balance with")),
+        _ => Ok(None),
     }
+}
+
+/// Balance each equation in a batch file the way the args ask for
+fn balance_text(args: &cli::ChemArgs, pool: Option<&ThreadPool>, text: &str) -> Vec<BatchResult> {
+    let lines = batch::equations(text);
+    let results = match (pool, args.jobs) {
+        (Some(pool), _) => batch::balance_all_in(pool, &lines, args.redox),
+        (None, Some(1)) => batch::balance_all_serial(&lines, args.redox),
+        (None, _) => batch::balance_all(&lines, args.redox),
+    };
+    if args.unique {
+        batch::dedupe(results)
+    } else {
+        results
+    }
+}
 
-    ui::tui()?;
+/// Write the report for `--export` of the equations from `--input`, the one
+/// given or the saved session
+fn export(args: &cli::ChemArgs, path: &Path) -> color_eyre::Result<ExitCode> {
+    let balance = |input: &str| match args.redox {
+        Some(medium) => BatchResult::redox(input, medium),
+        None => BatchResult::new(input),
+    };
+    let entries: Vec<Entry> = if let Some(input) = args.input.as_ref() {
+        let text = read_input(input)?;
+        balance_text(args, thread_pool(args)?.as_ref(), &text)
+            .into_iter()
+            .map(Entry::new)
+            .collect()
+    } else if let Some(input) = args.equation.as_ref() {
+        let result = balance(input);
+        let givens = match (&args.amounts, &args.given) {
+            (Some(givens), _) => givens.0.clone(),
+            (None, Some(given)) => vec![given.clone()],
+            (None, None) => Vec::new(),
+        };
+        let amounts = match &result.output {
+            Ok(eq) if !givens.is_empty() => match stoich::limiting(&result.input, eq, &givens) {
+                Ok(y) => Some(y),
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            },
+            _ => None,
+        };
+        vec![Entry { result, amounts }]
+    } else {
+        let session = match Session::path().map(|p| Session::load(&p)) {
+            Some(Ok(Some(session))) => session,
+            Some(Err(e)) => {
+                eprintln!("error: couldn't read the saved session: {e}");
+                return Ok(ExitCode::FAILURE);
+            }
+            _ => {
+                eprintln!("error: nothing to export, pass an equation or --input, or save a session with ctrl-s in the tui");
+                return Ok(ExitCode::FAILURE);
+            }
+        };
+        let balance = |input: &str| match session.toggles.redox.or(args.redox) {
+            Some(medium) => BatchResult::redox(input, medium),
+            None => BatchResult::new(input),
+        };
+        session
+            .tabs
+            .iter()
+            .map(|tab| Entry::new(balance(&tab.input)))
+            .collect()
+    };
+
+    match fs::write(path, report::markdown(&entries)) {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        Err(e) => {
+            eprintln!(
+                "error: couldn't write the report to {}: {e}",
+                path.display()
+            );
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// Report each equation that couldn't be balanced on stderr for `--check`
+fn check_results(results: &[BatchResult]) -> ExitCode {
+    let mut invalid = 0;
+    for res in results {
+        if let Err(e) = &res.output {
+            // a charge that doesn't add up is often why ions don't balance
+            let charge = match charge::net(&res.input) {
+                Some((left, right)) => format!(
+                    " (net charge {} on the left, {} on the right)",
+                    charge::format_net(left),
+                    charge::format_net(right)
+                ),
+                None => String::new(),
+            };
+            eprintln!("error: {}: {e}{charge}", res.input);
+            invalid += 1;
+        }
+    }
+    match results {
+        [res] => exit_code(res),
+        _ if invalid == 0 => ExitCode::SUCCESS,
+        _ => {
+            eprintln!("{invalid} of {} equations are invalid", results.len());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Print the percent composition of a formula, with its molar mass first
+fn print_composition(formula: &str) -> ExitCode {
+    let parts = Compound::parse(&normalize(formula))
+        .ok()
+        .and_then(|cmp| Some((chem::molar_mass(&cmp), stoich::composition(&cmp)?)));
+    match parts {
+        Some((mass, parts)) => {
+            println!("{formula} {} g/mol", stoich::round(mass));
+            print!("{}", stoich::composition_table(&parts));
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("error: couldn't read {formula:?} as a formula");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Print whether the coefficients an equation is written with balance it for
+/// `--verify`, exiting with a code for each answer
+fn print_verdict(input: &str) -> ExitCode {
+    match verify::verify(input) {
+        Some(verdict) => {
+            println!("{verdict}");
+            ExitCode::from(verdict.exit_code())
+        }
+        None => {
+            eprintln!("error: couldn't read {input:?} as an equation");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Print an amount of a compound in the other unit, eg. `18 g H2O = 0.9992 mol`
+fn print_conversion(conversion: &Conversion) -> ExitCode {
+    match stoich::convert(conversion) {
+        Ok(converted) => {
+            println!(
+                "{conversion} = {} {}",
+                stoich::round(converted),
+                conversion.unit.toggled()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            match e {
+                StoichError::InvalidFormula(_) => ExitCode::from(2),
+                _ => ExitCode::FAILURE,
+            }
+        }
+    }
+}
+
+/// Print the oxidation states of each compound, then what was oxidized and
+/// what was reduced
+fn print_oxidation(input: &str, eq: &Equation) {
+    for (formula, states) in oxidation::compounds(input, eq) {
+        println!("{}", oxidation::describe(&formula, states.as_deref()));
+    }
+    for change in oxidation::changes(input, eq) {
+        let verb = if change.oxidized() {
+            "oxidized"
+        } else {
+            "reduced"
+        };
+        println!("{verb}: {change}");
+    }
+    if let Some(agents) = oxidation::agents(input, eq) {
+        println!("oxidizing agent: {}", agents.oxidizing.join(", "));
+        println!("reducing agent: {}", agents.reducing.join(", "));
+    }
+}
+
+/// Get the code to exit with after balancing a single equation
+fn exit_code(res: &BatchResult) -> ExitCode {
+    match &res.output {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => ExitCode::from(e.exit_code()),
+    }
+}
+
+/// Read a batch file, where `-` is stdin
+fn read_input(path: &Path) -> io::Result<String> {
+    if path == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        fs::read_to_string(path)
+    }
+}
 
+/// Log to the end of a file. Never to stdout or stderr, which the tui draws on
+fn init_logging(path: &Path) -> color_eyre::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .wrap_err_with(|| format!("couldn't open log file {}", path.display()))?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_max_level(tracing::Level::DEBUG)
+        .init();
     Ok(())
 }
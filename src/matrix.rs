@@ -0,0 +1,114 @@
+/// Reduce an integer matrix to row echelon form by gaussian elimination,
+/// keeping to whole numbers. Returns the columns of each pivot, in order
+pub fn echelon(rows: &mut [Vec<i128>]) -> Vec<usize> {
+    let columns = rows.first().map_or(0, Vec::len);
+    let mut pivots = Vec::new();
+    for col in 0..columns {
+        let rank = pivots.len();
+        let Some(pivot) = (rank..rows.len()).find(|&r| rows[r][col] != 0) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        let pivot_row = rows[rank].clone();
+        for row in rows.iter_mut().skip(rank + 1) {
+            let factor = row[col];
+            if factor == 0 {
+                continue;
+            }
+            for (x, p) in row.iter_mut().zip(&pivot_row) {
+                *x = *x * pivot_row[col] - p * factor;
+            }
+            // keep the numbers small
+            let divisor = row.iter().fold(0, |a, &b| gcd(a, b.abs()));
+            if divisor > 1 {
+                row.iter_mut().for_each(|x| *x /= divisor);
+            }
+        }
+        pivots.push(col);
+    }
+    pivots
+}
+
+/// Rank of an integer matrix
+pub fn rank(mut rows: Vec<Vec<i128>>) -> usize {
+    echelon(&mut rows).len()
+}
+
+/// The smallest whole vector `x` with `rows * x = 0`, with its first entry
+/// positive. `None` unless there's exactly one, up to scaling
+pub fn null_vector(mut rows: Vec<Vec<i128>>) -> Option<Vec<i128>> {
+    let columns = rows.first().map_or(0, Vec::len);
+    let pivots = echelon(&mut rows);
+    if columns == 0 || pivots.len() + 1 != columns {
+        return None;
+    }
+    let free = (0..columns).find(|c| !pivots.contains(c))?;
+
+    // back substitute with the free entry as 1, each entry a fraction (num, den)
+    let mut x = vec![(0, 1); columns];
+    x[free] = (1, 1);
+    for (row, &col) in rows.iter().zip(&pivots).rev() {
+        let (mut num, mut den) = (0_i128, 1_i128);
+        for (j, &(n, d)) in x.iter().enumerate().skip(col + 1) {
+            // num/den += row[j] * n/d
+            num = num * d + row[j] * n * den;
+            den *= d;
+            let divisor = gcd(num.abs(), den);
+            (num, den) = (num / divisor, den / divisor);
+        }
+        // row[col] * x[col] = -num/den
+        let (mut n, mut d) = (-num, den * row[col]);
+        if d < 0 {
+            (n, d) = (-n, -d);
+        }
+        let divisor = gcd(n.abs(), d).max(1);
+        x[col] = (n / divisor, d / divisor);
+    }
+
+    let lcm = x.iter().fold(1, |l, &(_, d)| l / gcd(l, d) * d);
+    let mut whole: Vec<i128> = x.iter().map(|&(n, d)| n * (lcm / d)).collect();
+    let divisor = whole.iter().fold(0, |a, &b| gcd(a, b.abs())).max(1);
+    let sign = whole.iter().find(|&&n| n != 0).map_or(1, |n| n.signum());
+    whole.iter_mut().for_each(|n| *n = *n / divisor * sign);
+    Some(whole)
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks() {
+        assert_eq!(rank(vec![]), 0);
+        assert_eq!(rank(vec![vec![2, 0], vec![0, 3]]), 2);
+        assert_eq!(rank(vec![vec![1, 2], vec![2, 4]]), 1);
+        assert_eq!(rank(vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]), 2);
+    }
+
+    #[test]
+    fn null_vectors() {
+        // H2 + O2 -> H2O, products negated
+        assert_eq!(
+            null_vector(vec![vec![2, 0, -2], vec![0, 2, -1]]),
+            Some(vec![2, 1, 2])
+        );
+        // Fe + O2 -> Fe2O3
+        assert_eq!(
+            null_vector(vec![vec![1, 0, -2], vec![0, 2, -3]]),
+            Some(vec![4, 3, 2])
+        );
+        // only zeros work
+        assert_eq!(null_vector(vec![vec![1, 0], vec![0, 1]]), None);
+        // more than one way
+        assert_eq!(null_vector(vec![vec![1, -1, -1]]), None);
+        assert_eq!(null_vector(vec![]), None);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{charge, hydrate};
+
+/// Arrows from textbooks and other programs, and what chem-eq calls them.
+/// Longer arrows come first so `<=>` isn't read as `<` then `=`
+const ARROWS: &[(&str, &str)] = &[
+    ("<=>", "<->"),
+    ("⇌", "<->"),
+    ("⇄", "<->"),
+    ("↔", "<->"),
+    ("→", "->"),
+    ("⟶", "->"),
+    ("←", "<-"),
+    ("=", "->"),
+];
+
+/// Rewrite an equation into what chem-eq can parse, leaving the input as it was
+/// typed. On top of [`tidy`], charges are taken off and hydrates are expanded
+pub fn normalize(input: &str) -> String {
+    hydrate::expand(&charge::strip(&tidy(input)))
+}
+
+/// Arrows are swapped for chem-eq's and runs of whitespace become one space,
+/// but only outside of parentheses, so nothing inside a group or state like
+/// `(aq)` is touched. Subscript numbers and superscript charges, as in
+/// `SO₄²⁻`, are written out as `SO4^2-`
+pub fn tidy(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut depth = 0_usize;
+    let mut rest = input.trim();
+    while let Some(c) = rest.chars().next() {
+        if depth == 0 && c.is_whitespace() {
+            out.push(' ');
+            rest = rest.trim_start();
+            continue;
+        }
+        let arrow = ARROWS
+            .iter()
+            .find(|(from, _)| depth == 0 && rest.starts_with(from));
+        if let Some((from, to)) = arrow {
+            out.push_str(to);
+            rest = &rest[from.len()..];
+            continue;
+        }
+        if let Some(d) = subscript(c) {
+            out.push(d);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if charge::from_superscript(c).is_some() {
+            let end = rest
+                .find(|c| charge::from_superscript(c).is_none())
+                .unwrap_or(rest.len());
+            out.push('^');
+            out.extend(rest[..end].chars().filter_map(charge::from_superscript));
+            rest = &rest[end..];
+            continue;
+        }
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// The digit a subscript is of, eg. `2` for `₂`
+fn subscript(c: char) -> Option<char> {
+    match c {
+        '₀'..='₉' => char::from_digit(c as u32 - '₀' as u32, 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrows() {
+        assert_eq!(normalize("H2 + O2 = H2O"), "H2 + O2 -> H2O");
+        assert_eq!(normalize("H2 + O2 → H2O"), "H2 + O2 -> H2O");
+        assert_eq!(normalize("H2 + O2 ⟶ H2O"), "H2 + O2 -> H2O");
+        assert_eq!(normalize("H2O ← H2 + O2"), "H2O <- H2 + O2");
+        assert_eq!(normalize("N2 + H2 ⇌ NH3"), "N2 + H2 <-> NH3");
+        assert_eq!(normalize("N2 + H2 ↔ NH3"), "N2 + H2 <-> NH3");
+        assert_eq!(normalize("N2 + H2 <=> NH3"), "N2 + H2 <-> NH3");
+        // without spaces
+        assert_eq!(normalize("H2+O2=H2O"), "H2+O2->H2O");
+    }
+
+    #[test]
+    fn untouched() {
+        for input in ["H2 + O2 -> H2O", "N2 + H2 <-> NH3", "H2O <- H2 + O2", ""] {
+            assert_eq!(normalize(input), input);
+        }
+        // only arrows outside of parentheses are changed
+        assert_eq!(normalize("Ca(OH=)2 = CaO"), "Ca(OH=)2 -> CaO");
+        assert_eq!(normalize("NaCl(a→q) = Na"), "NaCl(a→q) -> Na");
+    }
+
+    #[test]
+    fn mixed() {
+        // each arrow is rewritten, even if there's more than one
+        assert_eq!(normalize("A = B → C"), "A -> B -> C");
+        assert_eq!(
+            normalize("Ag(aq) + Cl(aq) ⇌ AgCl(s)"),
+            "Ag(aq) + Cl(aq) <-> AgCl(s)"
+        );
+    }
+
+    #[test]
+    fn whitespace() {
+        assert_eq!(normalize("  H2  +  O2 = H2O  "), "H2 + O2 -> H2O");
+        assert_eq!(normalize("H2\t+ O2\t->\n H2O"), "H2 + O2 -> H2O");
+        // inside parentheses is left alone
+        assert_eq!(normalize("Na(  aq )  = Na"), "Na(  aq ) -> Na");
+    }
+
+    #[test]
+    fn unicode() {
+        assert_eq!(tidy("2H₂ + O₂ → 2H₂O"), "2H2 + O2 -> 2H2O");
+        assert_eq!(tidy("Fe³⁺ + SO₄²⁻(aq)"), "Fe^3+ + SO4^2-(aq)");
+        assert_eq!(normalize("Na⁺ + Cl⁻ = NaCl"), "Na + Cl -> NaCl");
+        // as copied in the unicode format
+        let eq = crate::balance::balance("Fe²⁺ + MnO₄⁻ + H⁺ → Fe³⁺ + Mn²⁺ + H₂O").unwrap();
+        let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+        assert_eq!(coefficients, [5, 1, 8, 5, 1, 4]);
+    }
+
+    #[test]
+    fn balances() {
+        let clean = crate::balance::balance("H2 + O2 -> H2O").unwrap();
+        let messy = crate::balance::balance("  H2  +  O2 = H2O  ").unwrap();
+        assert_eq!(messy.equation(), clean.equation());
+
+        let eq = crate::balance::balance("H2 + O2 = H2O").unwrap();
+        assert_eq!(eq.equation(), "2H2 + O2 -> 2H2O");
+        let eq = crate::balance::balance("N2 + H2 ⇌ NH3").unwrap();
+        assert_eq!(eq.equation(), "N2 + 3H2 <-> 2NH3");
+    }
+}
@@ -0,0 +1,524 @@
+use std::{cmp::Ordering, fmt};
+
+use chem_eq::{Compound, Equation};
+
+use crate::{charge, chem::typed_formulas, matrix::gcd, normalize::normalize};
+
+/// Elements whose oxidation state is all but certain, most certain first.
+/// Hydrogen is decided by what it's with, see [`hydrogen`]
+const RULES: &[(&str, i64)] = &[
+    ("F", -1),
+    ("Li", 1),
+    ("Na", 1),
+    ("K", 1),
+    ("Rb", 1),
+    ("Cs", 1),
+    ("Be", 2),
+    ("Mg", 2),
+    ("Ca", 2),
+    ("Sr", 2),
+    ("Ba", 2),
+    ("Al", 3),
+    ("Zn", 2),
+    ("Ag", 1),
+    ("H", 1),
+    ("O", -2),
+    ("Cl", -1),
+    ("Br", -1),
+    ("I", -1),
+];
+
+/// Ions that keep together inside a compound, so `FeSO4` is iron(II) and not
+/// whatever's left once sulfur is guessed at
+const IONS: &[(&str, i64)] = &[
+    ("SO4", -2),
+    ("SO3", -2),
+    ("NO3", -1),
+    ("NO2", -1),
+    ("PO4", -3),
+    ("CO3", -2),
+    ("ClO4", -1),
+    ("ClO3", -1),
+    ("MnO4", -1),
+    ("CrO4", -2),
+    ("Cr2O7", -2),
+    ("CN", -1),
+    ("NH4", 1),
+    ("S2", -2),
+];
+
+/// The usual state of nonmetals as anions, most electronegative first, for
+/// when nothing else decides it
+const ANIONS: &[(&str, i64)] = &[
+    ("N", -3),
+    ("S", -2),
+    ("C", -4),
+    ("Se", -2),
+    ("P", -3),
+    ("As", -3),
+];
+
+/// Elements more electronegative than hydrogen, which make it +1. With only
+/// other elements, like in `NaH` or `NaBH4`, it's a hydride and -1
+const ABOVE_HYDROGEN: &[&str] = &[
+    "C", "N", "O", "F", "P", "S", "Cl", "As", "Se", "Br", "Te", "I",
+];
+
+/// Each element in a compound and its oxidation state
+pub type States = Vec<(&'static str, State)>;
+
+/// An oxidation state. It's a fraction when atoms of an element share one
+/// unevenly, eg. `+8/3` for the iron in `Fe3O4`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+    pub num: i64,
+    /// Always positive
+    pub den: i64,
+}
+
+impl State {
+    pub fn new(num: i64, den: i64) -> Self {
+        let divisor = (gcd(i128::from(num.abs()), i128::from(den.abs())) as i64).max(1);
+        let sign = den.signum();
+        Self {
+            num: num / divisor * sign,
+            den: den.abs() / divisor,
+        }
+    }
+}
+
+impl From<i64> for State {
+    fn from(n: i64) -> Self {
+        Self { num: n, den: 1 }
+    }
+}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.num, self.den) {
+            (0, _) => write!(f, "0"),
+            (n, 1) => write!(f, "{n:+}"),
+            (n, d) => write!(f, "{n:+}/{d}"),
+        }
+    }
+}
+
+/// The oxidation state of each element in a formula as it was typed, with
+/// its charge, in the order they're written. An element in two places, like
+/// the nitrogen in `NH4NO3`, gets the average unless one is a known ion.
+/// `None` when the formula doesn't parse or nothing decides the states
+pub fn assign(formula: &str) -> Option<States> {
+    let (_, charge, _) = charge::split(formula);
+    assign_counts(&counts(&normalize(formula))?, charge)
+}
+
+/// How many of each element are in a formula chem-eq can parse, in the order
+/// they're written
+fn counts(formula: &str) -> Option<Vec<(&'static str, i64)>> {
+    let cmp = Compound::parse(formula).ok()?;
+    let mut counts: Vec<(&'static str, i64)> = Vec::new();
+    for el in &cmp.elements {
+        let n = el.count as i64;
+        match counts.iter_mut().find(|(s, _)| *s == el.symbol()) {
+            Some((_, total)) => *total += n,
+            None => counts.push((el.symbol(), n)),
+        }
+    }
+    Some(counts)
+}
+
+/// Whether hydrogen is +1 or a -1 hydride, by what else is in the compound
+fn hydrogen(counts: &[(&'static str, i64)]) -> i64 {
+    if counts.iter().any(|(e, _)| ABOVE_HYDROGEN.contains(e)) {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Assign states by the rules, most certain first. Each rule only applies
+/// while another element is left to take the rest of the charge, which is
+/// what makes the oxygen in `H2O2` -1 and the hydrogen in `NaH` -1
+fn assign_counts(counts: &[(&'static str, i64)], charge: i64) -> Option<States> {
+    let mut states: Vec<Option<i64>> = vec![None; counts.len()];
+    let unknown = |states: &[Option<i64>]| states.iter().filter(|s| s.is_none()).count();
+    let apply = |states: &mut Vec<Option<i64>>, rules: &[(&str, i64)]| {
+        for &(element, state) in rules {
+            let state = if element == "H" {
+                hydrogen(counts)
+            } else {
+                state
+            };
+            for (i, (e, _)) in counts.iter().enumerate() {
+                if *e == element && states[i].is_none() && unknown(states) > 1 {
+                    states[i] = Some(state);
+                }
+            }
+        }
+    };
+    apply(&mut states, RULES);
+    if unknown(&states) > 1 {
+        if let Some(states) = split_ion(counts, charge) {
+            return Some(states);
+        }
+        apply(&mut states, ANIONS);
+    }
+    if unknown(&states) != 1 {
+        return None;
+    }
+
+    // the last element takes what's left of the charge
+    let assigned: i64 = counts
+        .iter()
+        .zip(&states)
+        .filter_map(|((_, n), s)| s.map(|s| s * n))
+        .sum();
+    Some(
+        counts
+            .iter()
+            .zip(states)
+            .map(|(&(e, n), s)| match s {
+                Some(s) => (e, State::from(s)),
+                None => (e, State::new(charge - assigned, n)),
+            })
+            .collect(),
+    )
+}
+
+/// Take out as many of a known ion as there are, and assign it and what's
+/// left of the compound separately, eg. `Fe2(SO4)3` as `Fe2` and three `SO4`
+fn split_ion(counts: &[(&'static str, i64)], charge: i64) -> Option<States> {
+    IONS.iter().find_map(|&(formula, ion_charge)| {
+        let ion = self::counts(formula)?;
+        let times = ion
+            .iter()
+            .map(|(e, n)| {
+                counts
+                    .iter()
+                    .find(|(c, _)| c == e)
+                    .map_or(0, |(_, m)| m / n)
+            })
+            .min()?;
+        if times == 0 {
+            return None;
+        }
+        let rest: Vec<_> = counts
+            .iter()
+            .map(|&(e, n)| {
+                let used = ion
+                    .iter()
+                    .find(|(i, _)| *i == e)
+                    .map_or(0, |(_, m)| m * times);
+                (e, n - used)
+            })
+            .filter(|(_, n)| *n > 0)
+            .collect();
+        if rest.is_empty() {
+            return None;
+        }
+        let mut states = assign_counts(&rest, charge - ion_charge * times)?;
+        states.extend(assign_counts(&ion, ion_charge)?);
+        Some(states)
+    })
+}
+
+/// An element whose oxidation state changes across the arrow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub element: &'static str,
+    pub from: State,
+    pub to: State,
+    /// The compound on the left it's in
+    pub reactant: String,
+    /// The compound on the right it ends up in
+    pub product: String,
+}
+
+impl Change {
+    /// Whether the element lost electrons, otherwise it gained them
+    pub fn oxidized(&self) -> bool {
+        self.to > self.from
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} to {}, {} to {}",
+            self.element, self.from, self.to, self.reactant, self.product
+        )
+    }
+}
+
+/// Each compound in a balanced equation, written as it was typed, once each
+/// with its oxidation states
+pub fn compounds(input: &str, eq: &Equation) -> Vec<(String, Option<States>)> {
+    let mut out: Vec<(String, Option<Vec<_>>)> = Vec::new();
+    // electrons have no elements to have states
+    for formula in typed_formulas(input, eq) {
+        if !charge::is_electron(&formula) && out.iter().all(|(f, _)| *f != formula) {
+            let states = assign(&formula);
+            out.push((formula, states));
+        }
+    }
+    out
+}
+
+/// Every element whose oxidation state changes from a reactant to a product.
+/// An element can be both oxidized and reduced, like the chlorine in
+/// `Cl2 + OH^- -> Cl^- + ClO^- + H2O`
+pub fn changes(input: &str, eq: &Equation) -> Vec<Change> {
+    let formulas = typed_formulas(input, eq);
+    let (left, right) = formulas.split_at(eq.left().len().min(formulas.len()));
+    let states = |side: &[String]| -> Vec<(String, States)> {
+        side.iter()
+            .filter_map(|f| Some((f.clone(), assign(f)?)))
+            .collect()
+    };
+    let (left, right) = (states(left), states(right));
+    let mut changes: Vec<Change> = Vec::new();
+    for (reactant, states) in &left {
+        for &(element, from) in states {
+            for (product, product_states) in &right {
+                for &(e, to) in product_states {
+                    let seen = changes
+                        .iter()
+                        .any(|c| c.element == e && c.from == from && c.to == to);
+                    if e == element && to != from && !seen {
+                        changes.push(Change {
+                            element,
+                            from,
+                            to,
+                            reactant: reactant.clone(),
+                            product: product.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// The reactants that oxidize and reduce the others in a redox reaction. The
+/// oxidizing agent is the one that's reduced, the reducing agent is oxidized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Agents {
+    pub oxidizing: Vec<String>,
+    pub reducing: Vec<String>,
+}
+
+/// Work out the oxidizing and reducing agents of a balanced equation. `None`
+/// unless it's a redox reaction, where some element's state changes
+pub fn agents(input: &str, eq: &Equation) -> Option<Agents> {
+    let changes = changes(input, eq);
+    let mut agents = Agents {
+        oxidizing: Vec::new(),
+        reducing: Vec::new(),
+    };
+    for change in changes {
+        let list = if change.oxidized() {
+            &mut agents.reducing
+        } else {
+            &mut agents.oxidizing
+        };
+        if !list.contains(&change.reactant) {
+            list.push(change.reactant);
+        }
+    }
+    (!agents.oxidizing.is_empty() && !agents.reducing.is_empty()).then_some(agents)
+}
+
+/// Why the states of a compound aren't the ones the simple rules would give,
+/// so they're not taken at face value. `None` when nothing's unusual
+pub fn note(states: &[(&'static str, State)]) -> Option<&'static str> {
+    let has = |element, state: State| states.iter().any(|(e, s)| *e == element && *s == state);
+    if has("O", State::new(-1, 2)) {
+        Some("superoxide, O is -1/2")
+    } else if states.iter().any(|(_, s)| s.den != 1) {
+        Some("an average, the atoms aren't all in the same state")
+    } else if has("O", State::from(-1)) {
+        Some("peroxide, O is -1")
+    } else if has("H", State::from(-1)) {
+        Some("hydride, H is -1")
+    } else if states.iter().any(|(e, s)| *e == "O" && s.num > 0) {
+        Some("O is positive next to fluorine")
+    } else {
+        None
+    }
+}
+
+/// Write the states of a compound, eg. `MnO4^-: Mn +7, O -2`, with a note
+/// when they're unusual, eg. `H2O2: H +1, O -1 (peroxide, O is -1)`
+pub fn describe(formula: &str, states: Option<&[(&'static str, State)]>) -> String {
+    match states {
+        Some(states) => {
+            let described: Vec<String> = states.iter().map(|(e, s)| format!("{e} {s}")).collect();
+            match note(states) {
+                Some(note) => format!("{formula}: {} ({note})", described.join(", ")),
+                None => format!("{formula}: {}", described.join(", ")),
+            }
+        }
+        None => format!("{formula}: ?"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::balance;
+
+    fn states(formula: &str) -> Vec<(&'static str, String)> {
+        assign(formula)
+            .unwrap()
+            .into_iter()
+            .map(|(e, s)| (e, s.to_string()))
+            .collect()
+    }
+
+    fn state(formula: &str, element: &str) -> String {
+        let states = states(formula);
+        let (_, s) = states.iter().find(|(e, _)| *e == element).unwrap();
+        s.clone()
+    }
+
+    #[test]
+    fn simple() {
+        assert_eq!(states("H2O"), [("H", "+1".into()), ("O", "-2".into())]);
+        assert_eq!(state("O2", "O"), "0");
+        assert_eq!(state("Fe", "Fe"), "0");
+        assert_eq!(state("CO2", "C"), "+4");
+        assert_eq!(state("CH4", "C"), "-4");
+        assert_eq!(state("C6H12O6", "C"), "0");
+        assert_eq!(state("NH3", "N"), "-3");
+        assert_eq!(state("H2SO4", "S"), "+6");
+        assert_eq!(state("Fe3O4", "Fe"), "+8/3");
+        assert_eq!(state("CuSO4·5H2O", "Cu"), "+2");
+    }
+
+    #[test]
+    fn peroxides() {
+        assert_eq!(state("H2O2", "O"), "-1");
+        assert_eq!(state("Na2O2", "O"), "-1");
+        assert_eq!(state("BaO2", "O"), "-1");
+        // superoxide
+        assert_eq!(state("KO2", "O"), "-1/2");
+        // fluorine beats oxygen
+        assert_eq!(state("OF2", "O"), "+2");
+    }
+
+    #[test]
+    fn hydrides() {
+        assert_eq!(state("NaH", "H"), "-1");
+        assert_eq!(state("CaH2", "H"), "-1");
+        assert_eq!(
+            states("NaBH4"),
+            [("Na", "+1".into()), ("B", "+3".into()), ("H", "-1".into())]
+        );
+        assert_eq!(state("LiAlH4", "H"), "-1");
+        assert_eq!(state("HCl", "H"), "+1");
+    }
+
+    #[test]
+    fn ions() {
+        assert_eq!(state("MnO4^-", "Mn"), "+7");
+        assert_eq!(state("Cr2O7^2-", "Cr"), "+6");
+        assert_eq!(state("NH4+", "N"), "-3");
+        assert_eq!(state("Fe^3+", "Fe"), "+3");
+        assert_eq!(state("NaClO", "Cl"), "+1");
+        // ions inside compounds
+        assert_eq!(state("FeSO4", "Fe"), "+2");
+        assert_eq!(states("Fe2(SO4)3")[0], ("Fe", "+3".into()));
+        assert_eq!(
+            states("(NH4)2SO4"),
+            [
+                ("N", "-3".into()),
+                ("H", "+1".into()),
+                ("S", "+6".into()),
+                ("O", "-2".into())
+            ]
+        );
+        assert_eq!(state("FeS2", "S"), "-1");
+        assert_eq!(state("K4Fe(CN)6", "Fe"), "+2");
+        // nothing decides between the metals
+        assert!(assign("CuFe").is_none());
+        assert!(assign("Xq").is_none());
+    }
+
+    #[test]
+    fn notes() {
+        let note = |formula| note(&assign(formula).unwrap());
+        assert_eq!(note("H2O"), None);
+        assert_eq!(note("MnO4^-"), None);
+        assert_eq!(note("H2O2"), Some("peroxide, O is -1"));
+        assert_eq!(note("KO2"), Some("superoxide, O is -1/2"));
+        assert_eq!(
+            note("Fe3O4"),
+            Some("an average, the atoms aren't all in the same state")
+        );
+        assert_eq!(note("NaH"), Some("hydride, H is -1"));
+        assert_eq!(note("OF2"), Some("O is positive next to fluorine"));
+        assert_eq!(
+            describe("H2O2", assign("H2O2").as_deref()),
+            "H2O2: H +1, O -1 (peroxide, O is -1)"
+        );
+        assert_eq!(describe("CuFe", None), "CuFe: ?");
+    }
+
+    #[test]
+    fn oxidized_and_reduced() {
+        let input = "Fe^2+ + MnO4^- + H^+ -> Fe^3+ + Mn^2+ + H2O";
+        let eq = balance(input).unwrap();
+        let changes = changes(input, &eq);
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].oxidized());
+        assert_eq!(changes[0].to_string(), "Fe +2 to +3, Fe^2+ to Fe^3+");
+        assert!(!changes[1].oxidized());
+        assert_eq!(changes[1].to_string(), "Mn +7 to +2, MnO4^- to Mn^2+");
+        // both at once
+        let input = "Cl2 + OH^- -> Cl^- + ClO^- + H2O";
+        let eq = balance(input).unwrap();
+        let changes = super::changes(input, &eq);
+        assert_eq!(changes.len(), 2);
+        assert_ne!(changes[0].oxidized(), changes[1].oxidized());
+        // not redox
+        let input = "NaOH + HCl -> NaCl + H2O";
+        assert!(super::changes(input, &balance(input).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn oxidizing_and_reducing_agents() {
+        let agents_of = |input| agents(input, &balance(input).unwrap());
+        assert_eq!(
+            agents_of("Zn + CuSO4 = ZnSO4 + Cu"),
+            Some(Agents {
+                oxidizing: vec!["CuSO4".to_string()],
+                reducing: vec!["Zn".to_string()],
+            })
+        );
+        assert_eq!(
+            agents_of("Fe^2+ + MnO4^- + H^+ -> Fe^3+ + Mn^2+ + H2O"),
+            Some(Agents {
+                oxidizing: vec!["MnO4^-".to_string()],
+                reducing: vec!["Fe^2+".to_string()],
+            })
+        );
+        // the same reactant can be both
+        let both = agents_of("Cl2 + OH^- -> Cl^- + ClO^- + H2O").unwrap();
+        assert_eq!(both.oxidizing, both.reducing);
+        assert_eq!(agents_of("NaOH + HCl -> NaCl + H2O"), None);
+    }
+}
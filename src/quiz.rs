@@ -0,0 +1,193 @@
+use std::hash::{BuildHasher, RandomState};
+
+use chem_eq::Equation;
+
+use crate::{
+    balance::balance,
+    chem,
+    examples::{Example, EXAMPLES},
+    normalize::normalize,
+};
+
+/// Practice balancing the examples by hand, keeping score
+#[derive(Debug)]
+pub struct Quiz {
+    /// The example being balanced
+    pub example: &'static Example,
+    /// The balanced equation, shown when revealed
+    pub answer: String,
+    /// Formulas of the question, which the guess has to keep
+    formulas: Vec<String>,
+    coefficients: Vec<usize>,
+    /// The question was answered or the answer was shown, enter moves on
+    pub solved: bool,
+    /// The answer was shown, so the question doesn't count as right
+    pub revealed: bool,
+    /// Questions answered right without showing the answer
+    pub right: usize,
+    /// Questions finished, right or not
+    pub finished: usize,
+}
+
+/// How a guess compares to the answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    /// Balanced differently to the answer, or not at all
+    Incorrect,
+    /// The guess has different compounds to the question
+    Changed,
+    /// The guess isn't an equation
+    Invalid,
+}
+
+impl Quiz {
+    /// Start with a random example
+    pub fn new() -> Self {
+        let example = random_example(None);
+        let (answer, formulas, coefficients) = solve(example);
+        Self {
+            example,
+            answer,
+            formulas,
+            coefficients,
+            solved: false,
+            revealed: false,
+            right: 0,
+            finished: 0,
+        }
+    }
+
+    /// Move on to a different random example
+    pub fn next(&mut self) {
+        self.example = random_example(Some(self.example));
+        (self.answer, self.formulas, self.coefficients) = solve(self.example);
+        self.solved = false;
+        self.revealed = false;
+    }
+
+    /// Check a guess, scoring the question when it's right
+    pub fn check(&mut self, guess: &str) -> Verdict {
+        let verdict = match Equation::new(&normalize(guess)) {
+            Err(_) => Verdict::Invalid,
+            Ok(eq) if chem::formulas(&eq) != self.formulas => Verdict::Changed,
+            Ok(eq) => {
+                let guessed: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+                if proportional(&guessed, &self.coefficients) {
+                    Verdict::Correct
+                } else {
+                    Verdict::Incorrect
+                }
+            }
+        };
+        if verdict == Verdict::Correct && !self.solved {
+            self.solved = true;
+            self.finished += 1;
+            if !self.revealed {
+                self.right += 1;
+            }
+        }
+        verdict
+    }
+
+    /// Show the answer, the question no longer counts as right
+    pub fn reveal(&mut self) {
+        self.revealed = true;
+        if !self.solved {
+            self.solved = true;
+            self.finished += 1;
+        }
+    }
+}
+
+impl Default for Quiz {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether two sets of coefficients are the same up to a positive factor, eg.
+/// `[4, 2, 4]` and `[2, 1, 2]`. Coefficients of 0 never match
+pub fn proportional(a: &[usize], b: &[usize]) -> bool {
+    if a.len() != b.len() || a.is_empty() || a.contains(&0) || b.contains(&0) {
+        return false;
+    }
+    // a[i] / a[0] == b[i] / b[0], multiplied out to stay in whole numbers
+    a.iter()
+        .zip(b)
+        .all(|(&x, &y)| x as u128 * b[0] as u128 == y as u128 * a[0] as u128)
+}
+
+/// The balanced equation, formulas and coefficients of an example
+fn solve(example: &Example) -> (String, Vec<String>, Vec<usize>) {
+    let eq = balance(example.equation).expect("examples are tested to balance");
+    let coefficients = eq.iter_compounds().map(|c| c.coefficient).collect();
+    (eq.equation().to_string(), chem::formulas(&eq), coefficients)
+}
+
+/// Pick an example at random, other than `not` so a question isn't asked twice in a row
+fn random_example(not: Option<&Example>) -> &'static Example {
+    // std has no rng, but its hash maps are seeded randomly
+    let random = RandomState::new().hash_one(0u8) as usize;
+    let choices: Vec<&Example> = EXAMPLES.iter().filter(|e| Some(*e) != not).collect();
+    choices[random % choices.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A quiz on a chosen example
+    fn quiz_on(name: &str) -> Quiz {
+        let mut quiz = Quiz::new();
+        quiz.example = EXAMPLES.iter().find(|e| e.name == name).unwrap();
+        (quiz.answer, quiz.formulas, quiz.coefficients) = solve(quiz.example);
+        quiz
+    }
+
+    #[test]
+    fn proportional_coefficients() {
+        assert!(proportional(&[2, 1, 2], &[2, 1, 2]));
+        assert!(proportional(&[4, 2, 4], &[2, 1, 2]));
+        assert!(proportional(&[2, 1, 2], &[6, 3, 6]));
+        assert!(!proportional(&[1, 1, 1], &[2, 1, 2]));
+        assert!(!proportional(&[2, 1, 3], &[2, 1, 2]));
+        // different lengths, nothing and zeros
+        assert!(!proportional(&[2, 1], &[2, 1, 2]));
+        assert!(!proportional(&[], &[]));
+        assert!(!proportional(&[0, 0, 0], &[2, 1, 2]));
+        assert!(!proportional(&[2, 1, 2], &[0, 0, 0]));
+    }
+
+    #[test]
+    fn check_guesses() {
+        let mut quiz = quiz_on("Synthesis of water");
+        assert_eq!(quiz.check("H2 + O2 -> H2O"), Verdict::Incorrect);
+        assert_eq!(quiz.check("2H2 + O2 -> H2O2"), Verdict::Changed);
+        assert_eq!(quiz.check("2H2 + O2 ->"), Verdict::Invalid);
+        assert!(!quiz.solved);
+        // any multiple of the answer is right
+        assert_eq!(quiz.check("4H2 + 2O2 -> 4H2O"), Verdict::Correct);
+        assert!(quiz.solved);
+        assert_eq!((quiz.right, quiz.finished), (1, 1));
+        // only scored once
+        assert_eq!(quiz.check("2H2 + O2 -> 2H2O"), Verdict::Correct);
+        assert_eq!((quiz.right, quiz.finished), (1, 1));
+    }
+
+    #[test]
+    fn reveal() {
+        let mut quiz = quiz_on("Rusting of iron");
+        assert_eq!(quiz.answer, "4Fe + 3O2 -> 2Fe2O3");
+        quiz.reveal();
+        assert!(quiz.solved);
+        assert_eq!(quiz.check("4Fe + 3O2 -> 2Fe2O3"), Verdict::Correct);
+        assert_eq!((quiz.right, quiz.finished), (0, 1));
+
+        let previous = quiz.example;
+        quiz.next();
+        assert_ne!(quiz.example, previous);
+        assert!(!quiz.solved && !quiz.revealed);
+        assert_eq!((quiz.right, quiz.finished), (0, 1));
+    }
+}
@@ -0,0 +1,399 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chem_eq::Compound;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    charge,
+    chem::element_counts,
+    matrix::{gcd, null_vector},
+    normalize::{normalize, tidy},
+};
+
+/// What a redox equation happens in, which decides how hydrogen is balanced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Medium {
+    /// With `H^+`
+    Acidic,
+    /// With `OH^-`
+    Basic,
+}
+
+impl FromStr for Medium {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "acidic" | "acid" => Ok(Self::Acidic),
+            "basic" | "base" => Ok(Self::Basic),
+            _ => Err(format!("unknown medium {s:?}, expected acidic or basic")),
+        }
+    }
+}
+
+impl fmt::Display for Medium {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Acidic => write!(f, "acidic"),
+            Self::Basic => write!(f, "basic"),
+        }
+    }
+}
+
+/// Why an equation couldn't be balanced as a redox reaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedoxError {
+    /// A compound that couldn't be read, as it was typed
+    Parse(String),
+    /// There's no arrow, or nothing on one side of it
+    NoArrow,
+    /// The compounds fall into this many groups with no elements in common,
+    /// instead of the two halves
+    Halves(usize),
+    /// A half-reaction that couldn't be balanced, as it was typed
+    Half(String),
+    /// Both halves gain electrons or both lose them, so nothing is oxidised or reduced
+    NotRedox,
+}
+
+impl RedoxError {
+    /// The code the cli exits with after this error
+    pub const fn exit_code(&self) -> u8 {
+        match self {
+            Self::Parse(_) | Self::NoArrow => 2,
+            Self::Halves(_) | Self::Half(_) | Self::NotRedox => 3,
+        }
+    }
+}
+
+impl fmt::Display for RedoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(c) => write!(f, "Couldn't parse {c}"),
+            Self::NoArrow => write!(f, "Couldn't parse equation"),
+            Self::Halves(n) => write!(
+                f,
+                "Not a redox reaction, expected two half-reactions but found {n}"
+            ),
+            Self::Half(half) => write!(f, "Couldn't balance the half-reaction {half}"),
+            Self::NotRedox => write!(f, "Not a redox reaction, nothing is oxidised"),
+        }
+    }
+}
+
+impl std::error::Error for RedoxError {}
+
+/// A redox equation balanced by the half-reaction method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redox {
+    /// The whole equation, with the water and ions it needed
+    pub equation: String,
+    /// The half that loses electrons
+    pub oxidation: String,
+    /// The half that gains electrons
+    pub reduction: String,
+}
+
+/// A compound taking part in a half-reaction
+#[derive(Debug, Clone)]
+struct Species {
+    /// As it was typed, without a coefficient
+    formula: String,
+    counts: BTreeMap<&'static str, usize>,
+    charge: i64,
+}
+
+impl Species {
+    fn new(formula: &str) -> Result<Self, RedoxError> {
+        let cmp = Compound::parse(&normalize(formula))
+            .map_err(|_| RedoxError::Parse(formula.to_string()))?;
+        Ok(Self {
+            formula: formula.to_string(),
+            counts: element_counts(&cmp),
+            charge: charge::split(formula).1,
+        })
+    }
+
+    fn added(formula: &str, counts: &[(&'static str, usize)], charge: i64) -> Self {
+        Self {
+            formula: formula.to_string(),
+            counts: counts.iter().copied().collect(),
+            charge,
+        }
+    }
+
+    /// Water and the ions it splits into, which the method adds itself
+    fn is_solvent(&self) -> bool {
+        [water(), hydrogen_ion(), hydroxide()]
+            .iter()
+            .any(|s| s.counts == self.counts && s.charge == self.charge)
+    }
+
+    /// Elements that tie it to the other side of its half. Oxygen and hydrogen
+    /// are in nearly everything, so they only count for compounds made of nothing else
+    fn keys(&self) -> Vec<&'static str> {
+        let keys: Vec<_> = self
+            .counts
+            .keys()
+            .copied()
+            .filter(|e| !matches!(*e, "H" | "O"))
+            .collect();
+        if keys.is_empty() {
+            self.counts.keys().copied().collect()
+        } else {
+            keys
+        }
+    }
+}
+
+/// A species and how much of it there is, positive on the left of the arrow
+/// and negative on the right
+type Amount = (Species, i64);
+
+fn water() -> Species {
+    Species::added("H2O", &[("H", 2), ("O", 1)], 0)
+}
+
+fn hydrogen_ion() -> Species {
+    Species::added("H^+", &[("H", 1)], 1)
+}
+
+fn hydroxide() -> Species {
+    Species::added("OH^-", &[("H", 1), ("O", 1)], -1)
+}
+
+fn electron() -> Species {
+    Species::added("e^-", &[], -1)
+}
+
+/// Balance a redox equation by splitting it into its oxidation and reduction
+/// halves, balancing oxygen with water, hydrogen with `H^+` or `OH^-` and
+/// charge with electrons, then adding the halves so the electrons cancel.
+/// Coefficients, water and its ions in the input are ignored
+pub fn balance(input: &str, medium: Medium) -> Result<Redox, RedoxError> {
+    let tidied = tidy(input);
+    let tokens: Vec<&str> = tidied.split(' ').filter(|t| *t != "+").collect();
+    let arrow = tokens
+        .iter()
+        .position(|t| matches!(*t, "->" | "<-" | "<->"))
+        .ok_or(RedoxError::NoArrow)?;
+    // each compound with 1 on the left and -1 on the right
+    let mut compounds = Vec::new();
+    for (i, token) in tokens.iter().enumerate().filter(|(i, _)| *i != arrow) {
+        let formula = token.trim_start_matches(|c: char| c.is_ascii_digit());
+        let species = Species::new(formula)?;
+        if !species.is_solvent() {
+            compounds.push((species, if i < arrow { 1 } else { -1 }));
+        }
+    }
+    if !compounds.iter().any(|(_, side)| *side == 1)
+        || !compounds.iter().any(|(_, side)| *side == -1)
+    {
+        return Err(RedoxError::NoArrow);
+    }
+
+    let groups = halves(&compounds);
+    let [first, second] = groups.as_slice() else {
+        return Err(RedoxError::Halves(groups.len()));
+    };
+    let mut first = balance_half(first, medium)?;
+    let mut second = balance_half(second, medium)?;
+    let electrons = |half: &[Amount]| {
+        half.iter()
+            .find(|(s, _)| s.formula == "e^-")
+            .map_or(0, |(_, n)| *n)
+    };
+    // one half has electrons on the left, gaining them, and the other on the right
+    let (gained, lost) = (electrons(&first), electrons(&second));
+    if gained == 0 || lost == 0 || gained.signum() == lost.signum() {
+        return Err(RedoxError::NotRedox);
+    }
+    // each half is shown balanced on its own, before scaling
+    let (oxidation, reduction) = if gained > 0 {
+        (write(&second, "->"), write(&first, "->"))
+    } else {
+        (write(&first, "->"), write(&second, "->"))
+    };
+    let divisor = i64::try_from(gcd(gained.abs().into(), lost.abs().into()))
+        .expect("divisor is no bigger than the electrons");
+    first
+        .iter_mut()
+        .for_each(|(_, n)| *n *= lost.abs() / divisor);
+    second
+        .iter_mut()
+        .for_each(|(_, n)| *n *= gained.abs() / divisor);
+
+    // add the halves, cancelling what's on both sides like the electrons
+    let mut total: Vec<Amount> = Vec::new();
+    for (species, n) in first.iter().chain(&second) {
+        match total.iter_mut().find(|(s, _)| s.formula == species.formula) {
+            Some((_, sum)) => *sum += n,
+            None => total.push((species.clone(), *n)),
+        }
+    }
+    total.retain(|(_, n)| *n != 0);
+    let divisor = total
+        .iter()
+        .fold(0, |d, (_, n)| gcd(d, i128::from(n.abs())))
+        .max(1);
+    total
+        .iter_mut()
+        .for_each(|(_, n)| *n /= i64::try_from(divisor).expect("divisor is no bigger than n"));
+
+    Ok(Redox {
+        equation: write(&total, tokens[arrow]),
+        oxidation,
+        reduction,
+    })
+}
+
+/// Group compounds that share elements, which should give the two halves
+fn halves(compounds: &[Amount]) -> Vec<Vec<Amount>> {
+    let mut groups: Vec<(Vec<&'static str>, Vec<Amount>)> = Vec::new();
+    for compound in compounds {
+        let keys = compound.0.keys();
+        let (joined, rest): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .partition(|(k, _)| k.iter().any(|e| keys.contains(e)));
+        groups = rest;
+        let mut group = (keys, vec![compound.clone()]);
+        for (k, members) in joined {
+            group.0.extend(k);
+            group.1.splice(0..0, members);
+        }
+        groups.push(group);
+    }
+    groups.into_iter().map(|(_, members)| members).collect()
+}
+
+/// Balance one half, adding water, `H^+` or `OH^-` and electrons to whichever
+/// side needs them. Amounts are positive on the left and negative on the right
+fn balance_half(half: &[Amount], medium: Medium) -> Result<Vec<Amount>, RedoxError> {
+    let hydrogen = match medium {
+        Medium::Acidic => hydrogen_ion(),
+        Medium::Basic => hydroxide(),
+    };
+    // the added species can go on either side, so start them on the left
+    let mut species: Vec<Amount> = half.to_vec();
+    species.extend([(water(), 1), (hydrogen, 1), (electron(), 1)]);
+
+    let mut elements: Vec<&'static str> = species
+        .iter()
+        .flat_map(|(s, _)| s.counts.keys().copied())
+        .collect();
+    elements.sort_unstable();
+    elements.dedup();
+    let mut rows: Vec<Vec<i128>> = elements
+        .iter()
+        .map(|el| {
+            species
+                .iter()
+                .map(|(s, side)| i128::from(*side) * s.counts.get(el).map_or(0, |&n| n as i128))
+                .collect()
+        })
+        .collect();
+    rows.push(
+        species
+            .iter()
+            .map(|(s, side)| i128::from(*side * s.charge))
+            .collect(),
+    );
+
+    let typed = || {
+        let compounds: Vec<Amount> = half.iter().map(|(s, side)| (s.clone(), *side)).collect();
+        write(&compounds, "->")
+    };
+    let amounts = null_vector(rows)
+        .filter(|x| x[..half.len()].iter().all(|&n| n > 0))
+        .ok_or_else(|| RedoxError::Half(typed()))?;
+    Ok(species
+        .into_iter()
+        .zip(amounts)
+        .map(|((s, side), n)| {
+            (
+                s,
+                side * i64::try_from(n).expect("coefficients fit in an i64"),
+            )
+        })
+        .filter(|(_, n)| *n != 0)
+        .collect())
+}
+
+/// Write species with amounts positive on the left and negative on the right
+fn write(species: &[Amount], arrow: &str) -> String {
+    let side = |left: bool| {
+        species
+            .iter()
+            .filter(|(_, n)| (*n > 0) == left)
+            .map(|(s, n)| match n.abs() {
+                1 => s.formula.clone(),
+                n => format!("{n}{}", s.formula),
+            })
+            .collect::<Vec<_>>()
+            .join(" + ")
+    };
+    format!("{} {arrow} {}", side(true), side(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permanganate() {
+        let redox = balance("MnO4^- + Fe^2+ -> Mn^2+ + Fe^3+", Medium::Acidic).unwrap();
+        assert_eq!(
+            redox.equation,
+            "MnO4^- + 8H^+ + 5Fe^2+ -> Mn^2+ + 4H2O + 5Fe^3+"
+        );
+        assert_eq!(redox.oxidation, "Fe^2+ -> Fe^3+ + e^-");
+        assert_eq!(redox.reduction, "MnO4^- + 8H^+ + 5e^- -> Mn^2+ + 4H2O");
+        // balanced, atoms and charge
+        assert!(crate::balance::balance(&redox.equation).is_ok());
+    }
+
+    #[test]
+    fn basic() {
+        let redox = balance("MnO4^- + I^- -> MnO2 + I2", Medium::Basic).unwrap();
+        assert_eq!(
+            redox.equation,
+            "2MnO4^- + 4H2O + 6I^- -> 2MnO2 + 8OH^- + 3I2"
+        );
+        assert_eq!(redox.oxidation, "2I^- -> I2 + 2e^-");
+        assert_eq!(redox.reduction, "MnO4^- + 2H2O + 3e^- -> MnO2 + 4OH^-");
+    }
+
+    #[test]
+    fn water_in_the_input() {
+        // water and its ions are worked out again, whatever was typed
+        let redox = balance(
+            "Cr2O7^2- + H^+ + Fe^2+ -> Cr^3+ + Fe^3+ + H2O",
+            Medium::Acidic,
+        )
+        .unwrap();
+        assert_eq!(
+            redox.equation,
+            "Cr2O7^2- + 14H^+ + 6Fe^2+ -> 2Cr^3+ + 7H2O + 6Fe^3+"
+        );
+    }
+
+    #[test]
+    fn not_redox() {
+        assert_eq!(
+            balance("NaCl -> Na + Cl2", Medium::Acidic),
+            Err(RedoxError::Halves(1))
+        );
+        assert_eq!(
+            balance("Fe^2+ + Cu -> Fe^3+ + Cu^+", Medium::Acidic),
+            Err(RedoxError::NotRedox)
+        );
+        assert_eq!(balance("Fe + O2", Medium::Acidic), Err(RedoxError::NoArrow));
+        assert_eq!(
+            balance("Xx -> Fe", Medium::Acidic),
+            Err(RedoxError::Parse("Xx".to_string()))
+        );
+        assert_eq!("basic".parse(), Ok(Medium::Basic));
+        assert!("neutral".parse::<Medium>().is_err());
+    }
+}
@@ -0,0 +1,2380 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph, Tabs, Widget, Wrap,
+    },
+    Frame,
+};
+
+use std::time::Duration;
+
+use chem_eq::{error::EquationError, Equation};
+
+use crate::{
+    app::{App, Focus, InputMode, OutputView, Panes, Setting, Status},
+    balance, charge, chem,
+    classify::classify,
+    diagnose::{diagnose, parse_sides, Diagnosis},
+    elements::Info,
+    equilibrium,
+    error::Error,
+    examples,
+    format::{fractions, subscripts},
+    ionic, oxidation,
+    quiz::Quiz,
+    stoich::{self, Yield},
+    suggest::{closest_formula, miscased},
+    table,
+    tally::tally,
+    theme::Theme,
+    verify,
+};
+
+/// Smallest terminal the ui can be drawn in, with room for the margins, title,
+/// input, three lines of output, the help and the status line
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 2 + 1 + 3 + 3 + HELP_HEIGHT + 1;
+
+/// Frames of the animation shown while balancing
+const SPINNER: [&str; 8] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧"];
+
+/// How long balancing takes before the time spent is shown
+const SLOW_BALANCE: Duration = Duration::from_secs(1);
+
+/// Height of the help panel in the mode with the longest help text, with its
+/// borders. The panel is only this tall in that mode
+const HELP_HEIGHT: u16 = InputMode::HELP_LINES + 2;
+
+impl App {
+    /// Draw the input, scrolled `scroll` columns to the right
+    pub fn input_body(&self, scroll: u16) -> impl Widget + '_ {
+        let (text, text_colour) = if self.input.is_empty() {
+            ("Input equation...", Color::DarkGray)
+        } else {
+            (self.input.as_str(), self.theme.highlight())
+        };
+        let text_style = if let InputMode::Editing = self.input_mode {
+            Style::default().fg(text_colour)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let border_style = if self.focused(Focus::Input) {
+            Style::default().fg(self.theme.highlight())
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        // the rest of a common formula goes after the cursor, greyed out
+        let line = match self.completion() {
+            Some(rest) => {
+                let (before, after) = text.split_at(
+                    text.char_indices()
+                        .nth(self.cursor)
+                        .map_or(text.len(), |(i, _)| i),
+                );
+                Line::from(vec![
+                    Span::styled(format!(" {before}"), text_style),
+                    Span::styled(rest, Style::default().fg(Color::DarkGray)),
+                    Span::styled(after, text_style),
+                ])
+            }
+            None => match &self.output {
+                // the symbol that isn't an element stands out
+                Some(Err(Error::UnknownElement(u)))
+                    if text.get(u.range.clone()) == Some(&u.symbol) =>
+                {
+                    Line::from(vec![
+                        Span::styled(format!(" {}", &text[..u.range.start]), text_style),
+                        Span::styled(
+                            &text[u.range.clone()],
+                            Style::default()
+                                .fg(Color::Red)
+                                .add_modifier(Modifier::UNDERLINED),
+                        ),
+                        Span::styled(&text[u.range.end..], text_style),
+                    ])
+                }
+                _ => Line::from(Span::styled(format!(" {}", text), text_style)),
+            },
+        };
+        Paragraph::new(line)
+            .style(border_style)
+            .block(Block::default().borders(Borders::ALL))
+            .scroll((0, scroll))
+    }
+
+    /// Draw the output to fit in `area`, scrolled down by [`App::output_scroll`]
+    pub fn output_body(&self, area: Rect) -> impl Widget + '_ {
+        // padded rather than starting with a space, so wrapped lines line up
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .padding(Padding::left(1));
+        if self.focused(Focus::Output) {
+            block = block.border_style(Style::default().fg(self.theme.highlight()));
+        }
+        if self.quiz.is_some() {
+            block = block.title(" Quiz ");
+        } else if let Some(medium) = self.redox {
+            block = block.title(format!(" Redox, {medium} "));
+        } else if matches!(self.output, Some(Ok(_))) && self.common_factor > 1 && !self.balancing {
+            block = block.title(
+                Line::styled(
+                    format!(" divided by {} ", self.common_factor),
+                    Style::default().fg(Color::DarkGray),
+                )
+                .right_aligned(),
+            );
+        }
+
+        if let (None, Some(Ok(eq)), false) = (&self.quiz, &self.output, self.balancing) {
+            block = block.title_bottom(Line::styled(
+                format!(" {} ", classify(eq)),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+
+        let paragraph = self.output_text();
+        let view = OutputView {
+            lines: paragraph.line_count(area.width.saturating_sub(3)) as u16,
+            height: area.height.saturating_sub(2),
+        };
+        self.output_view.set(view);
+        // the output may have shrunk since it was scrolled
+        let scroll = self.output_scroll.min(view.overflow());
+        if view.overflow() > 0 {
+            block = block.title_bottom(
+                Line::styled(
+                    format!(" [{}/{}] ", scroll + view.height, view.lines),
+                    Style::default().fg(Color::DarkGray),
+                )
+                .right_aligned(),
+            );
+        }
+        paragraph.block(block).scroll((scroll, 0))
+    }
+
+    /// The output without its border, wrapped between compounds
+    fn output_text(&self) -> Paragraph<'_> {
+        if let Some(quiz) = &self.quiz {
+            return quiz_body(quiz);
+        }
+        if self.balancing {
+            let frame = SPINNER[self.spinner % SPINNER.len()];
+            // count the seconds once it's slow enough to wonder if it's stuck
+            let waited = match self.balance_started.map(|t| t.elapsed()) {
+                Some(took) if took >= SLOW_BALANCE => format!(" {}s", took.as_secs()),
+                _ => String::new(),
+            };
+            return Paragraph::new(format!("{frame} Balancing...{waited}"))
+                .style(Style::default().fg(Color::DarkGray))
+                .wrap(Wrap { trim: false });
+        }
+        let mut text = Text::from(match &self.output {
+            // side by side, show what balancing changed
+            Some(Ok(eq)) if self.side_by_side => {
+                let before = balance::parse(&self.input).ok();
+                equation_line(
+                    &self.input,
+                    eq,
+                    before.as_ref(),
+                    self.explicit_ones,
+                    self.fractional,
+                    self.subscripts,
+                    self.theme,
+                )
+            }
+            Some(Ok(eq)) => equation_line(
+                self.balanced_from(),
+                eq,
+                None,
+                self.explicit_ones,
+                self.fractional,
+                self.subscripts,
+                self.theme,
+            ),
+            Some(Err(e)) => match (self.diagnosis(), self.miscased()) {
+                (Some(d), _) => diagnosis_line(&d, self.theme),
+                (None, miscased) if !miscased.is_empty() => miscased_line(&miscased, self.theme),
+                (None, _) => Line::raw(e.to_string()),
+            },
+            None => Line::raw("Waiting for equation..."),
+        });
+        // say how the coefficients typed compare to the balanced ones
+        if let (Some(Ok(_)), None) = (&self.output, &self.half_reactions) {
+            if let Some(verdict) = verify::has_coefficients(&self.input)
+                .then(|| verify::verify(&self.input))
+                .flatten()
+            {
+                text.push_line(Line::styled(
+                    format!(
+                        "As typed: {}",
+                        charge::superscript_charges(&verdict.to_string())
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+        }
+        if let (Some(redox), Some(Ok(_))) = (&self.half_reactions, &self.output) {
+            let style = Style::default().fg(Color::DarkGray);
+            text.push_line(Line::styled(
+                format!(
+                    "Oxidation: {}",
+                    charge::superscript_charges(&redox.oxidation)
+                ),
+                style,
+            ));
+            text.push_line(Line::styled(
+                format!(
+                    "Reduction: {}",
+                    charge::superscript_charges(&redox.reduction)
+                ),
+                style,
+            ));
+        }
+        if let Some(kc) = self.output.as_ref().and_then(|out| {
+            let eq = out.as_ref().ok()?;
+            equilibrium::kc(self.balanced_from(), eq)
+        }) {
+            text.push_line(Line::styled(
+                kc.to_string(),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if let Some(agents) = self.output.as_ref().and_then(|out| {
+            let eq = out.as_ref().ok()?;
+            oxidation::agents(self.balanced_from(), eq)
+        }) {
+            let list = |agents: &[String]| {
+                agents
+                    .iter()
+                    .map(|a| charge::superscript_charges(a))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            text.push_line(Line::styled(
+                format!(
+                    "Oxidizing agent: {}, reducing agent: {}",
+                    list(&agents.oxidizing),
+                    list(&agents.reducing)
+                ),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if let Some(Ok(eq)) = self.output.as_ref().filter(|_| self.show_net_ionic) {
+            let net = match ionic::net_ionic(self.balanced_from(), eq) {
+                Some(net) => format!(
+                    "Net ionic: {}",
+                    charge::superscript_charges(&net.to_string())
+                ),
+                None => "No net reaction".to_string(),
+            };
+            text.push_line(Line::styled(net, Style::default().fg(Color::DarkGray)));
+        }
+        if let Some(Ok(eq)) = self.output.as_ref().filter(|_| self.show_oxidation) {
+            text.extend(oxidation_lines(self.balanced_from(), eq, self.theme));
+        }
+        let hint = match (self.suggestion(), &self.output) {
+            (Some(suggestion), _) => Some(format!("Did you mean {suggestion}? a to use it")),
+            (None, Some(Err(_))) if self.combustion().is_some() => {
+                Some("Burning it in O2? a to complete as combustion".to_string())
+            }
+            (None, Some(Err(Error::Eq(_) | Error::UnknownElement(_)))) => {
+                closest_formula(&self.input)
+                    .map(|(typed, known)| format!("Did you mean {known} instead of {typed}?"))
+            }
+            _ => None,
+        };
+        if let Some(hint) = hint {
+            text.push_line(Line::styled(hint, Style::default().fg(Color::DarkGray)));
+        }
+        let style = match &self.output {
+            Some(Ok(_)) => Style::default().fg(Color::Green),
+            Some(Err(_)) => Style::default().fg(Color::Red),
+            None => Style::default().fg(Color::DarkGray),
+        };
+        Paragraph::new(text).style(style).wrap(Wrap { trim: false })
+    }
+
+    /// Rows the output needs to show all of itself, with its border, when
+    /// it's `width` columns wide
+    fn output_height(&self, width: u16) -> u16 {
+        if !self.side_by_side {
+            return self.output_text().line_count(width.saturating_sub(3)) as u16 + 2;
+        }
+        // each half gets half the width, and the taller one decides
+        let half = width / 2;
+        let input = Paragraph::new(self.input.as_str()).wrap(Wrap { trim: false });
+        let lines = self
+            .output_text()
+            .line_count(width.saturating_sub(half + 3))
+            .max(input.line_count(half.saturating_sub(3)));
+        lines as u16 + 2
+    }
+
+    /// Draw the input as typed, to compare with the balanced equation
+    pub fn unbalanced_body(&self) -> impl Widget + '_ {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .padding(Padding::left(1));
+        if self.focused(Focus::Output) {
+            block = block.border_style(Style::default().fg(self.theme.highlight()));
+        }
+        let (text, style) = match &self.output {
+            Some(_) => (self.input.as_str(), Style::default()),
+            None => (
+                "Unbalanced equation...",
+                Style::default().fg(Color::DarkGray),
+            ),
+        };
+        Paragraph::new(text)
+            .style(style)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((self.output_scroll.min(self.output_view.get().overflow()), 0))
+    }
+
+    /// Why the input can't be balanced, for errors that don't say themselves
+    fn diagnosis(&self) -> Option<Diagnosis> {
+        if !matches!(
+            self.output,
+            Some(Err(
+                Error::Eq(EquationError::IncorrectEquation) | Error::Balance(_)
+            ))
+        ) {
+            return None;
+        }
+        let (left, right) = parse_sides(&self.input)?;
+        diagnose(&left, &right)
+    }
+
+    /// Formulas with the wrong capitals, when that's why the input doesn't parse
+    fn miscased(&self) -> Vec<(String, String)> {
+        match self.output {
+            Some(Err(Error::Eq(EquationError::ParsingError(_)) | Error::UnknownElement(_))) => {
+                miscased(&self.input)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The atoms of each element on both sides of the input as it's typed, for
+    /// balancing by hand. Green when they match and red when they don't
+    fn tally_text(&self) -> Option<Paragraph<'_>> {
+        let (left, right) = parse_sides(&self.input)?;
+        let tallies = tally(&left, &right);
+        if tallies.is_empty() {
+            return None;
+        }
+        let spans: Vec<Span> = tallies
+            .iter()
+            .map(|t| {
+                let (sign, color) = if t.matches() {
+                    ('=', Color::Green)
+                } else {
+                    ('≠', Color::Red)
+                };
+                Span::styled(
+                    format!("{} {}{sign}{}  ", t.element, t.left, t.right),
+                    Style::default().fg(color),
+                )
+            })
+            .collect();
+        Some(Paragraph::new(Line::from(spans)).wrap(Wrap { trim: true }))
+    }
+
+    /// The mass of each side of the balanced equation, green when they're
+    /// the same, which they should be
+    fn mass_text(&self) -> Option<Paragraph<'_>> {
+        let Some(Ok(eq)) = self.output.as_ref().filter(|_| self.quiz.is_none()) else {
+            return None;
+        };
+        let masses = stoich::masses(eq);
+        let (sign, color) = if masses.conserved() {
+            ('=', Color::Green)
+        } else {
+            ('≠', Color::Red)
+        };
+        Some(
+            Paragraph::new(format!(
+                "{} g {sign} {} g",
+                stoich::round(masses.left),
+                stoich::round(masses.right)
+            ))
+            .style(Style::default().fg(color)),
+        )
+    }
+
+    /// Whether `pane` is drawn highlighted, editing always highlights the input
+    fn focused(&self, pane: Focus) -> bool {
+        match self.input_mode {
+            InputMode::Editing => pane == Focus::Input,
+            _ => self.focus == pane,
+        }
+    }
+
+    pub fn status_body(&self) -> impl Widget + '_ {
+        let (text, style) = match &self.status {
+            Some(Status::Info(s)) => (s.as_str(), Style::default().fg(Color::DarkGray)),
+            Some(Status::Warning(s)) => (s.as_str(), Style::default().fg(self.theme.highlight())),
+            Some(Status::Error(s)) => (s.as_str(), Style::default().fg(Color::Red)),
+            None => ("", Style::default()),
+        };
+        Paragraph::new(format!(" {}", text)).style(style)
+    }
+}
+
+/// The compounds on each side and the elements in an equation, eg.
+/// `2 → 1 compounds, 2 elements`
+fn counts_text(counts: chem::Counts) -> String {
+    format!(
+        "{} → {} compounds, {} element{}",
+        counts.reactants,
+        counts.products,
+        counts.elements,
+        if counts.elements == 1 { "" } else { "s" }
+    )
+}
+
+/// Explain why an equation can't be balanced, highlighting the elements at fault
+fn diagnosis_line(diagnosis: &Diagnosis, theme: Theme) -> Line<'static> {
+    let element_style = Style::default()
+        .fg(theme.highlight())
+        .add_modifier(Modifier::BOLD);
+    let mut spans = vec![Span::raw("Can't balance: ")];
+    match diagnosis {
+        Diagnosis::OneSided { left, right } => {
+            let sides = [(left, "left"), (right, "right")];
+            for (i, (elements, side)) in sides.iter().filter(|(e, _)| !e.is_empty()).enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(", "));
+                }
+                for (j, el) in elements.iter().enumerate() {
+                    if j > 0 {
+                        spans.push(Span::raw(" "));
+                    }
+                    spans.push(Span::styled(*el, element_style));
+                }
+                spans.push(Span::raw(format!(" only on the {side}")));
+            }
+        }
+        Diagnosis::OverDetermined { elements } => spans.push(Span::raw(format!(
+            "over-determined by {elements} elements, is a compound missing?"
+        ))),
+    }
+    Line::from(spans)
+}
+
+/// Warn about formulas with the wrong capitals, with what they should be. It's
+/// a likely mistake rather than an error, so it's yellow instead of red
+fn miscased_line(miscased: &[(String, String)], theme: Theme) -> Line<'static> {
+    let warning = Style::default().fg(theme.highlight());
+    let mut spans = vec![Span::styled("Check the capitals: ", warning)];
+    for (i, (typed, fixed)) in miscased.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(", ", warning));
+        }
+        spans.push(Span::styled(format!("{typed} should be "), warning));
+        spans.push(Span::styled(
+            fixed.clone(),
+            warning.add_modifier(Modifier::BOLD),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// The oxidation state of each element in each compound, with the ones that
+/// change across the arrow highlighted, then which were oxidized and reduced
+fn oxidation_lines(input: &str, eq: &Equation, theme: Theme) -> Vec<Line<'static>> {
+    let style = Style::default().fg(Color::DarkGray);
+    let changed = style.fg(theme.highlight()).add_modifier(Modifier::BOLD);
+    let changes = oxidation::changes(input, eq);
+    let mut lines: Vec<Line> = oxidation::compounds(input, eq)
+        .into_iter()
+        .map(|(formula, states)| {
+            let Some(states) = states else {
+                return Line::styled(
+                    charge::superscript_charges(&oxidation::describe(&formula, None)),
+                    style,
+                );
+            };
+            let shown = charge::superscript_charges(&formula);
+            let mut spans = vec![Span::styled(format!("{shown}: "), style)];
+            let note = oxidation::note(&states);
+            for (i, (element, state)) in states.into_iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::styled(", ", style));
+                }
+                let highlight = changes.iter().any(|c| {
+                    c.element == element
+                        && ((c.reactant == formula && c.from == state)
+                            || (c.product == formula && c.to == state))
+                });
+                spans.push(Span::styled(
+                    format!("{element} {state}"),
+                    if highlight { changed } else { style },
+                ));
+            }
+            if let Some(note) = note {
+                spans.push(Span::styled(format!(" ({note})"), style));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    lines.extend(changes.iter().map(|c| {
+        let verb = if c.oxidized() { "Oxidized" } else { "Reduced" };
+        Line::styled(charge::superscript_charges(&format!("{verb}: {c}")), style)
+    }));
+    lines
+}
+
+/// What's being practiced, then the answer once it's shown or guessed
+fn quiz_body(quiz: &Quiz) -> Paragraph<'static> {
+    let line = if quiz.revealed {
+        Line::styled(
+            format!("Answer: {}", quiz.answer),
+            Style::default().fg(Color::Green),
+        )
+    } else if quiz.solved {
+        Line::styled(
+            format!("Correct! {}", quiz.answer),
+            Style::default().fg(Color::Green),
+        )
+    } else {
+        Line::raw(format!("Balance the {}", quiz.example.name.to_lowercase()))
+    };
+    Paragraph::new(line).wrap(Wrap { trim: false })
+}
+
+/// Write a balanced equation, highlighting the coefficients that are different
+/// to the ones in `before`. A coefficient changed to 1 is written out so the
+/// change can be seen. States like `(aq)` are dimmed so they don't look like
+/// part of the formula. With `fractional`, the coefficients are fractions
+/// with one of the first reactant
+fn equation_line(
+    input: &str,
+    eq: &Equation,
+    before: Option<&Equation>,
+    ones: bool,
+    fractional: bool,
+    subscript: bool,
+    theme: Theme,
+) -> Line<'static> {
+    let before: Vec<usize> = before
+        .filter(|b| b.num_compounds() == eq.num_compounds())
+        .map(|b| b.iter_compounds().map(|c| c.coefficient).collect())
+        .unwrap_or_default();
+    let fractions = fractional.then(|| fractions(eq, 0));
+    let coefficient = |i: usize, n: usize| match fractions.as_ref().map(|f| f[i]) {
+        Some(f) if f.den != 1 => format!("{f} "),
+        Some(f) => f.to_string(),
+        None => n.to_string(),
+    };
+    let changed_style = Style::default()
+        .fg(theme.accent())
+        .add_modifier(Modifier::BOLD);
+    let state_style = Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::ITALIC);
+
+    let mut spans = Vec::new();
+    for (i, (cmp, formula)) in eq
+        .iter_compounds()
+        .zip(chem::typed_formulas(input, eq))
+        .enumerate()
+    {
+        if i == eq.left().len() {
+            spans.push(Span::raw(format!(" {} ", eq.direction())));
+        } else if i > 0 {
+            spans.push(Span::raw(" + "));
+        }
+        let changed = before.get(i).is_some_and(|b| *b != cmp.coefficient);
+        let written = coefficient(i, cmp.coefficient);
+        if changed {
+            spans.push(Span::styled(written, changed_style));
+        } else if written != "1" || ones {
+            spans.push(Span::raw(written));
+        }
+        let (formula, state) = charge::split_state(&formula);
+        let formula = charge::superscript_charges(formula);
+        if subscript {
+            spans.push(Span::raw(subscripts(&formula)));
+        } else {
+            spans.push(Span::raw(formula));
+        }
+        if !state.is_empty() {
+            spans.push(Span::styled(state.to_string(), state_style));
+        }
+    }
+    Line::from(spans)
+}
+
+/// Write a short duration with a fitting unit, eg. `153µs` or `1.4ms`
+fn format_duration(d: Duration) -> String {
+    let micros = d.as_micros();
+    if micros < 1000 {
+        format!("{micros}µs")
+    } else if micros < 1_000_000 {
+        format!("{:.1}ms", micros as f64 / 1000.0)
+    } else {
+        format!("{:.2}s", d.as_secs_f64())
+    }
+}
+
+/// Longest preview of an equation in the tab strip, in characters
+const TAB_PREVIEW: usize = 12;
+
+/// A tab for each equation, numbered for switching with the number keys
+fn tab_strip(app: &App) -> impl Widget {
+    let titles = app.tabs.iter().enumerate().map(|(i, tab)| {
+        let input = if i == app.active_tab {
+            &app.input
+        } else {
+            &tab.input
+        };
+        let preview = if input.is_empty() {
+            "new".to_string()
+        } else if input.chars().count() > TAB_PREVIEW {
+            let start: String = input.chars().take(TAB_PREVIEW - 1).collect();
+            format!("{}…", start.trim_end())
+        } else {
+            input.to_string()
+        };
+        format!("{} {preview}", i + 1)
+    });
+    Tabs::new(titles)
+        .select(app.active_tab)
+        .style(Style::default().fg(Color::DarkGray))
+        .highlight_style(
+            Style::default()
+                .fg(app.theme.highlight())
+                .add_modifier(Modifier::BOLD),
+        )
+}
+
+/// Keys in normal mode after the ones in its help panel
+const NORMAL_KEYS: &str = " Y L U w l < >   copy input, LaTeX, unicode, save, load, step
+ r a             edit the balanced equation, use a suggestion
+ E Q c T         examples, quiz, convert g and mol, transcript
+ h or F1 p       to show this screen or the periodic table
+ tab shift-tab   switch pane, enter uses it
+ o O I F         ones, oxidation states, net ionic, fractions
+ n x [ ] 1-9     tabs, ctrl-s saves them, ctrl-e reports them
+ v R s S         input alongside, redox, amounts, swap sides
+ * f ,           star it, pick a starred one, settings";
+
+/// Every keybinding, under a heading for each mode. Kept to 22 lines so the
+/// screen fits an 80x24 terminal
+fn help_lines() -> Vec<Line<'static>> {
+    let heading = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::styled(" Normal mode", heading)];
+    lines.extend(
+        InputMode::Normal
+            .to_help()
+            .lines()
+            .chain(NORMAL_KEYS.lines())
+            .map(Line::raw),
+    );
+    lines.push(Line::styled(" Editing mode", heading));
+    lines.extend(InputMode::Editing.to_help().lines().map(Line::raw));
+    lines.push(Line::styled(
+        " ctrl-c quits, ctrl-l redraws, ctrl-z suspends, any key closes",
+        Style::default().fg(Color::DarkGray),
+    ));
+    lines
+}
+
+/// The full screen list of keybindings, with the version and where to find the project
+fn help_screen(lines: Vec<Line<'static>>) -> impl Widget {
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .title(concat!(" cmbl v", env!("CARGO_PKG_VERSION"), " "))
+            .title_alignment(Alignment::Center)
+            .title_bottom(Line::from(concat!(" ", env!("CARGO_PKG_HOMEPAGE"), " ")).centered())
+            .borders(Borders::ALL),
+    )
+}
+
+/// The choice of formats to copy the balanced equation as
+fn copy_menu() -> impl Widget {
+    Paragraph::new(" p  plain\n l  LaTeX\n u  unicode\n j  json\n esc  cancel").block(
+        Block::default()
+            .title(" Copy as ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL),
+    )
+}
+
+/// The formats the transcript can be copied or saved as
+fn transcript_menu() -> impl Widget {
+    Paragraph::new(" p  plain\n m  markdown\n c  csv\n shift  save to a file\n esc  cancel").block(
+        Block::default()
+            .title(" Transcript as ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL),
+    )
+}
+
+/// Search the examples, with the chosen one's equation along the bottom
+fn example_picker(f: &mut Frame, app: &App, area: Rect) {
+    let found = examples::search(&app.example_query);
+    let mut block = Block::default()
+        .title(" Examples ")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    if let Some(e) = found.get(app.example_selected) {
+        block = block.title_bottom(Line::styled(
+            format!(" {} ", e.equation),
+            Style::default().fg(app.theme.accent()),
+        ));
+    }
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let [query, list] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+    f.render_widget(
+        Paragraph::new(format!(" > {}", app.example_query))
+            .style(Style::default().fg(app.theme.highlight())),
+        query,
+    );
+    if found.is_empty() {
+        f.render_widget(
+            Paragraph::new(" No matches").style(Style::default().fg(Color::DarkGray)),
+            list,
+        );
+        return;
+    }
+    let items: Vec<ListItem> = found.iter().map(|e| ListItem::new(e.name)).collect();
+    let list_widget = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(" ");
+    let mut state = ListState::default().with_selected(Some(app.example_selected));
+    f.render_stateful_widget(list_widget, list, &mut state);
+}
+
+/// The favorite equations, to pick one for the input
+fn favorites_picker(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Favorites ")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::styled(
+            " delete removes ",
+            Style::default().fg(Color::DarkGray),
+        ))
+        .borders(Borders::ALL);
+    let items: Vec<ListItem> = app
+        .favorites
+        .iter()
+        .map(|f| ListItem::new(f.as_str()))
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(" ");
+    let mut state = ListState::default().with_selected(Some(app.favorite_selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Each setting with whether it's on, to turn them on and off
+fn settings_panel(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title(" Settings ")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::styled(
+            " saved on exit ",
+            Style::default().fg(Color::DarkGray),
+        ))
+        .borders(Borders::ALL);
+    let items: Vec<ListItem> = Setting::ALL
+        .iter()
+        .map(|s| {
+            let check = if s.value(app) { "[x]" } else { "[ ]" };
+            ListItem::new(format!("{check} {}", s.name()))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(" ");
+    let mut state = ListState::default().with_selected(Some(app.setting_selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// What's typed to convert, with it in the other unit underneath
+fn convert_panel(app: &App) -> impl Widget {
+    let result = match app.converted() {
+        Some(Ok(converted)) => {
+            Line::styled(format!(" = {converted}"), Style::default().fg(Color::Green))
+        }
+        Some(Err(e)) => Line::styled(format!(" {e}"), Style::default().fg(Color::DarkGray)),
+        None => Line::styled(" eg. 18 g H2O", Style::default().fg(Color::DarkGray)),
+    };
+    Paragraph::new(vec![
+        Line::styled(
+            format!(" > {}", app.convert_query),
+            Style::default().fg(app.theme.highlight()),
+        ),
+        result,
+    ])
+    .block(
+        Block::default()
+            .title(" Convert ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL),
+    )
+}
+
+/// Where to save the balanced equation, and whether it's added to the file
+fn save_panel(app: &App) -> impl Widget {
+    let mode = match app.save_append {
+        true => " tab: adding to the end of it",
+        false => " tab: replacing what's in it",
+    };
+    Paragraph::new(vec![
+        Line::styled(
+            format!(" > {}", app.save_path),
+            Style::default().fg(app.theme.highlight()),
+        ),
+        Line::styled(mode, Style::default().fg(Color::DarkGray)),
+    ])
+    .block(
+        Block::default()
+            .title(" Save to ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL),
+    )
+}
+
+/// The file to load equations from
+fn load_panel(app: &App) -> impl Widget {
+    Paragraph::new(vec![
+        Line::styled(
+            format!(" > {}", app.load_path),
+            Style::default().fg(app.theme.highlight()),
+        ),
+        Line::styled(
+            " one equation to a line",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ])
+    .block(
+        Block::default()
+            .title(" Load from ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL),
+    )
+}
+
+/// Size of an element in the periodic table, borders included
+const CELL_WIDTH: u16 = 8;
+const CELL_HEIGHT: u16 = 5;
+
+/// The periodic table, scrolled to keep the highlighted element in view when
+/// it doesn't all fit, with what's typed to find one along the bottom
+fn periodic_table(f: &mut Frame, app: &App, area: Rect) {
+    let selected = table::element(app.table_selected);
+    let footer = if app.table_query.is_empty() {
+        Line::styled(
+            " type a symbol or name to find it ",
+            Style::default().fg(Color::DarkGray),
+        )
+    } else {
+        Line::styled(
+            format!(" > {} ", app.table_query),
+            Style::default().fg(app.theme.highlight()),
+        )
+    };
+    let block = Block::default()
+        .title(" Periodic table ")
+        .title_alignment(Alignment::Center)
+        .title_bottom(footer)
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let rows = (inner.height / CELL_HEIGHT) as usize;
+    let columns = (inner.width / CELL_WIDTH) as usize;
+    let (row, column) = table::position(selected);
+    // the highlight stays in the middle until an edge of the table is reached
+    let first_row = row
+        .saturating_sub(rows / 2)
+        .min(table::ROWS.saturating_sub(rows));
+    let first_column = column
+        .saturating_sub(columns / 2)
+        .min(table::COLUMNS.saturating_sub(columns));
+    for r in first_row..(first_row + rows).min(table::ROWS) {
+        for c in first_column..(first_column + columns).min(table::COLUMNS) {
+            let Some(el) = table::at(r, c) else {
+                continue;
+            };
+            let cell = Rect {
+                x: inner.x + (c - first_column) as u16 * CELL_WIDTH,
+                y: inner.y + (r - first_row) as u16 * CELL_HEIGHT,
+                width: CELL_WIDTH,
+                height: CELL_HEIGHT,
+            };
+            let highlight = if el == selected {
+                Style::default().fg(app.theme.highlight())
+            } else {
+                Style::default()
+            };
+            let info = Info::from(el);
+            let text = vec![
+                Line::styled(
+                    info.number.to_string(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Line::styled(info.symbol, highlight.add_modifier(Modifier::BOLD)),
+                Line::from(stoich::round(info.mass)),
+            ];
+            f.render_widget(
+                Paragraph::new(text).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(highlight),
+                ),
+                cell,
+            );
+        }
+    }
+
+    // in the corner away from the highlight, so it isn't covered
+    let popup = Rect {
+        x: inner.right().saturating_sub(ELEMENT_INFO_WIDTH),
+        y: if row - first_row < rows / 2 {
+            inner.bottom().saturating_sub(ELEMENT_INFO_HEIGHT)
+        } else {
+            inner.y
+        },
+        width: ELEMENT_INFO_WIDTH.min(inner.width),
+        height: ELEMENT_INFO_HEIGHT.min(inner.height),
+    };
+    f.render_widget(Clear, popup);
+    f.render_widget(element_info(Info::from(selected), app.theme), popup);
+}
+
+/// Size of the highlighted element's details over the periodic table
+const ELEMENT_INFO_WIDTH: u16 = 40;
+const ELEMENT_INFO_HEIGHT: u16 = 5;
+
+/// The name, atomic number, mass and usual oxidation states of an element
+fn element_info(info: Info, theme: Theme) -> impl Widget {
+    Paragraph::new(vec![
+        Line::from(format!("{}, atomic number {}", info.symbol, info.number)),
+        Line::from(format!("{} g/mol", info.mass)),
+        Line::from(format!("Oxidation states {}", info.states_text())),
+    ])
+    .wrap(Wrap { trim: false })
+    .block(
+        Block::default()
+            .title(format!(" {} ", info.name))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.accent()))
+            .padding(Padding::left(1)),
+    )
+}
+
+/// The amount typed for each compound, with how much of everything reacts or
+/// is made and which reactant runs out first. Under that are the mole ratios
+/// of the chosen compound to the others, and what its mass is made of
+fn stoich_panel(f: &mut Frame, app: &App, eq: &Equation, area: Rect) {
+    let block = Block::default()
+        .title(" Amounts ")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::styled(
+            " tab g/mol ",
+            Style::default().fg(Color::DarkGray),
+        ))
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let [list, summary, ratios, composition] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(inner);
+    let found = app.stoich_yield();
+    let formulas = chem::typed_formulas(app.balanced_from(), eq);
+    let results: Vec<String> = match &found {
+        Some(Ok(y)) => stoich::table(&y.amounts)
+            .lines()
+            .map(str::to_string)
+            .collect(),
+        _ => formulas.clone(),
+    };
+    let items: Vec<ListItem> = app
+        .stoich_inputs
+        .iter()
+        .zip(results)
+        .map(|((amount, unit), result)| ListItem::new(format!("{amount:>8} {unit:<3}  {result}")))
+        .collect();
+    let list_widget = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(" ");
+    let mut state = ListState::default().with_selected(Some(app.stoich_selected));
+    f.render_stateful_widget(list_widget, list, &mut state);
+
+    let (mut text, mut color) = match &found {
+        None => (
+            "type an amount of any compound".to_string(),
+            Color::DarkGray,
+        ),
+        Some(Err(e)) => (e.to_string(), Color::Red),
+        Some(Ok(y)) => (limiting_summary(y), app.theme.highlight()),
+    };
+    // with a product typed too, how much of it was made
+    match app.stoich_percent_yield() {
+        Some(Ok(p)) => {
+            let made = format!(
+                "{}% yield of {} {}",
+                stoich::round(p.percent),
+                stoich::round(p.theoretical),
+                p.unit
+            );
+            text = match text.as_str() {
+                "" => made,
+                _ => format!("{text}, {made}"),
+            };
+        }
+        Some(Err(e)) => (text, color) = (e.to_string(), Color::Red),
+        None => {}
+    }
+    f.render_widget(
+        Paragraph::new(format!(" {text}")).style(Style::default().fg(color)),
+        summary,
+    );
+
+    let selected = app.stoich_selected.min(formulas.len().saturating_sub(1));
+    let chosen = &formulas[selected];
+    let ratio_text: Vec<String> = (0..formulas.len())
+        .filter(|&i| i != selected)
+        .map(|i| {
+            let (a, b) = stoich::mole_ratio(eq, selected, i);
+            format!("{chosen}:{} {a}:{b}", formulas[i])
+        })
+        .collect();
+    let composition_text = eq
+        .iter_compounds()
+        .nth(selected)
+        .and_then(stoich::composition)
+        .map(|parts| {
+            let parts: Vec<String> = parts
+                .iter()
+                .map(|(e, share)| format!("{e} {}%", stoich::round(*share)))
+                .collect();
+            format!("{chosen} is {}", parts.join(", "))
+        })
+        .unwrap_or_default();
+    let dim = Style::default().fg(Color::DarkGray);
+    f.render_widget(
+        Paragraph::new(format!(" {}", ratio_text.join(", "))).style(dim),
+        ratios,
+    );
+    f.render_widget(
+        Paragraph::new(format!(" {composition_text}")).style(dim),
+        composition,
+    );
+}
+
+/// Which reactant runs out and how much of the others is left, in a line
+fn limiting_summary(y: &Yield) -> String {
+    let Some(index) = y.limiting else {
+        // with one amount there's nothing for it to run out before
+        return if y.excess.is_empty() && y.known > 1 {
+            "no limiting reagent, nothing left over".to_string()
+        } else {
+            String::new()
+        };
+    };
+    let mut out = format!("{} runs out", y.amounts[index].formula);
+    for a in &y.excess {
+        let left = match a.grams {
+            Some(grams) => format!("{} g", stoich::round(grams)),
+            None => format!("{} mol", stoich::round(a.moles)),
+        };
+        out.push_str(&format!(", {left} {} left", a.formula));
+    }
+    out
+}
+
+/// A rectangle of at most `width` by `height` in the middle of `area`
+fn centered(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Draw tui ui
+pub fn ui(f: &mut Frame, app: &App) {
+    let size = f.area();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        let msg = Paragraph::new(format!(
+            "Terminal too small (need at least {MIN_WIDTH}x{MIN_HEIGHT})"
+        ))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .style(Style::default().fg(Color::Red));
+        f.render_widget(msg, size);
+        // nothing can be clicked
+        app.panes.set(Panes::default());
+        return;
+    }
+
+    // the output gets the help's space when it's hidden, and grows to fit a
+    // long equation up to what's left of the screen
+    let panel_height = app.input_mode.help_lines() + 2;
+    let help_height = if app.show_help { panel_height } else { 0 };
+    let spare = size.height.saturating_sub(2 + 1 + 3 + help_height + 1);
+    let output_height = app
+        .output_height(size.width.saturating_sub(2))
+        .min(spare)
+        .max(3 + panel_height - help_height);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Length(3),
+                Constraint::Length(output_height),
+                Constraint::Length(help_height),
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(size);
+
+    // title, or the tabs once there's more than one
+    if app.tabs.len() > 1 {
+        f.render_widget(tab_strip(app), chunks[0]);
+    } else {
+        let title = Paragraph::new("Chemical Equation Balancer")
+            .alignment(Alignment::Center)
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        f.render_widget(title, chunks[0]);
+    }
+
+    app.panes.set(Panes {
+        input: chunks[1],
+        output: chunks[2],
+    });
+
+    // input area, less the borders
+    let input_width = chunks[1].width.saturating_sub(2);
+    let input_body = app.input_body(app.input_scroll(input_width));
+    f.render_widget(input_body, chunks[1]);
+
+    // output area, next to the unbalanced input when side by side
+    if app.side_by_side {
+        let halves = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(chunks[2]);
+        f.render_widget(app.unbalanced_body(), halves[0]);
+        let output = app.output_body(halves[1]);
+        f.render_widget(output, halves[1]);
+    } else {
+        let output = app.output_body(chunks[2]);
+        f.render_widget(output, chunks[2]);
+    }
+
+    // help area
+    if app.show_help {
+        let help_body = Paragraph::new(app.input_mode.to_help()).block(
+            Block::default()
+                .title("Help (h for all keys)")
+                .borders(Borders::ALL),
+        );
+        f.render_widget(help_body, chunks[3]);
+    }
+
+    // status line, with the size of the equation and the balancing time on the right
+    let status_body = app.status_body();
+    f.render_widget(status_body, chunks[4]);
+    let mut right = Vec::new();
+    if let Some(counts) = app.counts {
+        let style = match app.counts_stale {
+            true => Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::DIM),
+            // not the colour of the status message under it
+            false => Style::default().fg(Color::Reset),
+        };
+        right.push(Span::styled(counts_text(counts), style));
+    }
+    if let Some(quiz) = &app.quiz {
+        right.push(Span::styled(
+            format!("score {}/{}", quiz.right, quiz.finished),
+            Style::default().fg(app.theme.accent()),
+        ));
+    } else if let Some(took) = app.balance_time.filter(|_| app.show_timing) {
+        right.push(Span::styled(
+            format!("balanced in {}", format_duration(took)),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if !right.is_empty() {
+        let mut spans = Vec::new();
+        for (i, span) in right.into_iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(span);
+        }
+        spans.push(Span::raw(" "));
+        f.render_widget(
+            Paragraph::new(Line::from(spans)).alignment(Alignment::Right),
+            chunks[4],
+        );
+    }
+
+    // atom counts while typing, in whatever room is left
+    if let (InputMode::Editing, Some(tally)) = (&app.input_mode, app.tally_text()) {
+        let area = chunks[5];
+        let height = (tally.line_count(area.width.saturating_sub(3)) as u16 + 2).min(area.height);
+        if height >= 3 {
+            let area = Rect { height, ..area };
+            let block = Block::default()
+                .title("Atoms, left and right")
+                .borders(Borders::ALL)
+                .padding(Padding::left(1));
+            f.render_widget(tally.block(block), area);
+        }
+    }
+    // and the mass of each side once it's balanced
+    if let (InputMode::Normal, Some(mass)) = (&app.input_mode, app.mass_text()) {
+        let area = chunks[5];
+        if area.height >= 3 {
+            let block = Block::default()
+                .title("Mass, left and right")
+                .borders(Borders::ALL)
+                .padding(Padding::left(1));
+            f.render_widget(mass.block(block), Rect { height: 3, ..area });
+        }
+    }
+
+    // cursor
+    match app.input_mode {
+        InputMode::Editing => {
+            f.set_cursor_position((
+                chunks[1].x + 1 + app.cursor_column(input_width),
+                chunks[1].y + 1,
+            ));
+        }
+        InputMode::Normal => {}
+        InputMode::Help => {
+            let lines = help_lines();
+            let area = centered(64, lines.len() as u16 + 2, size);
+            f.render_widget(Clear, area);
+            f.render_widget(help_screen(lines), area);
+        }
+        InputMode::Copy => {
+            let area = centered(24, 7, size);
+            f.render_widget(Clear, area);
+            f.render_widget(copy_menu(), area);
+        }
+        InputMode::Transcript => {
+            let area = centered(26, 7, size);
+            f.render_widget(Clear, area);
+            f.render_widget(transcript_menu(), area);
+        }
+        InputMode::Examples => example_picker(f, app, centered(48, 14, size)),
+        InputMode::Favorites => favorites_picker(f, app, centered(56, 14, size)),
+        InputMode::Settings => settings_panel(f, app, centered(50, 7, size)),
+        InputMode::Convert => {
+            let area = centered(48, 4, size);
+            f.render_widget(Clear, area);
+            f.render_widget(convert_panel(app), area);
+        }
+        InputMode::Save => {
+            let area = centered(48, 4, size);
+            f.render_widget(Clear, area);
+            f.render_widget(save_panel(app), area);
+        }
+        InputMode::Load => {
+            let area = centered(48, 4, size);
+            f.render_widget(Clear, area);
+            f.render_widget(load_panel(app), area);
+        }
+        InputMode::Table => {
+            let width = CELL_WIDTH * table::COLUMNS as u16 + 2;
+            let height = CELL_HEIGHT * table::ROWS as u16 + 2;
+            periodic_table(f, app, centered(width, height, size));
+        }
+        InputMode::Stoich => {
+            if let Some(Ok(eq)) = &app.output {
+                let height = eq.num_compounds() as u16 + 5;
+                stoich_panel(f, app, eq, centered(60, height, size));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+    use super::*;
+    use crate::stoich::Unit;
+
+    /// Draw the app and get each line of the screen
+    fn render(app: &App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| ui(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(width as usize)
+            .map(|row| row.iter().map(|c| c.symbol()).collect())
+            .collect()
+    }
+
+    /// Draw the app on a standard 80x24 terminal
+    fn draw(app: &App) -> Buffer {
+        draw_sized(app, 80, 24)
+    }
+
+    /// Draw the app on a terminal of any size
+    fn draw_sized(app: &App, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| ui(f, app)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// The text of the screen, without trailing whitespace
+    fn screen(buffer: &Buffer) -> String {
+        let lines: Vec<String> = buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| {
+                let line: String = row.iter().map(|c| c.symbol()).collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+        lines.join("\n").trim_end().to_string()
+    }
+
+    /// Assert the input and output boxes show these lines
+    fn assert_boxes(buffer: &Buffer, input: &str, output: &str) {
+        assert_labelled_boxes(buffer, input, output, "");
+    }
+
+    /// Assert the input and output boxes show these lines, with the reaction
+    /// type under the output
+    /// `output` can be several lines, one per line of the box
+    fn assert_labelled_boxes(buffer: &Buffer, input: &str, output: &str, label: &str) {
+        let screen = screen(buffer);
+        let outputs = output.lines().count();
+        let lines: Vec<&str> = screen.lines().skip(2).take(5 + outputs).collect();
+        let border = format!(" ┌{}┐", "─".repeat(76));
+        let bottom = format!(" └{}┘", "─".repeat(76));
+        let label = match label {
+            "" => String::new(),
+            label => format!(" {label} "),
+        };
+        let labelled = format!(" └{label}{}┘", "─".repeat(76 - label.chars().count()));
+        let mut expected = vec![border.clone(), format!(" │ {input:<75}│"), bottom, border];
+        expected.extend(output.lines().map(|l| format!(" │ {l:<75}│")));
+        expected.push(labelled);
+        assert_eq!(lines, expected);
+    }
+
+    fn app_with(input: &str) -> App {
+        let mut app = App {
+            input: input.to_string(),
+            ..Default::default()
+        };
+        app.update_eq();
+        app
+    }
+
+    #[test]
+    fn too_short() {
+        let lines = render(&App::default(), 80, 10);
+        assert!(lines[0].contains(&format!(
+            "Terminal too small (need at least {MIN_WIDTH}x{MIN_HEIGHT})"
+        )));
+        assert!(!lines
+            .iter()
+            .any(|l| l.contains("Chemical Equation Balancer")));
+    }
+
+    #[test]
+    fn too_small() {
+        let lines = render(&App::default(), 20, 5);
+        assert_eq!(lines[0].trim(), "Terminal too small");
+        assert!(!lines.iter().any(|l| l.contains("Help")));
+    }
+
+    #[test]
+    fn big_enough() {
+        let lines = render(&App::default(), MIN_WIDTH, MIN_HEIGHT);
+        assert!(lines[1].contains("Chemical Equation Balancer"));
+        assert!(lines.iter().any(|l| l.contains("Help")));
+    }
+
+    #[test]
+    fn balanced_output() {
+        let lines = render(&app_with("Fe + O2 -> Fe2O3"), 60, 20);
+        assert_eq!(
+            lines[3],
+            " │ Fe + O2 -> Fe2O3                                       │ "
+        );
+        assert_eq!(
+            lines[6],
+            " │ 4Fe + 3O2 -> 2Fe2O3                                    │ "
+        );
+    }
+
+    #[test]
+    fn divided_output() {
+        let lines = render(&app_with("4H2 + 2O2 -> 4H2O"), 60, 20);
+        assert_eq!(
+            lines[5],
+            " ┌────────────────────────────────────────── divided by 2 ┐ "
+        );
+        assert!(lines[6].contains("2H2 + O2 -> 2H2O"));
+
+        let lines = render(&app_with("Fe + O2 -> Fe2O3"), 60, 20);
+        assert!(!lines[5].contains("divided"));
+    }
+
+    #[test]
+    fn scrolled_output() {
+        let mut app = app_with("Ca(OH)2 + H3PO4 -> Ca3(PO4)2 + H2O");
+        // the help is tallest while editing, leaving the output one line
+        app.input_mode = InputMode::Editing;
+        let lines = render(&app, MIN_WIDTH, MIN_HEIGHT);
+        assert_eq!(lines[6], " │ 3Ca(OH)2 + 2H3PO4 -> Ca3(PO4)2 +   │ ");
+        assert_eq!(lines[7], " └ neutralization ───────────── [1/2] ┘ ");
+        assert_eq!(
+            app.output_view.get(),
+            OutputView {
+                lines: 2,
+                height: 1
+            }
+        );
+
+        app.scroll_output(5);
+        let lines = render(&app, MIN_WIDTH, MIN_HEIGHT);
+        assert_eq!(lines[6], " │ 6H2O                               │ ");
+        assert_eq!(lines[7], " └ neutralization ───────────── [2/2] ┘ ");
+
+        // everything fits on a wider terminal
+        let lines = render(&app, 80, 24);
+        assert!(lines[6].contains("3Ca(OH)2 + 2H3PO4 -> Ca3(PO4)2 + 6H2O"));
+        assert!(!lines[7].contains('['));
+    }
+
+    #[test]
+    fn explicit_ones_output() {
+        let mut app = app_with("H2 + O2 -> H2O");
+        app.explicit_ones = true;
+        let lines = render(&app, 60, 20);
+        assert!(lines[6].contains(" 2H2 + 1O2 -> 2H2O "));
+    }
+
+    #[test]
+    fn pane_areas() {
+        let app = App::default();
+        draw(&app);
+        assert_eq!(
+            app.panes.get(),
+            Panes {
+                input: Rect::new(1, 2, 78, 3),
+                output: Rect::new(1, 5, 78, 3),
+            }
+        );
+        render(&app, 20, 5);
+        assert_eq!(app.panes.get(), Panes::default());
+    }
+
+    #[test]
+    fn side_by_side() {
+        let mut app = App {
+            side_by_side: true,
+            ..Default::default()
+        };
+        let lines = render(&app, 60, 20);
+        assert_eq!(
+            lines[6],
+            " │ Unbalanced equation...    ││ Waiting for equation...   │ "
+        );
+
+        app.set_input("Fe + O2 -> Fe2O3");
+        let buffer = draw(&app);
+        let text = screen(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[6],
+            " │ Fe + O2 -> Fe2O3                    ││ 4Fe + 3O2 -> 2Fe2O3                 │"
+        );
+        assert_eq!(buffer[(4, 6)].fg, Color::Reset);
+        assert_eq!(buffer[(43, 6)].fg, Color::Green);
+    }
+
+    #[test]
+    fn changed_coefficients_highlighted() {
+        let mut app = App {
+            side_by_side: true,
+            ..Default::default()
+        };
+        app.set_input("2Fe + 3O2 -> Fe2O3");
+        let buffer = draw(&app);
+        assert!(screen(&buffer)
+            .lines()
+            .nth(6)
+            .unwrap()
+            .ends_with("││ 4Fe + 3O2 -> 2Fe2O3                 │"));
+        // `4` and `2` changed, `3` was already right
+        assert_eq!(buffer[(42, 6)].fg, Color::Cyan);
+        assert_eq!(buffer[(48, 6)].fg, Color::Green);
+        assert_eq!(buffer[(55, 6)].fg, Color::Cyan);
+        assert_eq!(buffer[(56, 6)].fg, Color::Green);
+    }
+
+    #[test]
+    fn tab_strip() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.new_tab();
+        app.set_input("Ca(OH)2 + H3PO4 <-> Ca3(PO4)2 + H2O");
+        app.new_tab();
+        let buffer = draw(&app);
+        let text = screen(&buffer);
+        assert_eq!(
+            text.lines().nth(1).unwrap(),
+            "  1 Fe + O2 ->… │ 2 Ca(OH)2 + H… │ 3 new"
+        );
+        assert_eq!(buffer[(35, 1)].fg, Color::Yellow);
+        assert_eq!(buffer[(2, 1)].fg, Color::DarkGray);
+    }
+
+    #[test]
+    fn timing() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        assert!(app.balance_time.is_some());
+        app.balance_time = Some(Duration::from_micros(1530));
+        let lines = render(&app, 60, 20);
+        assert!(!lines[15].contains("balanced in"));
+
+        app.show_timing = true;
+        app.status = Some(Status::Info("Copied".to_string()));
+        let lines = render(&app, 60, 20);
+        assert_eq!(
+            lines[15],
+            "  Copied    2 → 1 compounds, 2 elements  balanced in 1.5ms  "
+        );
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::from_micros(153)), "153µs");
+        assert_eq!(format_duration(Duration::from_micros(1530)), "1.5ms");
+        assert_eq!(format_duration(Duration::from_millis(2310)), "2.31s");
+    }
+
+    #[test]
+    fn hidden_help() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.show_help = false;
+        let lines = render(&app, 60, 20);
+        assert!(!lines.iter().any(|l| l.contains("Help")));
+        // the output takes the space the help used
+        assert!(lines[5].contains('┌'));
+        assert!(lines[13].contains('└'));
+    }
+
+    #[test]
+    fn help_overlay() {
+        let mut app = App {
+            input_mode: InputMode::Help,
+            ..Default::default()
+        };
+        let lines = render(&app, 80, 24);
+        assert!(lines[0].contains(concat!("cmbl v", env!("CARGO_PKG_VERSION"))));
+        assert!(lines[1].contains(" Normal mode "));
+        assert!(lines.iter().any(|l| l.contains("Y L U")));
+        assert!(lines.iter().any(|l| l.contains("h or F1")));
+        assert!(lines.iter().any(|l| l.contains("leave editing mode")));
+        // nothing is cut off on a standard terminal, down to the last line
+        assert!(lines[21].contains(" ctrl-o          edit it in $EDITOR "));
+        assert!(
+            lines[22].contains(" ctrl-c quits, ctrl-l redraws, ctrl-z suspends, any key closes")
+        );
+        assert!(lines[23].contains(" https://github.com/beaconbrigade/balance-tui "));
+        assert_eq!(help_lines().len(), 22);
+
+        // still fits in the smallest terminal
+        let lines = render(&app, MIN_WIDTH, MIN_HEIGHT);
+        assert!(lines[0].contains("cmbl v"));
+        assert!(lines[MIN_HEIGHT as usize - 1].contains('└'));
+
+        app.input_mode = InputMode::Normal;
+        let lines = render(&app, 80, 24);
+        assert!(!lines.iter().any(|l| l.contains("cmbl v")));
+    }
+
+    #[test]
+    fn error_output() {
+        let lines = render(&app_with("Fe + S8 -> Fe2O3"), 60, 20);
+        assert!(lines[6].contains("Can't balance: S only on the left, O only on the right"));
+        // errors that can't be explained say what went wrong
+        let lines = render(&app_with("Fe + O2 => Fe2O3"), 60, 20);
+        assert!(lines[6].contains("Couldn't parse equation"));
+    }
+
+    #[test]
+    fn empty_snapshot() {
+        let buffer = draw(&App::default());
+        assert_eq!(
+            screen(&buffer),
+            "
+                           Chemical Equation Balancer
+ ┌────────────────────────────────────────────────────────────────────────────┐
+ │ Input equation...                                                          │
+ └────────────────────────────────────────────────────────────────────────────┘
+ ┌────────────────────────────────────────────────────────────────────────────┐
+ │ Waiting for equation...                                                    │
+ └────────────────────────────────────────────────────────────────────────────┘
+ ┌Help (h for all keys)───────────────────────────────────────────────────────┐
+ │ i or e          to edit                                                    │
+ │ q or esc        to quit                                                    │
+ │ y               to copy balanced equation                                  │
+ │ ?               to toggle this help                                        │
+ └────────────────────────────────────────────────────────────────────────────┘"
+        );
+        assert_eq!(buffer[(3, 5)].fg, Color::DarkGray);
+        // the input is focused to start with
+        assert_eq!(buffer[(3, 2)].fg, Color::Yellow);
+        assert_eq!(buffer[(3, 3)].fg, Color::DarkGray);
+    }
+
+    #[test]
+    fn focused_output() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.focus = Focus::Output;
+        let buffer = draw(&app);
+        assert_eq!(buffer[(3, 5)].fg, Color::Yellow);
+        assert_eq!(buffer[(3, 2)].fg, Color::DarkGray);
+        // the equation keeps its colour
+        assert_eq!(buffer[(3, 6)].fg, Color::Green);
+
+        // editing highlights the input whatever is focused
+        app.input_mode = InputMode::Editing;
+        let buffer = draw(&app);
+        assert_eq!(buffer[(3, 2)].fg, Color::Yellow);
+        assert_eq!(buffer[(3, 5)].fg, Color::Green);
+    }
+
+    #[test]
+    fn balanced_snapshot() {
+        let buffer = draw(&app_with("Fe + O2 -> Fe2O3"));
+        assert_labelled_boxes(
+            &buffer,
+            "Fe + O2 -> Fe2O3",
+            "4Fe + 3O2 -> 2Fe2O3\nOxidizing agent: O2, reducing agent: Fe",
+            "synthesis",
+        );
+        assert_eq!(buffer[(3, 5)].fg, Color::Green);
+        assert!(buffer[(27, 1)].modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn states_dimmed() {
+        let input = "NaCl(aq) + AgNO3(aq) -> AgCl(s) + NaNO3(aq)";
+        let buffer = draw(&app_with(input));
+        assert_labelled_boxes(&buffer, input, input, "double replacement");
+        // `NaCl` is the colour of the output, `(aq)` isn't
+        assert_eq!(buffer[(3, 6)].fg, Color::Green);
+        assert_eq!(buffer[(7, 6)].fg, Color::DarkGray);
+        assert!(buffer[(7, 6)].modifier.contains(Modifier::ITALIC));
+        assert!(!buffer[(3, 6)].modifier.contains(Modifier::ITALIC));
+    }
+
+    #[test]
+    fn completion_snapshot() {
+        let mut app = app_with("Zn + HC -> ZnCl2 + H2");
+        app.input_mode = InputMode::Editing;
+        app.cursor = 7;
+        let buffer = draw(&app);
+        assert!(screen(&buffer).contains("│ Zn + HCl -> ZnCl2 + H2 "));
+        assert_eq!(buffer[(9, 3)].fg, Color::Yellow);
+        assert_eq!(buffer[(10, 3)].fg, Color::DarkGray);
+        assert_eq!(buffer[(11, 3)].fg, Color::Yellow);
+    }
+
+    #[test]
+    fn editing_snapshot() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.input_mode = InputMode::Editing;
+        app.cursor = 4;
+        let buffer = draw(&app);
+        assert_labelled_boxes(
+            &buffer,
+            "Fe + O2 -> Fe2O3",
+            "4Fe + 3O2 -> 2Fe2O3\nOxidizing agent: O2, reducing agent: Fe",
+            "synthesis",
+        );
+        assert!(screen(&buffer).contains(" │ esc or ctrl-[   leave editing mode"));
+        assert!(screen(&buffer).contains(" │ ctrl-o          edit it in $EDITOR"));
+        // the input and its border are highlighted
+        assert_eq!(buffer[(3, 3)].fg, Color::Yellow);
+        assert_eq!(buffer[(1, 2)].fg, Color::Yellow);
+    }
+
+    #[test]
+    fn editing_help() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.input_mode = InputMode::Editing;
+        let text = screen(&draw(&app));
+        // down to the last line, nothing is cut off by the panel's border
+        for line in InputMode::Editing.to_help().lines() {
+            assert!(
+                text.contains(&format!(" │{line}")),
+                "{line:?} is missing\n{text}"
+            );
+        }
+        assert!(text.contains(" │ tab             fill in the grey formula"));
+        assert!(text.contains(" │ ctrl-z ctrl-y   undo and redo"));
+    }
+
+    #[test]
+    fn parse_error_snapshot() {
+        let buffer = draw(&app_with("Fe + O2 => Fe2O3"));
+        assert_boxes(&buffer, "Fe + O2 => Fe2O3", "Couldn't parse equation");
+        assert_eq!(buffer[(3, 5)].fg, Color::Red);
+    }
+
+    #[test]
+    fn invalid_snapshot() {
+        let buffer = draw(&app_with("Fe + S8 -> Fe2O3"));
+        assert_boxes(
+            &buffer,
+            "Fe + S8 -> Fe2O3",
+            "Can't balance: S only on the left, O only on the right",
+        );
+        assert_eq!(buffer[(3, 5)].fg, Color::Red);
+        // the elements at fault stand out
+        assert_eq!(buffer[(18, 6)].symbol(), "S");
+        assert_eq!(buffer[(18, 6)].fg, Color::Yellow);
+        assert!(buffer[(18, 6)].modifier.contains(Modifier::BOLD));
+        assert_eq!(buffer[(19, 6)].fg, Color::Red);
+        assert_eq!(buffer[(38, 6)].symbol(), "O");
+        assert_eq!(buffer[(38, 6)].fg, Color::Yellow);
+    }
+
+    #[test]
+    fn unknown_element_snapshot() {
+        let buffer = draw(&app_with("Fe + O2 -> FeXz"));
+        let text = screen(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[6].contains("│ Unknown element Xz in compound 3 "));
+        // the symbol is picked out in the input
+        assert_eq!(buffer[(16, 3)].symbol(), "X");
+        assert_eq!(buffer[(16, 3)].fg, Color::Red);
+        assert!(buffer[(17, 3)].modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(buffer[(15, 3)].fg, Color::DarkGray);
+    }
+
+    #[test]
+    fn table_snapshot() {
+        let mut app = App {
+            input_mode: InputMode::Table,
+            table_selected: 26,
+            ..Default::default()
+        };
+        let buffer = draw(&app);
+        let text = screen(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].contains("─ Periodic table ─"));
+        // scrolled so iron is in the middle
+        assert!(lines[12].starts_with("││22    ││23    ││24    ││25    ││26    ││27"));
+        assert!(lines[13].contains("││Fe    ││"));
+        assert!(lines[14].contains("││55.84 ││"));
+        assert_eq!(buffer[(34, 13)].fg, Color::Yellow);
+        assert_eq!(buffer[(26, 13)].fg, Color::Reset);
+        assert!(lines[23].contains("─ type a symbol or name to find it ─"));
+        // with its details in a corner it doesn't cover
+        assert!(lines[1].ends_with("┌ Iron ────────────────────────────────┐│"));
+        assert!(lines[2].contains("│ Fe, atomic number 26 "));
+        assert!(lines[3].contains("│ 55.845 g/mol "));
+        assert!(lines[4].contains("│ Oxidation states +2, +3 "));
+
+        // and hydrogen in the corner, with its details at the bottom
+        app.table_selected = 1;
+        app.table_query = "hyd".to_string();
+        let text = screen(&draw(&app));
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[3].starts_with("││H     │ "));
+        assert!(lines[18].contains("┌ Hydrogen ─"));
+        assert!(lines[21].contains("│ Oxidation states -1, +1 "));
+        assert!(lines[23].contains("─ > hyd ─"));
+    }
+
+    #[test]
+    fn combustion_snapshot() {
+        let lines = render(&app_with("CH4 + O2 ->"), 80, 24);
+        assert!(lines[7].contains("│ Burning it in O2? a to complete as combustion "));
+        let lines = render(&app_with("Fe + O2 ->"), 80, 24);
+        assert!(!lines[7].contains("combustion"));
+    }
+
+    #[test]
+    fn suggestion_snapshot() {
+        let buffer = draw(&app_with("co2 + H2O -> c6h12o6 + O2"));
+        let text = screen(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(
+            lines[6].contains("Check the capitals: co2 should be CO2, c6h12o6 should be C6H12O6")
+        );
+        assert!(lines[7].contains("Did you mean CO2 + H2O -> C6H12O6 + O2? a to use it"));
+        // a warning, not an error
+        assert_eq!(buffer[(5, 6)].fg, Color::Yellow);
+        assert_eq!(buffer[(5, 7)].fg, Color::DarkGray);
+        // the fix stands out
+        assert_eq!(buffer[(37, 6)].symbol(), "C");
+        assert!(buffer[(37, 6)].modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn closest_formula_snapshot() {
+        let buffer = draw(&app_with("2Na + Cl2 -> 2NaCi"));
+        let text = screen(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[6].contains("Unknown element Ci in compound 3"));
+        assert!(lines[7].contains("Did you mean NaCl instead of NaCi?"));
+        assert_eq!(buffer[(5, 7)].fg, Color::DarkGray);
+    }
+
+    #[test]
+    fn balance_error_snapshot() {
+        let buffer = draw(&app_with("H2O -> H2O2"));
+        assert_boxes(
+            &buffer,
+            "H2O -> H2O2",
+            "Can't balance: over-determined by 2 elements, is a compound missing?",
+        );
+        assert_eq!(buffer[(3, 5)].fg, Color::Red);
+    }
+
+    #[test]
+    fn long_input_snapshot() {
+        let mut app = app_with(&"CH4 + O2 -> CO2 + H2O ".repeat(5));
+        // normal mode shows the start of the input
+        let buffer = draw(&app);
+        let text = screen(&buffer);
+        let lines: Vec<&str> = text.lines().skip(3).take(5).collect();
+        assert_eq!(
+            lines,
+            [
+                " │ CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 │",
+                " └────────────────────────────────────────────────────────────────────────────┘",
+                " ┌────────────────────────────────────────────────────────────────────────────┐",
+                " │ Too much input: -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O   │",
+                // the box grows to fit the rest of the error
+                " │ CH4 + O2 -> CO2 + H2O                                                      │",
+            ]
+        );
+
+        // editing at the end scrolls the input to keep the cursor in view
+        app.input_mode = InputMode::Editing;
+        app.cursor = app.input.chars().count();
+        let buffer = draw(&app);
+        assert_eq!(
+            screen(&buffer).lines().nth(3),
+            Some(" │O2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O  │")
+        );
+    }
+
+    #[test]
+    fn resize() {
+        let mut app = app_with(&"CH4 + O2 -> CO2 + H2O ".repeat(5));
+        app.input_mode = InputMode::Editing;
+        app.cursor = app.input.chars().count();
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        assert_eq!(
+            screen(terminal.backend().buffer()).lines().nth(3),
+            Some(" │O2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O  │")
+        );
+
+        // the input is scrolled against the new width, without anything else changing
+        terminal.backend_mut().resize(120, 24);
+        terminal.autoresize().unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(
+            screen(buffer).lines().nth(3),
+            Some(" │ CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O CH4 + O2 -> CO2 + H2O      │")
+        );
+        assert_eq!(buffer.area.width, 120);
+        assert_eq!(terminal.get_cursor_position().unwrap().x, 113);
+    }
+
+    #[test]
+    fn wrapped_output() {
+        let app = app_with("C57H110O6 + O2 -> CO2 + H2O");
+        let lines = render(&app, 40, 22);
+        // the box grows to fit, breaking between compounds
+        assert_eq!(
+            lines[5..12],
+            [
+                " ┌────────────────────────────────────┐ ",
+                " │ 2C57H110O6 + 163O2 -> 114CO2 +     │ ",
+                // lined up with the first line
+                " │ 110H2O                             │ ",
+                " │ Oxidizing agent: O2, reducing      │ ",
+                " │ agent: C57H110O6                   │ ",
+                " └ combustion ────────────────────────┘ ",
+                " ┌Help (h for all keys)───────────────┐ ",
+            ]
+        );
+
+        // and scrolls when it can't grow any more
+        let mut app = app_with(&"C57H110O6 + O2 -> CO2 + H2O + ".repeat(8));
+        app.input_mode = InputMode::Editing;
+        let lines = render(&app, 40, MIN_HEIGHT);
+        assert!(lines[5].starts_with(" ┌"));
+        assert!(lines[7].contains("[1/"));
+        assert!(lines[8].contains("Help"));
+    }
+
+    #[test]
+    fn balancing_snapshot() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.balancing = true;
+        app.spinner = 9;
+        let buffer = draw(&app);
+        assert_boxes(&buffer, "Fe + O2 -> Fe2O3", "⠙ Balancing...");
+        assert_eq!(buffer[(5, 5)].fg, Color::DarkGray);
+
+        app.balance_started = Some(Instant::now());
+        assert_boxes(&draw(&app), "Fe + O2 -> Fe2O3", "⠙ Balancing...");
+        app.balance_started = Instant::now().checked_sub(Duration::from_secs(3));
+        assert_boxes(&draw(&app), "Fe + O2 -> Fe2O3", "⠙ Balancing... 3s");
+    }
+
+    #[test]
+    fn copy_menu_snapshot() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.input_mode = InputMode::Copy;
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[8].contains("┌────── Copy as ───────┐"));
+        assert!(lines[9].contains("│ p  plain             │"));
+        assert!(lines[13].contains("│ esc  cancel          │"));
+        assert!(lines[14].contains("└──────────────────────┘"));
+    }
+
+    #[test]
+    fn transcript_menu_snapshot() {
+        let app = App {
+            input_mode: InputMode::Transcript,
+            ..Default::default()
+        };
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[8].contains("─ Transcript as ─"), "{lines:#?}");
+        assert!(lines[10].contains("│ m  markdown "));
+        assert!(lines[12].contains("│ shift  save to a file "));
+    }
+
+    #[test]
+    fn example_picker_snapshot() {
+        let mut app = App {
+            input_mode: InputMode::Examples,
+            example_query: "comb".to_string(),
+            example_selected: 1,
+            ..Default::default()
+        };
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[5].contains("Examples"));
+        assert!(lines[6].contains(" > comb"));
+        assert!(lines[7].contains("Combustion of methane"));
+        assert!(lines[8].contains("Combustion of propane"));
+        assert!(lines[18].contains("C3H8 + O2 -> CO2 + H2O"));
+        let buffer = draw(&app);
+        assert!(buffer[(20, 8)].modifier.contains(Modifier::REVERSED));
+        assert!(!buffer[(20, 7)].modifier.contains(Modifier::REVERSED));
+
+        app.example_query = "qqq".to_string();
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[7].contains("No matches"));
+    }
+
+    #[test]
+    fn favorites_snapshot() {
+        let app = App {
+            input_mode: InputMode::Favorites,
+            favorites: vec!["H2 + O2 -> H2O".to_string(), "C + O2 -> CO2".to_string()],
+            favorite_selected: 1,
+            ..Default::default()
+        };
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[5].contains("─ Favorites ─"), "{lines:#?}");
+        assert!(lines[6].contains("│ H2 + O2 -> H2O "));
+        assert!(lines[7].contains("│ C + O2 -> CO2 "));
+        assert!(lines[18].contains(" delete removes "));
+        let buffer = draw(&app);
+        assert!(buffer[(20, 7)].modifier.contains(Modifier::REVERSED));
+        assert!(!buffer[(20, 6)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn settings_snapshot() {
+        let mut app = App {
+            input_mode: InputMode::Settings,
+            setting_selected: 2,
+            ..Default::default()
+        };
+        let lines = render(&app, 80, 24);
+        assert!(lines[8].contains("─ Settings ─"), "{lines:#?}");
+        assert!(lines[9].contains("│ [ ] Colours for a light background "));
+        assert!(lines[10].contains("│ [x] Show the keys under the output "));
+        assert!(lines[11].contains("│ [ ] Write numbers as subscripts "));
+        assert!(lines[12].contains("│ [x] Confirm quitting with an equation typed in │"));
+        assert!(lines[14].contains(" saved on exit "));
+        assert!(draw(&app)[(24, 11)].modifier.contains(Modifier::REVERSED));
+
+        app.subscripts = true;
+        let lines = render(&app, 80, 24);
+        assert!(lines[11].contains("│ [x] Write numbers as subscripts "));
+    }
+
+    #[test]
+    fn light_theme() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.input_mode = InputMode::Editing;
+        app.theme = Theme::Light;
+        let buffer = draw(&app);
+        // yellow is hard to read on white
+        assert_eq!(buffer[(3, 3)].fg, Color::Blue);
+        assert_eq!(buffer[(1, 2)].fg, Color::Blue);
+    }
+
+    #[test]
+    fn subscript_output() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.subscripts = true;
+        let lines = render(&app, 80, 24);
+        assert!(
+            lines.iter().any(|l| l.contains("4Fe + 3O₂ -> 2Fe₂O₃")),
+            "{lines:#?}"
+        );
+    }
+
+    #[test]
+    fn convert_snapshot() {
+        let mut app = App {
+            input_mode: InputMode::Convert,
+            convert_query: "18 g H2O".to_string(),
+            ..Default::default()
+        };
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[10].contains("─ Convert ─"), "{lines:#?}");
+        assert!(lines[11].contains("│ > 18 g H2O "));
+        assert!(lines[12].contains("│ = 0.9992 mol "));
+        assert_eq!(draw(&app)[(20, 12)].fg, Color::Green);
+
+        app.convert_query = "18 g Xy".to_string();
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[12].contains("couldn't read \"Xy\" as a formula"));
+    }
+
+    #[test]
+    fn save_snapshot() {
+        let mut app = App {
+            input_mode: InputMode::Save,
+            ..Default::default()
+        };
+        let buffer = draw(&app);
+        let lines: Vec<String> = screen(&buffer).lines().map(str::to_string).collect();
+        assert!(lines[10].contains("─ Save to ─"), "{lines:#?}");
+        assert!(lines[11].contains("│ > equations.txt "));
+        assert!(lines[12].contains("│ tab: adding to the end of it "));
+
+        app.save_append = false;
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[12].contains("tab: replacing what's in it"));
+
+        app.input_mode = InputMode::Load;
+        let lines: Vec<String> = screen(&draw(&app)).lines().map(str::to_string).collect();
+        assert!(lines[10].contains("─ Load from ─"));
+        assert!(lines[11].contains("│ > equations.txt "));
+    }
+
+    #[test]
+    fn tally_snapshot() {
+        let mut app = App {
+            input_mode: InputMode::Editing,
+            ..Default::default()
+        };
+        app.set_input("H2 + O2 -> H2O");
+        let lines = render(&app, 80, 24);
+        assert!(lines[18].contains("┌Atoms, left and right─"));
+        assert!(lines[19].contains("│ H 2=2  O 2≠1 "));
+        let buffer = draw(&app);
+        assert_eq!(buffer[(3, 19)].fg, Color::Green);
+        assert_eq!(buffer[(10, 19)].fg, Color::Red);
+
+        // only while typing
+        app.input_mode = InputMode::Normal;
+        assert!(!render(&app, 80, 24)[16].contains("Atoms"));
+        // once it's balanced, the masses instead
+        let lines = render(&app, 80, 24);
+        assert!(lines[16].contains("┌Mass, left and right─"));
+        assert!(lines[17].contains("│ 36.03 g = 36.03 g "));
+        assert_eq!(draw(&app)[(3, 17)].fg, Color::Green);
+        // nowhere to put it
+        app.input_mode = InputMode::Editing;
+        assert!(!render(&app, 80, 16).join("").contains("Atoms"));
+    }
+
+    #[test]
+    fn stoich_snapshot() {
+        let mut app = App::default();
+        app.set_input("H2 + O2 -> H2O");
+        app.input_mode = InputMode::Stoich;
+        app.stoich_inputs = vec![
+            ("10".to_string(), Unit::Grams),
+            ("10".to_string(), Unit::Grams),
+            (String::new(), Unit::Grams),
+        ];
+        let lines = render(&app, 80, 24);
+        assert!(lines[8].contains("┌──────────────────────── Amounts ─────────────────────────┐"));
+        assert!(lines[9].contains("│       10 g  H2    0.625 mol  1.26 g "));
+        assert!(lines[10].contains("│       10 g  O2   0.3125 mol  10 g "));
+        assert!(lines[11].contains("│          g  H2O   0.625 mol  11.26 g "));
+        assert!(lines[12].contains("│ O2 runs out, 8.74 g H2 left "));
+        assert!(lines[13].contains("│ H2:O2 2:1, H2:H2O 1:1 "));
+        assert!(lines[14].contains("│ H2 is H 100% "));
+        assert!(lines[15].contains("└─────────────────────── tab g/mol ────────────────────────┘"));
+        let buffer = draw(&app);
+        assert!(buffer[(20, 9)].modifier.contains(Modifier::REVERSED));
+        assert!(!buffer[(20, 10)].modifier.contains(Modifier::REVERSED));
+
+        // the ratios and composition follow the chosen compound
+        app.stoich_selected = 2;
+        let lines = render(&app, 80, 24);
+        assert!(lines[13].contains("│ H2O:H2 1:1, H2O:O2 2:1 "));
+        assert!(lines[14].contains("│ H2O is H 11.19%, O 88.81% "));
+        app.stoich_selected = 0;
+
+        // just the formulas until there's an amount
+        app.stoich_inputs = vec![(String::new(), Unit::Grams); 3];
+        let lines = render(&app, 80, 24);
+        assert!(lines[10].contains("│          g  O2 "));
+        assert!(lines[12].contains("│ type an amount of any compound "));
+
+        app.stoich_inputs[1] = ("0".to_string(), Unit::Moles);
+        let lines = render(&app, 80, 24);
+        assert!(lines[12].contains("│ 0 isn't an amount, it has to be above 0 "));
+
+        // a product typed with the reactants is how much was made
+        app.stoich_inputs = vec![
+            ("10".to_string(), Unit::Grams),
+            ("10".to_string(), Unit::Grams),
+            ("9".to_string(), Unit::Grams),
+        ];
+        let lines = render(&app, 80, 24);
+        assert!(lines[11].contains("│        9 g  H2O   0.625 mol  11.26 g "));
+        assert!(lines[12].contains("│ O2 runs out, 8.74 g H2 left, 79.93% yield of 11.26 g "));
+    }
+
+    #[test]
+    fn kc_snapshot() {
+        let mut app = App::default();
+        app.set_input("N2 + H2 <-> NH3");
+        let lines = render(&app, 80, 24);
+        assert!(lines[6].contains("│ N2 + 3H2 <-> 2NH3 "));
+        assert!(lines[7].contains("│ Kc = [NH3]^2 / ([N2][H2]^3) "));
+
+        app.set_input("N2 + H2 -> NH3");
+        let lines = render(&app, 80, 24);
+        assert!(!lines.join("").contains("Kc"));
+    }
+
+    #[test]
+    fn fractional_output() {
+        let mut app = App {
+            fractional: true,
+            ..Default::default()
+        };
+        app.set_input("C2H6 + O2 -> CO2 + H2O");
+        let lines = render(&app, 80, 24);
+        assert!(lines[6].contains("│ C2H6 + 7/2 O2 -> 2CO2 + 3H2O "));
+    }
+
+    #[test]
+    fn net_ionic_snapshot() {
+        let mut app = App {
+            show_net_ionic: true,
+            ..Default::default()
+        };
+        app.set_input("AgNO3(aq) + NaCl(aq) -> AgCl(s) + NaNO3(aq)");
+        let lines = render(&app, 80, 24);
+        assert!(lines[7].contains("│ Net ionic: Ag⁺(aq) + Cl⁻(aq) -> AgCl(s) "));
+
+        app.set_input("NaCl + KNO3 -> NaNO3 + KCl");
+        let lines = render(&app, 80, 24);
+        assert!(lines[7].contains("│ No net reaction "));
+    }
+
+    #[test]
+    fn electrons_snapshot() {
+        let mut app = App::default();
+        app.set_input("MnO4^- + H^+ + e^- -> Mn^2+ + H2O");
+        let lines = render(&app, 80, 24);
+        assert!(
+            lines[6].contains("│ MnO4⁻ + 8H⁺ + 5e⁻ -> Mn²⁺ + 4H2O "),
+            "{lines:#?}"
+        );
+        assert!(lines[7].contains("└ half-reaction ─"));
+    }
+
+    #[test]
+    fn verify_snapshot() {
+        let mut app = App::default();
+        app.set_input("4H2 + 2O2 -> 4H2O");
+        let lines = render(&app, 80, 24);
+        assert!(lines[6].contains("│ 2H2 + O2 -> 2H2O "));
+        assert!(lines[7].contains(
+            "│ As typed: balanced, but not in lowest terms, simplest form is 2H2 + O2 ->"
+        ));
+
+        app.set_input("2H2 + 2O2 -> 2H2O");
+        let lines = render(&app, 80, 24);
+        assert!(lines[7].contains("│ As typed: unbalanced, O is 4 on the left but 2 on the right "));
+
+        // nothing to check without coefficients
+        app.set_input("H2 + O2 -> H2O");
+        let lines = render(&app, 80, 24);
+        assert!(!screen(&draw(&app)).contains("As typed"), "{lines:#?}");
+    }
+
+    #[test]
+    fn redox_snapshot() {
+        let mut app = App::default();
+        app.toggle_redox();
+        app.set_input("MnO4^- + Fe^2+ -> Mn^2+ + Fe^3+");
+        let lines = render(&app, 80, 24);
+        assert!(lines[5].contains("┌ Redox, acidic ─"));
+        assert!(lines[6].contains("│ MnO4⁻ + 8H⁺ + 5Fe²⁺ -> Mn²⁺ + 4H2O + 5Fe³⁺"));
+        assert!(lines[7].contains("│ Oxidation: Fe²⁺ -> Fe³⁺ + e⁻"));
+        assert!(lines[8].contains("│ Reduction: MnO4⁻ + 8H⁺ + 5e⁻ -> Mn²⁺ + 4H2O"));
+
+        // not a redox reaction
+        app.set_input("NaCl -> Na + Cl2");
+        let lines = render(&app, 80, 24);
+        assert!(lines[6].contains("Not a redox reaction, expected two half-reactions but found 1"));
+        assert!(!lines[7].contains("Oxidation"));
+    }
+
+    #[test]
+    fn oxidation_snapshot() {
+        let mut app = App {
+            show_oxidation: true,
+            ..Default::default()
+        };
+        app.set_input("Cu + Ag^+ -> Cu^2+ + Ag");
+        let buffer = draw_sized(&app, 80, 26);
+        let text = screen(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[6].contains("│ Cu + 2Ag⁺ -> Cu²⁺ + 2Ag"));
+        assert!(lines[7].contains("│ Oxidizing agent: Ag⁺, reducing agent: Cu"));
+        assert!(lines[8].contains("│ Cu: Cu 0"));
+        assert!(lines[9].contains("│ Ag⁺: Ag +1"));
+        assert!(lines[12].contains("│ Oxidized: Cu 0 to +2, Cu to Cu²⁺"));
+        assert!(lines[13].contains("│ Reduced: Ag +1 to 0, Ag⁺ to Ag"));
+        // the states that change stand out
+        let column = lines[9][..lines[9].find(" +1").unwrap()].chars().count() as u16 + 1;
+        assert_eq!(buffer[(column, 9)].fg, Color::Yellow);
+        assert_eq!(buffer[(column - 6, 9)].fg, Color::DarkGray);
+
+        // hidden by default, but the agents aren't
+        let lines = render(&app_with("Cu + Ag^+ -> Cu^2+ + Ag"), 80, 24);
+        assert!(lines[7].contains("Oxidizing agent"));
+        assert!(!lines[8].contains("Cu: Cu 0"));
+
+        // and there are none when it isn't redox
+        let lines = render(&app_with("NaOH + HCl -> NaCl + H2O"), 80, 24);
+        assert!(!lines.join("").contains("agent"));
+    }
+
+    #[test]
+    fn quiz_snapshot() {
+        let mut app = App::default();
+        app.toggle_quiz();
+        let quiz = app.quiz.as_mut().unwrap();
+        quiz.right = 2;
+        quiz.finished = 3;
+        let name = quiz.example.name.to_lowercase();
+        let answer = quiz.answer.clone();
+        // some examples are written balanced, so the answer is the question
+        let hidden = answer != quiz.example.equation;
+        let lines = render(&app, 100, 24);
+        assert!(lines[5].contains("┌ Quiz ─"));
+        assert!(lines[6].contains(&format!("│ Balance the {name}")));
+        assert!(!hidden || !lines.iter().any(|l| l.contains(&answer)));
+        assert!(lines[16].ends_with("score 2/3  "));
+
+        app.quiz.as_mut().unwrap().reveal();
+        let lines = render(&app, 100, 24);
+        assert!(lines[6].contains(&format!("│ Answer: {answer}")));
+    }
+
+    #[test]
+    fn status_snapshot() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        app.status = Some(Status::Info("Copied with OSC 52".to_string()));
+        let buffer = draw(&app);
+        assert_eq!(
+            screen(&buffer).lines().nth(15),
+            Some("  Copied with OSC 52                               2 → 1 compounds, 2 elements")
+        );
+        assert_eq!(buffer[(2, 15)].fg, Color::DarkGray);
+        assert_eq!(buffer[(52, 15)].fg, Color::Reset);
+
+        app.status = Some(Status::Error("Couldn't copy".to_string()));
+        let buffer = draw(&app);
+        assert_eq!(buffer[(2, 15)].fg, Color::Red);
+    }
+
+    #[test]
+    fn stale_counts() {
+        let mut app = app_with("Fe + O2 -> Fe2O3");
+        // the counts from before it stopped parsing stay, greyed out
+        app.input = "Fe + O2 -> Fe2O3 + (".to_string();
+        app.update_eq();
+        assert!(app.counts_stale);
+        let buffer = draw(&app);
+        let row = screen(&buffer)
+            .lines()
+            .position(|l| l.ends_with("2 → 1 compounds, 2 elements"))
+            .unwrap();
+        assert_eq!(buffer[(52, row as u16)].fg, Color::DarkGray);
+
+        app.input = "Fe + O2 -> Fe2O3 + Fe3O4".to_string();
+        app.update_eq();
+        assert_eq!(app.counts.unwrap().products, 2);
+        assert!(!app.counts_stale);
+        app.input.clear();
+        app.update_eq();
+        assert_eq!(app.counts, None);
+    }
+}
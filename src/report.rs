@@ -0,0 +1,157 @@
+use crate::{
+    batch::BatchResult,
+    chem,
+    format::{copy_text, markdown_escape, plain_equation, CopyFormat},
+    stoich::{self, Yield},
+};
+
+/// Where the tui writes its report, in the directory it was started in
+pub const DEFAULT_PATH: &str = "report.md";
+
+/// An equation worked on, as it goes in a report
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub result: BatchResult,
+    /// How much of each compound reacts or is made, when amounts were given
+    pub amounts: Option<Yield>,
+}
+
+impl Entry {
+    pub fn new(result: BatchResult) -> Self {
+        Self {
+            result,
+            amounts: None,
+        }
+    }
+}
+
+/// Write a markdown report with a section for each equation: what was typed,
+/// the balanced equation as text and LaTeX, the molar mass of each compound
+/// and the amounts, if there are any. Empty inputs are left out
+pub fn markdown(entries: &[Entry]) -> String {
+    let mut out = String::from("# Chemical equations\n");
+    let entries = entries.iter().filter(|e| !e.result.input.trim().is_empty());
+    for (i, entry) in entries.enumerate() {
+        let input = entry.result.input.trim();
+        out.push_str(&format!("\n## {}. {}\n\n", i + 1, markdown_escape(input)));
+        let eq = match &entry.result.output {
+            Ok(eq) => eq,
+            Err(e) => {
+                out.push_str(&format!("Couldn't balance it: {e}\n"));
+                continue;
+            }
+        };
+        out.push_str(&format!(
+            "Balanced: `{}`\n\n$$\n{}\n$$\n",
+            plain_equation(input, eq, false),
+            copy_text(CopyFormat::Latex, input, eq, false)
+        ));
+
+        let masses: Vec<(String, f64)> = chem::typed_formulas(input, eq)
+            .into_iter()
+            .zip(eq.iter_compounds())
+            .filter(|(_, c)| !chem::is_electron(c))
+            .map(|(formula, c)| (formula, chem::molar_mass(c)))
+            .collect();
+        if !masses.is_empty() {
+            out.push_str("\n| Compound | Molar mass |\n| --- | --- |\n");
+            for (formula, mass) in masses {
+                out.push_str(&format!(
+                    "| {} | {} g/mol |\n",
+                    markdown_escape(&formula),
+                    stoich::round(mass)
+                ));
+            }
+        }
+
+        if let Some(y) = &entry.amounts {
+            out.push_str("\n| Compound | Moles | Grams |\n| --- | --- | --- |\n");
+            for a in &y.amounts {
+                let grams = a.grams.map(stoich::round).unwrap_or_default();
+                out.push_str(&format!(
+                    "| {} | {} | {grams} |\n",
+                    markdown_escape(&a.formula),
+                    stoich::round(a.moles)
+                ));
+            }
+            if let Some(limiting) = y.limiting {
+                out.push_str(&format!(
+                    "\nLimiting reagent: {}\n",
+                    markdown_escape(&y.amounts[limiting].formula)
+                ));
+            }
+            for a in &y.excess {
+                out.push_str(&format!(
+                    "\n{} left over: {} mol\n",
+                    markdown_escape(&a.formula),
+                    stoich::round(a.moles)
+                ));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stoich::{Given, Unit};
+
+    #[test]
+    fn structure() {
+        let water = BatchResult::new("H2 + O2 -> H2O");
+        let y = match &water.output {
+            Ok(eq) => stoich::limiting(
+                &water.input,
+                eq,
+                &[Given {
+                    formula: "H2".to_string(),
+                    amount: 4.0,
+                    unit: Unit::Grams,
+                }],
+            )
+            .unwrap(),
+            Err(e) => panic!("{e}"),
+        };
+        let entries = [
+            Entry {
+                result: water,
+                amounts: Some(y),
+            },
+            Entry::new(BatchResult::new("  ")),
+            Entry::new(BatchResult::new("H2O -> H2O2")),
+        ];
+        let report = markdown(&entries);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "# Chemical equations");
+        assert_eq!(lines[2], "## 1. H2 + O2 -> H2O");
+        assert_eq!(lines[4], "Balanced: `2H2 + O2 -> 2H2O`");
+        assert_eq!(
+            lines[6..9],
+            [
+                "$$",
+                r"2\mathrm{H_{2}} + \mathrm{O_{2}} \rightarrow 2\mathrm{H_{2}O}",
+                "$$"
+            ]
+        );
+        assert!(report.contains("| H2O | 18.02 g/mol |\n"), "{report}");
+        assert!(report.contains("| Compound | Moles | Grams |\n"));
+        assert!(report.contains("| H2O | 1.984 | 35.74 |\n"), "{report}");
+        // the empty one isn't numbered
+        assert!(report.contains("## 2. H2O -> H2O2\n\nCouldn't balance it: "));
+        assert!(!report.contains("## 3."));
+    }
+
+    #[test]
+    fn electrons() {
+        let report = markdown(&[Entry::new(BatchResult::new("Cu -> Cu^2+ + e-"))]);
+        assert!(report.contains("| Cu^2+ |"), "{report}");
+        assert!(!report.contains("| e"), "{report}");
+    }
+
+    #[test]
+    fn escaped_heading() {
+        let report = markdown(&[Entry::new(BatchResult::new("H2 + `O2` -> H2O"))]);
+        assert!(report.contains("## 1. H2 + \\`O2\\` -> H2O\n"), "{report}");
+    }
+}
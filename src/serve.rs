@@ -0,0 +1,54 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    batch::BatchResult,
+    format::{self, Format},
+    redox::Medium,
+};
+
+/// Balance each line read from `reader` and write what happened as one line
+/// of json to `writer` straight away, until there's nothing left to read.
+/// Every line gets an answer, even blank ones, so a client can match them up
+pub fn serve(
+    reader: impl BufRead,
+    mut writer: impl Write,
+    redox: Option<Medium>,
+) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        let input = line.trim();
+        let res = match redox {
+            Some(medium) => BatchResult::redox(input, medium),
+            None => BatchResult::new(input),
+        };
+        writer.write_all(format::format_results(Format::Json, &[res]).as_bytes())?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serve_str(input: &str) -> String {
+        let mut out = Vec::new();
+        serve(input.as_bytes(), &mut out, None).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn one_line_each() {
+        let out = serve_str("H2 + O2 -> H2O\nFe + S8 -> Fe2O3\r\n\nFe + O2 -> Fe2O3");
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains(r#""equation":"2H2 + O2 -> 2H2O""#));
+        // errors don't stop the rest from being balanced
+        assert_eq!(
+            lines[1],
+            r#"{"input":"Fe + S8 -> Fe2O3","error":"Equation is not valid"}"#
+        );
+        assert!(lines[2].starts_with(r#"{"input":"","error":"#));
+        assert!(lines[3].contains(r#""equation":"4Fe + 3O2 -> 2Fe2O3""#));
+    }
+}
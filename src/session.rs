@@ -0,0 +1,154 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::redox::Medium;
+
+/// Changed whenever what's saved changes, a session saved with any other
+/// version is ignored
+pub const VERSION: u32 = 1;
+
+/// Everything open in the tui, saved with ctrl-s to pick up where it was left
+/// off. The outputs aren't saved, each tab is balanced again when it's shown
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub tabs: Vec<SavedTab>,
+    pub active_tab: usize,
+    /// Equations entered, oldest first
+    pub history: Vec<String>,
+    pub toggles: Toggles,
+}
+
+/// The equation typed in a tab
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedTab {
+    pub input: String,
+    /// Position of the cursor, counted in characters
+    pub cursor: usize,
+}
+
+/// How the output is shown, as switched by the keys in normal mode
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Toggles {
+    pub show_help: bool,
+    pub side_by_side: bool,
+    pub explicit_ones: bool,
+    pub show_oxidation: bool,
+    pub show_net_ionic: bool,
+    pub fractional: bool,
+    pub redox: Option<Medium>,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Saved by a version that saves something else, with its version
+    Version(u64),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Json(e) => write!(f, "{e}"),
+            Self::Version(v) => write!(f, "it's version {v}, expected version {VERSION}"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl Session {
+    /// Read the session saved at `path`. `None` if there isn't one
+    pub fn load(path: &Path) -> Result<Option<Self>, SessionError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SessionError::Io(e)),
+        }
+    }
+
+    /// Read a saved session, checking its version before the rest as that
+    /// may be laid out differently
+    pub fn parse(text: &str) -> Result<Self, SessionError> {
+        let value: serde_json::Value = serde_json::from_str(text).map_err(SessionError::Json)?;
+        match value["version"].as_u64() {
+            Some(v) if v == u64::from(VERSION) => {}
+            v => return Err(SessionError::Version(v.unwrap_or(0))),
+        }
+        serde_json::from_value(value).map_err(SessionError::Json)
+    }
+
+    /// Write the session to `path` as json
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string_pretty(self).expect("sessions are always serializable");
+        fs::write(path, text)
+    }
+
+    /// Where the session is saved, eg. `~/.local/share/balance-tui/session.json`
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("balance-tui").join("session.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session {
+            version: VERSION,
+            tabs: vec![SavedTab {
+                input: "H2 + O2 -> H2O".to_string(),
+                cursor: 3,
+            }],
+            active_tab: 0,
+            history: vec!["Fe + O2 -> Fe2O3".to_string()],
+            toggles: Toggles {
+                redox: Some(Medium::Basic),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("balance-tui-session-{}", std::process::id()));
+        let path = dir.join("session.json");
+        assert!(Session::load(&path).unwrap().is_none());
+
+        session().save(&path).unwrap();
+        assert_eq!(Session::load(&path).unwrap(), Some(session()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ignored() {
+        assert!(matches!(
+            Session::parse("{\"version\": 1, \"tabs\""),
+            Err(SessionError::Json(_))
+        ));
+        let other = serde_json::to_string(&Session {
+            version: VERSION + 1,
+            ..session()
+        })
+        .unwrap();
+        let e = Session::parse(&other).unwrap_err();
+        assert_eq!(e.to_string(), "it's version 2, expected version 1");
+        // not even the version is where it should be
+        assert!(matches!(
+            Session::parse("[]"),
+            Err(SessionError::Version(0))
+        ));
+    }
+}
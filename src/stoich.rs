@@ -0,0 +1,797 @@
+use std::{fmt, ops::Range, str::FromStr};
+
+use chem_eq::{Compound, Direction, Equation};
+
+use crate::{chem, elements::Info, matrix::gcd, normalize::normalize};
+
+/// What a known amount is measured in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Unit {
+    #[default]
+    Grams,
+    Moles,
+}
+
+impl Unit {
+    /// The other unit, to switch between them
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Grams => Self::Moles,
+            Self::Moles => Self::Grams,
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "g" => Ok(Self::Grams),
+            "mol" => Ok(Self::Moles),
+            _ => Err(format!("unknown unit {s:?}, expected g or mol")),
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Grams => write!(f, "g"),
+            Self::Moles => write!(f, "mol"),
+        }
+    }
+}
+
+/// How much of one compound there is, eg. `H2=4g` or `O2=0.5mol`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Given {
+    pub formula: String,
+    pub amount: f64,
+    pub unit: Unit,
+}
+
+impl FromStr for Given {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((formula, amount)) = s.split_once('=') else {
+            return Err(format!("expected COMPOUND=AMOUNT like H2=4g, found {s:?}"));
+        };
+        let amount = amount.trim();
+        let split = amount
+            .find(|c: char| c.is_alphabetic())
+            .ok_or_else(|| format!("{amount:?} needs a unit, g or mol"))?;
+        let (number, unit) = amount.split_at(split);
+        let number: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} isn't a number", number.trim()))?;
+        Ok(Self {
+            formula: formula.trim().to_string(),
+            amount: number,
+            unit: unit.parse()?,
+        })
+    }
+}
+
+/// Several amounts given together, separated by commas, eg. `H2=10g,O2=10g`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Givens(pub Vec<Given>);
+
+impl FromStr for Givens {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Two compounds to compare, eg. `H2:H2O`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pair {
+    pub first: String,
+    pub second: String,
+}
+
+impl FromStr for Pair {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((first, second)) if !first.trim().is_empty() && !second.trim().is_empty() => {
+                Ok(Self {
+                    first: first.trim().to_string(),
+                    second: second.trim().to_string(),
+                })
+            }
+            _ => Err(format!("expected two compounds like H2:H2O, found {s:?}")),
+        }
+    }
+}
+
+/// An amount of one compound to convert to the other unit, eg. `18 g H2O`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub amount: f64,
+    pub unit: Unit,
+    pub formula: String,
+}
+
+impl FromStr for Conversion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| c.is_alphabetic())
+            .ok_or_else(|| format!("expected AMOUNT UNIT COMPOUND like 18 g H2O, found {s:?}"))?;
+        let (number, rest) = s.split_at(split);
+        let amount: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} isn't a number", number.trim()))?;
+        let (unit, formula) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let formula = formula.trim();
+        if formula.is_empty() {
+            return Err(format!("{s:?} needs a compound, like 18 g H2O"));
+        }
+        Ok(Self {
+            amount,
+            unit: unit.parse()?,
+            formula: formula.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Conversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", round(self.amount), self.unit, self.formula)
+    }
+}
+
+/// A known amount of the compound at `index` in an equation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Known {
+    pub index: usize,
+    pub amount: f64,
+    pub unit: Unit,
+}
+
+/// How much of a compound takes part, in both units
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amount {
+    pub formula: String,
+    pub moles: f64,
+    /// `None` when the compound's molar mass isn't known
+    pub grams: Option<f64>,
+}
+
+impl Amount {
+    fn new(formula: String, moles: f64, cmp: &Compound) -> Self {
+        Self {
+            formula,
+            moles,
+            grams: molar_mass(cmp).map(|m| moles * m),
+        }
+    }
+}
+
+/// What happens when the known amounts react, using up the one there's least
+/// of by the coefficients
+#[derive(Debug, Clone, PartialEq)]
+pub struct Yield {
+    /// How much of every compound reacts or is made
+    pub amounts: Vec<Amount>,
+    /// The index of the reactant that runs out first. `None` with just one
+    /// amount, or when they're exactly in the ratio of the coefficients
+    pub limiting: Option<usize>,
+    /// What's left of each reactant there was too much of
+    pub excess: Vec<Amount>,
+    /// How many amounts were given
+    pub known: usize,
+}
+
+/// How much of a product was made against how much the reactants could have made
+#[derive(Debug, Clone, PartialEq)]
+pub struct PercentYield {
+    pub formula: String,
+    /// The most there could be of the product from the limiting reactant, in `unit`
+    pub theoretical: f64,
+    /// How much was measured, in `unit`
+    pub actual: f64,
+    pub unit: Unit,
+    pub percent: f64,
+}
+
+/// The total mass of each side of an equation, in grams for the coefficients in moles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Masses {
+    pub left: f64,
+    pub right: f64,
+}
+
+impl Masses {
+    /// Both sides weigh the same, give or take rounding
+    pub fn conserved(&self) -> bool {
+        (self.left - self.right).abs() <= self.left.abs().max(self.right.abs()) * 1e-9
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoichError {
+    /// The coefficients don't balance, so they can't be used as ratios
+    NotBalanced,
+    /// The compound the amount was given for isn't in the equation
+    UnknownCompound(String),
+    /// Amounts have to be positive and finite
+    InvalidAmount(f64),
+    /// Grams were given for a compound with no molar mass to turn them into moles
+    NoMolarMass(String),
+    /// Amounts of a product were given with others, which can't limit anything
+    NotReactant(String),
+    /// The compound to convert an amount of isn't a formula
+    InvalidFormula(String),
+    /// The actual yield was given for a reactant
+    NotProduct(String),
+    /// No amount of a reactant was given to work out a yield from
+    NoReactants,
+}
+
+impl fmt::Display for StoichError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotBalanced => write!(f, "the equation has to be balanced first"),
+            Self::UnknownCompound(c) => write!(f, "{c} isn't in the equation"),
+            Self::InvalidAmount(n) => write!(f, "{n} isn't an amount, it has to be above 0"),
+            Self::NoMolarMass(c) => write!(f, "{c} has no molar mass, give it in mol"),
+            Self::NotReactant(c) => write!(
+                f,
+                "{c} is a product, only reactants can be given with other amounts"
+            ),
+            Self::InvalidFormula(c) => write!(f, "couldn't read {c:?} as a formula"),
+            Self::NotProduct(c) => write!(f, "{c} is a reactant, the yield has to be of a product"),
+            Self::NoReactants => write!(f, "give an amount of a reactant to work out the yield"),
+        }
+    }
+}
+
+impl std::error::Error for StoichError {}
+
+/// Turn grams of a compound into moles or moles into grams, by its molar mass.
+/// The result is in the other unit to the one given
+pub fn convert(conversion: &Conversion) -> Result<f64, StoichError> {
+    let Conversion {
+        amount,
+        unit,
+        formula,
+    } = conversion;
+    if !amount.is_finite() || *amount <= 0.0 {
+        return Err(StoichError::InvalidAmount(*amount));
+    }
+    let cmp = Compound::parse(&normalize(formula))
+        .map_err(|_| StoichError::InvalidFormula(formula.clone()))?;
+    let mass = molar_mass(&cmp).ok_or_else(|| StoichError::NoMolarMass(formula.clone()))?;
+    Ok(match unit {
+        Unit::Grams => amount / mass,
+        Unit::Moles => amount * mass,
+    })
+}
+
+/// How much of every compound in a balanced equation reacts or is made when
+/// there's `given` of one of them, by the ratio of their coefficients. The
+/// compound is found by its formula as typed in `input` or as chem-eq reads it
+pub fn amounts(input: &str, eq: &Equation, given: &Given) -> Result<Vec<Amount>, StoichError> {
+    limiting(input, eq, std::slice::from_ref(given)).map(|y| y.amounts)
+}
+
+/// Which of the `givens` runs out first, and how much of everything reacts and
+/// is left over when it does. Compounds are found like in [`amounts`]
+pub fn limiting(input: &str, eq: &Equation, givens: &[Given]) -> Result<Yield, StoichError> {
+    if !eq.is_balanced() {
+        return Err(StoichError::NotBalanced);
+    }
+    let typed = chem::typed_formulas(input, eq);
+    let known = givens
+        .iter()
+        .map(|given| {
+            Ok(Known {
+                index: find(&typed, eq, &given.formula)?,
+                amount: given.amount,
+                unit: given.unit,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    react(eq, typed, &known)
+}
+
+/// The theoretical and percent yield of a product when `givens` react and
+/// `actual` of it is measured. Compounds are found like in [`amounts`]
+pub fn percent_yield(
+    input: &str,
+    eq: &Equation,
+    givens: &[Given],
+    actual: &Given,
+) -> Result<PercentYield, StoichError> {
+    let y = limiting(input, eq, givens)?;
+    let typed = chem::typed_formulas(input, eq);
+    let index = find(&typed, eq, &actual.formula)?;
+    yield_of(eq, &y, index, actual.amount, actual.unit)
+}
+
+/// Like [`percent_yield`], with what reacted already worked out and the
+/// product given by index
+pub fn yield_of(
+    eq: &Equation,
+    y: &Yield,
+    index: usize,
+    actual: f64,
+    unit: Unit,
+) -> Result<PercentYield, StoichError> {
+    // nothing being made is a yield too
+    if !actual.is_finite() || actual < 0.0 {
+        return Err(StoichError::InvalidAmount(actual));
+    }
+    let Some(made) = y.amounts.get(index) else {
+        return Err(StoichError::NoReactants);
+    };
+    if reactants(eq).contains(&index) {
+        return Err(StoichError::NotProduct(made.formula.clone()));
+    }
+    let theoretical = match unit {
+        Unit::Moles => made.moles,
+        Unit::Grams => made
+            .grams
+            .ok_or_else(|| StoichError::NoMolarMass(made.formula.clone()))?,
+    };
+    if theoretical <= 0.0 {
+        return Err(StoichError::NoReactants);
+    }
+    Ok(PercentYield {
+        formula: made.formula.clone(),
+        theoretical,
+        actual,
+        unit,
+        percent: actual / theoretical * 100.0,
+    })
+}
+
+/// Add up the molar mass of each compound times its coefficient on both sides.
+/// They're the same when the equation is balanced, since mass is conserved
+pub fn masses(eq: &Equation) -> Masses {
+    let side = |compounds: &[Compound]| -> f64 {
+        compounds
+            .iter()
+            .map(|c| c.coefficient as f64 * chem::molar_mass(c))
+            .sum()
+    };
+    Masses {
+        left: side(eq.left()),
+        right: side(eq.right()),
+    }
+}
+
+/// The indexes of the reactants of an equation, on the right when it's written backwards
+pub fn reactants(eq: &Equation) -> Range<usize> {
+    match eq.direction() {
+        Direction::Left => eq.left().len()..eq.num_compounds(),
+        _ => 0..eq.left().len(),
+    }
+}
+
+/// The index of a compound in an equation, by its formula as typed or as
+/// chem-eq reads it
+pub fn find(typed: &[String], eq: &Equation, formula: &str) -> Result<usize, StoichError> {
+    let normalized = normalize(formula);
+    typed
+        .iter()
+        .zip(chem::formulas(eq))
+        .position(|(t, f)| t == formula || f == normalized)
+        .ok_or_else(|| StoichError::UnknownCompound(formula.to_string()))
+}
+
+/// The simplest whole number ratio of moles of the two compounds in a
+/// balanced equation, found like in [`amounts`]
+pub fn ratio(input: &str, eq: &Equation, pair: &Pair) -> Result<(usize, usize), StoichError> {
+    if !eq.is_balanced() {
+        return Err(StoichError::NotBalanced);
+    }
+    let typed = chem::typed_formulas(input, eq);
+    let first = find(&typed, eq, &pair.first)?;
+    let second = find(&typed, eq, &pair.second)?;
+    Ok(mole_ratio(eq, first, second))
+}
+
+/// Like [`ratio`], for the compounds at `first` and `second` by index
+pub fn mole_ratio(eq: &Equation, first: usize, second: usize) -> (usize, usize) {
+    let coefficients: Vec<usize> = eq.iter_compounds().map(|c| c.coefficient).collect();
+    let (a, b) = (coefficients[first], coefficients[second]);
+    let divisor = gcd(a as i128, b as i128).max(1) as usize;
+    (a / divisor, b / divisor)
+}
+
+/// Each element's share of a compound's mass in percent, in the order they're
+/// first written. `None` when there's no molar mass to share out
+pub fn composition(cmp: &Compound) -> Option<Vec<(&'static str, f64)>> {
+    let total = molar_mass(cmp)?;
+    let mut parts: Vec<(&'static str, f64)> = Vec::new();
+    for el in &cmp.elements {
+        let mass = Info::from(**el).mass * el.count as f64 / total * 100.0;
+        match parts.iter_mut().find(|(symbol, _)| *symbol == el.symbol()) {
+            Some((_, share)) => *share += mass,
+            None => parts.push((el.symbol(), mass)),
+        }
+    }
+    Some(parts)
+}
+
+/// Like [`limiting`], with the compounds of a balanced equation given by index
+/// and `formulas` to call them by
+pub fn react(eq: &Equation, formulas: Vec<String>, known: &[Known]) -> Result<Yield, StoichError> {
+    let compounds: Vec<_> = eq.iter_compounds().collect();
+    let reactants = reactants(eq);
+    // moles of each known amount, and how many times over the reaction it's enough for
+    let mut runs = Vec::with_capacity(known.len());
+    for k in known {
+        let cmp = compounds[k.index];
+        if !k.amount.is_finite() || k.amount <= 0.0 {
+            return Err(StoichError::InvalidAmount(k.amount));
+        }
+        if known.len() > 1 && !reactants.contains(&k.index) {
+            return Err(StoichError::NotReactant(formulas[k.index].clone()));
+        }
+        let moles = match (k.unit, molar_mass(cmp)) {
+            (Unit::Moles, _) => k.amount,
+            (Unit::Grams, Some(mass)) => k.amount / mass,
+            (Unit::Grams, None) => return Err(StoichError::NoMolarMass(formulas[k.index].clone())),
+        };
+        runs.push((k.index, moles, moles / cmp.coefficient as f64));
+    }
+    let Some(&(first, _, times)) = runs.iter().min_by(|a, b| a.2.total_cmp(&b.2)) else {
+        return Ok(Yield {
+            amounts: Vec::new(),
+            limiting: None,
+            excess: Vec::new(),
+            known: 0,
+        });
+    };
+    // rounding can leave a little over when they're really in ratio
+    let exact = |t: f64| t - times <= times * 1e-9;
+    let limiting = (!runs.iter().all(|r| exact(r.2))).then_some(first);
+    let excess = runs
+        .iter()
+        .filter(|r| !exact(r.2))
+        .map(|&(index, moles, _)| {
+            let cmp = compounds[index];
+            let left = moles - times * cmp.coefficient as f64;
+            Amount::new(formulas[index].clone(), left, cmp)
+        })
+        .collect();
+    let amounts = compounds
+        .iter()
+        .zip(formulas)
+        .map(|(cmp, formula)| Amount::new(formula, times * cmp.coefficient as f64, cmp))
+        .collect();
+    Ok(Yield {
+        amounts,
+        limiting,
+        excess,
+        known: known.len(),
+    })
+}
+
+/// The molar mass of a compound, if all its elements have an atomic weight
+fn molar_mass(cmp: &Compound) -> Option<f64> {
+    let mass = chem::molar_mass(cmp);
+    (mass.is_finite() && mass > 0.0).then_some(mass)
+}
+
+/// Round to 4 significant figures, without trailing zeros, eg. `4.032` or
+/// `0.5`
+pub fn round(n: f64) -> String {
+    if n == 0.0 || !n.is_finite() {
+        return n.to_string();
+    }
+    let decimals = (3 - n.abs().log10().floor() as i32).clamp(0, 12) as usize;
+    let out = format!("{n:.decimals$}");
+    if out.contains('.') {
+        out.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        out
+    }
+}
+
+/// Write the amounts as a table, one compound to a line with its formulas,
+/// moles and grams lined up. Grams are left off when there's no molar mass
+pub fn table(amounts: &[Amount]) -> String {
+    let width = amounts
+        .iter()
+        .map(|a| a.formula.chars().count())
+        .max()
+        .unwrap_or(0);
+    let moles: Vec<String> = amounts.iter().map(|a| round(a.moles)).collect();
+    let moles_width = moles.iter().map(String::len).max().unwrap_or(0);
+    amounts
+        .iter()
+        .zip(&moles)
+        .map(|(a, moles)| {
+            let row = format!("{:<width$}  {moles:>moles_width$} mol", a.formula);
+            match a.grams {
+                Some(grams) => format!("{row}  {} g\n", round(grams)),
+                None => format!("{row}\n"),
+            }
+        })
+        .collect()
+}
+
+/// Write a percent composition one element to a line, with the percents lined up
+pub fn composition_table(parts: &[(&str, f64)]) -> String {
+    let width = parts.iter().map(|(e, _)| e.len()).max().unwrap_or(0);
+    let shares: Vec<String> = parts.iter().map(|(_, share)| round(*share)).collect();
+    let share_width = shares.iter().map(String::len).max().unwrap_or(0);
+    parts
+        .iter()
+        .zip(&shares)
+        .map(|((e, _), share)| format!("{e:<width$}  {share:>share_width$}%\n"))
+        .collect()
+}
+
+/// Say which reactant limits the reaction, then how much of everything reacts
+/// or is made and what's left over
+pub fn report(y: &Yield) -> String {
+    let mut out = match y.limiting {
+        Some(index) => format!("limiting reagent: {}\n", y.amounts[index].formula),
+        None if y.known > 1 => "no limiting reagent, nothing is left over\n".to_string(),
+        None => String::new(),
+    };
+    out.push_str(&table(&y.amounts));
+    for a in &y.excess {
+        let grams = a
+            .grams
+            .map(|g| format!(", {} g", round(g)))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "{} left over: {} mol{grams}\n",
+            a.formula,
+            round(a.moles)
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::balance;
+
+    #[test]
+    fn given() {
+        assert_eq!(
+            "H2=4g".parse(),
+            Ok(Given {
+                formula: "H2".to_string(),
+                amount: 4.0,
+                unit: Unit::Grams
+            })
+        );
+        let given: Given = "Fe2O3 = 0.5 mol".parse().unwrap();
+        assert_eq!((given.amount, given.unit), (0.5, Unit::Moles));
+        assert!("H2".parse::<Given>().is_err());
+        assert!("H2=4".parse::<Given>().is_err());
+        assert!("H2=4kg".parse::<Given>().is_err());
+        assert!("H2=lots g".parse::<Given>().is_err());
+    }
+
+    #[test]
+    fn conversions() {
+        let grams: Conversion = "18 g H2O".parse().unwrap();
+        assert_eq!(grams.to_string(), "18 g H2O");
+        assert_eq!(round(convert(&grams).unwrap()), "0.9992");
+        let moles: Conversion = "0.5mol  Ca(OH)2".parse().unwrap();
+        assert_eq!((moles.amount, moles.unit), (0.5, Unit::Moles));
+        assert_eq!(round(convert(&moles).unwrap()), "37.05");
+
+        assert!("18 g".parse::<Conversion>().is_err());
+        assert!("18 kg H2O".parse::<Conversion>().is_err());
+        assert!("H2O".parse::<Conversion>().is_err());
+        assert_eq!(
+            convert(&"2 g Xy".parse().unwrap()),
+            Err(StoichError::InvalidFormula("Xy".to_string()))
+        );
+        assert_eq!(
+            convert(&"0 g H2O".parse().unwrap()),
+            Err(StoichError::InvalidAmount(0.0))
+        );
+    }
+
+    #[test]
+    fn water() {
+        let input = "H2 + O2 -> H2O";
+        let eq = balance(input).unwrap();
+        let amounts = amounts(input, &eq, &"H2=4g".parse().unwrap()).unwrap();
+        let rounded: Vec<_> = amounts
+            .iter()
+            .map(|a| (a.formula.as_str(), round(a.moles), round(a.grams.unwrap())))
+            .collect();
+        assert_eq!(
+            rounded,
+            [
+                ("H2", "1.984".into(), "4".into()),
+                ("O2", "0.9921".into(), "31.74".into()),
+                ("H2O", "1.984".into(), "35.74".into())
+            ]
+        );
+        assert_eq!(
+            table(&amounts),
+            "H2    1.984 mol  4 g\nO2   0.9921 mol  31.74 g\nH2O   1.984 mol  35.74 g\n"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let input = "H2 + O2 -> H2O";
+        let eq = balance(input).unwrap();
+        let given = "CO2=1mol".parse().unwrap();
+        assert_eq!(
+            amounts(input, &eq, &given),
+            Err(StoichError::UnknownCompound("CO2".to_string()))
+        );
+        let given = "H2=0g".parse().unwrap();
+        assert_eq!(
+            amounts(input, &eq, &given),
+            Err(StoichError::InvalidAmount(0.0))
+        );
+        // the coefficients as typed aren't a ratio that means anything
+        let unbalanced = Equation::new(input).unwrap();
+        let given = "H2=1mol".parse().unwrap();
+        assert_eq!(
+            amounts(input, &unbalanced, &given),
+            Err(StoichError::NotBalanced)
+        );
+    }
+
+    #[test]
+    fn limiting_reagent() {
+        let input = "H2 + O2 -> H2O";
+        let eq = balance(input).unwrap();
+        let givens: Givens = "H2=10g,O2=10g".parse().unwrap();
+        let y = limiting(input, &eq, &givens.0).unwrap();
+        assert_eq!(y.limiting, Some(1));
+        assert_eq!(
+            report(&y),
+            "limiting reagent: O2\n\
+             H2    0.625 mol  1.26 g\n\
+             O2   0.3125 mol  10 g\n\
+             H2O   0.625 mol  11.26 g\n\
+             H2 left over: 4.335 mol, 8.74 g\n"
+        );
+
+        // grams and moles together
+        let givens = ["H2=1mol".parse().unwrap(), "O2=32g".parse().unwrap()];
+        let y = limiting(input, &eq, &givens).unwrap();
+        assert_eq!(y.limiting, Some(0));
+        assert_eq!(round(y.amounts[2].moles), "1");
+        assert_eq!(y.excess.len(), 1);
+        assert_eq!(round(y.excess[0].moles), "0.5001");
+
+        // exactly in ratio
+        let givens: Givens = "H2=4mol,O2=2mol".parse().unwrap();
+        let y = limiting(input, &eq, &givens.0).unwrap();
+        assert_eq!((y.limiting, y.excess.len()), (None, 0));
+        assert!(report(&y).starts_with("no limiting reagent"));
+
+        let givens: Givens = "H2=4mol,H2O=2mol".parse().unwrap();
+        assert_eq!(
+            limiting(input, &eq, &givens.0),
+            Err(StoichError::NotReactant("H2O".to_string()))
+        );
+        // a reaction written backwards has its reactants on the right
+        let input = "H2O <- H2 + O2";
+        let eq = balance(input).unwrap();
+        let givens: Givens = "H2=4mol,O2=1mol".parse().unwrap();
+        let y = limiting(input, &eq, &givens.0).unwrap();
+        assert_eq!(y.amounts[y.limiting.unwrap()].formula, "O2");
+        assert!("H2=4g,O2".parse::<Givens>().is_err());
+    }
+
+    #[test]
+    fn percent_yields() {
+        let input = "H2 + O2 -> H2O";
+        let eq = balance(input).unwrap();
+        let givens: Givens = "H2=10g,O2=10g".parse().unwrap();
+        let p = percent_yield(input, &eq, &givens.0, &"H2O=9g".parse().unwrap()).unwrap();
+        assert_eq!(
+            (round(p.theoretical), p.unit),
+            ("11.26".to_string(), Unit::Grams)
+        );
+        assert_eq!(round(p.percent), "79.93");
+        // in moles, from one amount
+        let givens = ["O2=1mol".parse().unwrap()];
+        let p = percent_yield(input, &eq, &givens, &"H2O=1.5mol".parse().unwrap()).unwrap();
+        assert_eq!(
+            (round(p.theoretical), p.unit),
+            ("2".to_string(), Unit::Moles)
+        );
+        assert_eq!(round(p.percent), "75");
+
+        assert_eq!(
+            percent_yield(input, &eq, &givens, &"H2=1g".parse().unwrap()),
+            Err(StoichError::NotProduct("H2".to_string()))
+        );
+        assert_eq!(
+            percent_yield(input, &eq, &givens, &"H2O=-1g".parse().unwrap()),
+            Err(StoichError::InvalidAmount(-1.0))
+        );
+        let y = react(&eq, chem::typed_formulas(input, &eq), &[]).unwrap();
+        assert_eq!(
+            yield_of(&eq, &y, 2, 1.0, Unit::Grams),
+            Err(StoichError::NoReactants)
+        );
+    }
+
+    #[test]
+    fn conservation() {
+        let eq = Equation::new(&normalize("2H2 + O2 = 2H2O")).unwrap();
+        let m = masses(&eq);
+        assert!(m.conserved());
+        assert_eq!(
+            (round(m.left), round(m.right)),
+            ("36.03".into(), "36.03".into())
+        );
+
+        let eq = Equation::new(&normalize("H2 + O2 -> H2O")).unwrap();
+        assert!(!masses(&eq).conserved());
+    }
+
+    #[test]
+    fn ratios() {
+        let input = "C3H8 + O2 -> CO2 + H2O";
+        let eq = balance(input).unwrap();
+        let pair = |s: &str| s.parse::<Pair>().unwrap();
+        assert_eq!(ratio(input, &eq, &pair("C3H8:O2")), Ok((1, 5)));
+        assert_eq!(ratio(input, &eq, &pair("CO2 : H2O")), Ok((3, 4)));
+        assert_eq!(ratio(input, &eq, &pair("O2:O2")), Ok((1, 1)));
+        assert_eq!(
+            ratio(input, &eq, &pair("C3H8:CH4")),
+            Err(StoichError::UnknownCompound("CH4".to_string()))
+        );
+        // 6:6 is 1:1
+        let input = "C6H12O6 + O2 -> CO2 + H2O";
+        let eq = balance(input).unwrap();
+        assert_eq!(ratio(input, &eq, &pair("CO2:H2O")), Ok((1, 1)));
+        assert!("H2".parse::<Pair>().is_err());
+        assert!("H2:".parse::<Pair>().is_err());
+    }
+
+    #[test]
+    fn percent_composition() {
+        let parts = |formula: &str| {
+            let cmp = Compound::parse(&normalize(formula)).unwrap();
+            composition(&cmp).unwrap()
+        };
+        assert_eq!(
+            composition_table(&parts("C6H12O6")),
+            "C     40%\nH  6.714%\nO  53.28%\n"
+        );
+        // each element once, however many times it's written
+        let acetic = parts("CH3COOH");
+        assert_eq!(
+            acetic.iter().map(|(e, _)| *e).collect::<Vec<_>>(),
+            ["C", "H", "O"]
+        );
+        assert!((acetic.iter().map(|(_, p)| p).sum::<f64>() - 100.0).abs() < 1e-9);
+        assert_eq!(composition_table(&parts("Fe")), "Fe  100%\n");
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!(round(4.0), "4");
+        assert_eq!(round(31.7504), "31.75");
+        assert_eq!(round(0.000123456), "0.0001235");
+        assert_eq!(round(12345.6), "12346");
+        assert_eq!(round(0.0), "0");
+    }
+}
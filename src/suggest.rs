@@ -0,0 +1,375 @@
+use chem_eq::{error::EquationError, Compound, Element, Equation};
+
+use crate::{
+    charge,
+    normalize::{normalize, tidy},
+};
+
+/// Elements most equations are made of. When the letters can be read more than
+/// one way, these are preferred, so `cuso4` is copper sulfate and not uranium
+const COMMON: &[&str] = &[
+    "H", "Li", "Be", "B", "C", "N", "O", "F", "Na", "Mg", "Al", "Si", "P", "S", "Cl", "K", "Ca",
+    "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Br", "Ag", "Sn", "I", "Ba", "Pt", "Au", "Hg", "Pb",
+];
+
+/// Formulas people type often, to compare one that doesn't parse against
+const KNOWN: &[&str] = &[
+    "H2", "O2", "N2", "Cl2", "H2O", "H2O2", "CO", "CO2", "CH4", "NH3", "HCl", "NaCl", "NaOH",
+    "KOH", "H2SO4", "HNO3", "CaO", "CaCO3", "Fe2O3", "Al2O3", "MgO", "SO2", "SO3", "NO2",
+];
+
+/// Formulas offered while typing, the most common first so they win when more
+/// than one starts the same way
+const COMPLETIONS: &[&str] = &[
+    "H2O", "H2O2", "HCl", "H2SO4", "HNO3", "H3PO4", "H2CO3", "HBr", "HF", "HI", "H2S", "CH3COOH",
+    "CO2", "CO", "CH4", "C2H6", "C3H8", "C4H10", "C8H18", "C2H5OH", "C6H12O6", "NaCl", "NaOH",
+    "NaHCO3", "Na2CO3", "Na2SO4", "NaNO3", "NH3", "NH4Cl", "NH4NO3", "NO2", "KOH", "KCl", "KNO3",
+    "KMnO4", "KClO3", "KI", "Ca(OH)2", "CaCO3", "CaCl2", "CaO", "CaSO4", "Mg(OH)2", "MgO", "MgCl2",
+    "MgSO4", "AgNO3", "AgCl", "BaCl2", "BaSO4", "CuSO4", "CuO", "Cu(NO3)2", "Fe2O3", "FeCl3",
+    "FeSO4", "Al2O3", "AlCl3", "ZnCl2", "ZnO", "Pb(NO3)2", "SO2", "SO3",
+];
+
+/// States written after a compound, which stay lowercase
+const STATES: &[&str] = &["(s)", "(l)", "(g)", "(aq)"];
+
+/// Guess what was meant by an equation with the wrong capitals, eg. `co2` or
+/// `NaCL`, by reading each formula's letters as element symbols. `None` when
+/// there's no other way to read it that parses
+pub fn suggest(input: &str) -> Option<String> {
+    let out = recase(input)?;
+    (out != input && Equation::new(&normalize(&out)).is_ok()).then_some(out)
+}
+
+/// Find each formula that doesn't parse, but would with different capitals,
+/// eg. `h2o` and `H2O`. Unlike [`suggest`], the rest of the equation doesn't
+/// have to parse, so each mistake can be pointed out on its own
+pub fn miscased(input: &str) -> Vec<(String, String)> {
+    // a reading with elements on only one side, like `NaCI` for `NaCi`, is a
+    // typo that happens to parse rather than the wrong capitals
+    let recased = recase(input).map(|r| Equation::new(&normalize(&r)));
+    if let Some(Err(EquationError::IncorrectEquation)) = recased {
+        return Vec::new();
+    }
+    charge::compounds(&tidy(input))
+        .into_iter()
+        .map(|c| c.trim_start_matches(|c: char| c.is_ascii_digit()))
+        .filter(|c| Compound::parse(&normalize(c)).is_err())
+        .filter_map(|c| {
+            let fixed = recase(c)?;
+            (fixed != c && Compound::parse(&normalize(&fixed)).is_ok())
+                .then(|| (c.to_string(), fixed))
+        })
+        .collect()
+}
+
+/// Fix the capitals of each formula that doesn't parse but would with them
+/// fixed, eg. `na` to `Na`, leaving everything else as it was typed. Only the
+/// case of letters changes, so the input stays the same length. Formulas are
+/// separated by spaces, the same as [`miscased`]
+pub fn capitalize(input: &str) -> String {
+    input
+        .split(' ')
+        .map(|token| {
+            let formula = token.trim_start_matches(|c: char| c.is_ascii_digit());
+            if formula.is_empty()
+                || matches!(formula, "+" | "=" | "->" | "<-" | "<->")
+                || Compound::parse(&normalize(formula)).is_ok()
+            {
+                return token.to_string();
+            }
+            match recase(formula) {
+                Some(fixed) if Compound::parse(&normalize(&fixed)).is_ok() => {
+                    token[..token.len() - formula.len()].to_string() + &fixed
+                }
+                _ => token.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The rest of a common formula starting with the one being typed at the end of
+/// `before`, eg. `O3` after `Cu + HN`. Single letters and formulas that are
+/// already common on their own, like `H2O` before `H2O2`, aren't completed
+pub fn complete(before: &str) -> Option<&'static str> {
+    let typed = before
+        .rsplit(|c: char| c.is_whitespace() || c == '+')
+        .next()?
+        .trim_start_matches(|c: char| c.is_ascii_digit());
+    if typed.chars().count() < 2
+        || !typed.starts_with(|c: char| c.is_ascii_uppercase())
+        || COMMON
+            .iter()
+            .chain(KNOWN)
+            .chain(COMPLETIONS)
+            .any(|f| *f == typed)
+    {
+        return None;
+    }
+    COMPLETIONS
+        .iter()
+        .find(|f| f.len() > typed.len() && f.starts_with(typed))
+        .map(|f| &f[typed.len()..])
+}
+
+/// Read every run of letters as element symbols, keeping states lowercase
+fn recase(input: &str) -> Option<String> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if let Some(state) = STATES.iter().find(|s| {
+            rest.get(..s.len())
+                .is_some_and(|r| r.eq_ignore_ascii_case(s))
+        }) {
+            out.push_str(state);
+            rest = &rest[state.len()..];
+        } else if c.is_ascii_alphabetic() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            out.push_str(&symbols(&rest[..end])?);
+            rest = &rest[end..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(out)
+}
+
+/// Find the first formula in an equation that doesn't parse and the well known
+/// symbol or formula it's closest to, eg. `na` and `Na`. Only close, unambiguous
+/// matches are given, as a wrong guess is worse than none
+pub fn closest_formula(input: &str) -> Option<(String, &'static str)> {
+    let normalized = normalize(input);
+    let typed = normalized
+        .split(|c: char| c.is_whitespace() || c == '+')
+        .filter(|t| !t.is_empty() && !matches!(*t, "->" | "<-" | "<->"))
+        .find(|t| Compound::parse(t).is_err())?
+        .trim_start_matches(|c: char| c.is_ascii_digit());
+    let lower = typed.to_lowercase();
+    // only differences of case for symbols, one more letter wrong for longer formulas
+    let threshold = usize::from(typed.chars().count() > 2);
+    let mut candidates = COMMON
+        .iter()
+        .chain(KNOWN)
+        .map(|known| (edit_distance(&lower, &known.to_lowercase()), *known))
+        .filter(|(d, _)| *d <= threshold)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(d, _)| *d);
+    match candidates.as_slice() {
+        [(d, known), rest @ ..] if rest.first().is_none_or(|(next, _)| next > d) => {
+            Some((typed.to_string(), *known))
+        }
+        _ => None,
+    }
+}
+
+/// Levenshtein distance, how many characters have to be added, removed or
+/// swapped to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb))
+                .min(above + 1)
+                .min(row[j] + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// How bad a reading of some letters is: letters that changed case, uncommon
+/// elements, then minus the number of symbols. Lower is better
+type Cost = (usize, usize, isize);
+
+/// Read a run of letters as element symbols. Changing the case of what was
+/// typed is avoided first, then uncommon elements, then fewer longer symbols,
+/// so `co` is carbon monoxide rather than cobalt. All lowercase letters can be
+/// raised freely, as they don't say anything about where symbols start
+fn symbols(letters: &str) -> Option<String> {
+    let mixed = letters.chars().any(|c| c.is_ascii_uppercase());
+    let letters: Vec<char> = letters.chars().collect();
+    // the best reading of each prefix
+    let mut best: Vec<Option<(Cost, String)>> = vec![None; letters.len() + 1];
+    best[0] = Some(((0, 0, 0), String::new()));
+    for start in 0..letters.len() {
+        let Some(((recased, rare, count), read)) = best[start].clone() else {
+            continue;
+        };
+        for len in 1..=2.min(letters.len() - start) {
+            let typed = &letters[start..start + len];
+            let symbol: String = typed[..1]
+                .iter()
+                .map(char::to_ascii_uppercase)
+                .chain(typed[1..].iter().map(char::to_ascii_lowercase))
+                .collect();
+            if Element::parse(&symbol).is_err() {
+                continue;
+            }
+            let changed = typed[1..].iter().filter(|c| c.is_ascii_uppercase()).count()
+                + usize::from(mixed && typed[0].is_ascii_lowercase());
+            let cost = (
+                recased + changed,
+                rare + usize::from(!COMMON.contains(&symbol.as_str())),
+                count - 1,
+            );
+            let end = start + len;
+            if best[end].as_ref().is_none_or(|(c, _)| cost < *c) {
+                best[end] = Some((cost, read.clone() + &symbol));
+            }
+        }
+    }
+    best.pop().flatten().map(|(_, read)| read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completions() {
+        assert_eq!(complete("Cu + HN"), Some("O3"));
+        assert_eq!(complete("H2 + O2 -> 2H2SO"), Some("4"));
+        assert_eq!(complete("NaH"), Some("CO3"));
+        assert_eq!(complete("Ca("), Some("OH)2"));
+        // too short, already complete, or nothing like it
+        assert_eq!(complete("Fe + O2 -> F"), None);
+        assert_eq!(complete("H2SO4"), None);
+        assert_eq!(complete("H2 + O2 -> H2O"), None);
+        assert_eq!(complete("Cl"), None);
+        assert_eq!(complete("Xy"), None);
+        assert_eq!(complete("na"), None);
+        assert_eq!(complete("Fe "), None);
+    }
+
+    #[test]
+    fn lowercase() {
+        assert_eq!(suggest("h2o -> h2 + o2").as_deref(), Some("H2O -> H2 + O2"));
+        assert_eq!(
+            suggest("ch4 + o2 -> co2 + h2o").as_deref(),
+            Some("CH4 + O2 -> CO2 + H2O")
+        );
+        assert_eq!(
+            suggest("mg(oh)2 + hcl -> mgcl2 + h2o").as_deref(),
+            Some("Mg(OH)2 + HCl -> MgCl2 + H2O")
+        );
+    }
+
+    #[test]
+    fn wrong_capitals() {
+        assert_eq!(
+            suggest("Na + CL2 -> NaCL").as_deref(),
+            Some("Na + Cl2 -> NaCl")
+        );
+        assert_eq!(
+            suggest("cuso4 + fe -> feso4 + cu").as_deref(),
+            Some("CuSO4 + Fe -> FeSO4 + Cu")
+        );
+    }
+
+    #[test]
+    fn keeps_states() {
+        assert_eq!(
+            suggest("nacl(aq) + agno3(AQ) -> agcl(s) + nano3(aq)").as_deref(),
+            Some("NaCl(aq) + AgNO3(aq) -> AgCl(s) + NaNO3(aq)")
+        );
+    }
+
+    #[test]
+    fn nothing_to_suggest() {
+        // already right
+        assert_eq!(suggest("H2 + O2 -> H2O"), None);
+        // letters that aren't elements whichever way they're read
+        assert_eq!(suggest("xq + o2 -> xqo2"), None);
+        // still doesn't parse
+        assert_eq!(suggest("h2 + o2 => h2o"), None);
+    }
+
+    #[test]
+    fn miscased_formulas() {
+        assert_eq!(
+            miscased("h2o + nacl -> NaCl + H2O"),
+            [
+                ("h2o".to_string(), "H2O".to_string()),
+                ("nacl".to_string(), "NaCl".to_string())
+            ]
+        );
+        // coefficients, states and charges stay as they were
+        assert_eq!(
+            miscased("2NaCL(aq) + so4^2- -> X"),
+            [
+                ("NaCL(aq)".to_string(), "NaCl(aq)".to_string()),
+                ("so4^2-".to_string(), "SO4^2-".to_string())
+            ]
+        );
+        // right already, or not elements however they're read
+        assert!(miscased("H2 + O2 -> H2O").is_empty());
+        assert!(miscased("xq + O2 => H2O").is_empty());
+        assert!(miscased("2Na + Cl2 -> 2NaCi").is_empty());
+    }
+
+    #[test]
+    fn capitalizes() {
+        assert_eq!(capitalize("na + cl2 -> 2nacl"), "Na + Cl2 -> 2NaCl");
+        assert_eq!(
+            capitalize("2h2(g)  +  o2 = 2h2o(l)"),
+            "2H2(g)  +  O2 = 2H2O(l)"
+        );
+        assert_eq!(capitalize("fe^3+ + cu"), "Fe^3+ + Cu");
+        // formulas that already parse are left alone, even if they're unusual
+        assert_eq!(capitalize("Co + CO -> Co"), "Co + CO -> Co");
+        // as are arrows, coefficients and what can't be read as elements
+        assert_eq!(capitalize("2 xq => 3"), "2 xq => 3");
+        assert_eq!(capitalize(""), "");
+        // typing a symbol one letter at a time
+        assert_eq!(capitalize("n"), "N");
+        assert_eq!(capitalize("Na"), "Na");
+    }
+
+    #[test]
+    fn closest() {
+        assert_eq!(
+            closest_formula("na + Cl2 -> NaCl"),
+            Some(("na".to_string(), "Na"))
+        );
+        assert_eq!(
+            closest_formula("2Na + Cl2 -> 2NaCi"),
+            Some(("NaCi".to_string(), "NaCl"))
+        );
+        assert_eq!(
+            closest_formula("CH4 + O2 -> CO2 + H2O + 2nh3"),
+            Some(("nh3".to_string(), "NH3"))
+        );
+        // symbols have to match except for case, `Fx` could be `F` or `Fe`
+        assert_eq!(closest_formula("Fx + O2 -> FeO"), None);
+        // too far from anything
+        assert_eq!(closest_formula("Qq + O2 -> QqO2"), None);
+        assert_eq!(closest_formula("H2 + O2 -> H2O"), None);
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("nacl", "nacl"), 0);
+        assert_eq!(edit_distance("naci", "nacl"), 1);
+        assert_eq!(edit_distance("h2o", "h2o2"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "co2"), 3);
+    }
+
+    #[test]
+    fn readings() {
+        assert_eq!(symbols("co").as_deref(), Some("CO"));
+        assert_eq!(symbols("Co").as_deref(), Some("Co"));
+        assert_eq!(symbols("CoO").as_deref(), Some("CoO"));
+        assert_eq!(symbols("NaCL").as_deref(), Some("NaCl"));
+        assert_eq!(symbols("sn").as_deref(), Some("SN"));
+        assert_eq!(symbols("q"), None);
+    }
+}
@@ -0,0 +1,86 @@
+use mendeleev::Element;
+
+/// Columns of the periodic table, one for each group
+pub const COLUMNS: usize = 18;
+
+/// Rows of the periodic table, one for each period and the lanthanides and
+/// actinides under them
+pub const ROWS: usize = 9;
+
+/// The element with atomic number `number`, hydrogen if there isn't one
+pub fn element(number: u32) -> Element {
+    Element::list()
+        .get(number.saturating_sub(1) as usize)
+        .copied()
+        .unwrap_or(Element::H)
+}
+
+/// Where an element is drawn, as its row and column counted from 0. The
+/// lanthanides and actinides go in the two rows under the rest, lined up
+/// from the third column
+pub fn position(el: Element) -> (usize, usize) {
+    match el.atomic_number() as usize {
+        n @ 57..=71 => (7, n - 57 + 2),
+        n @ 89..=103 => (8, n - 89 + 2),
+        _ => (
+            el.period() as usize - 1,
+            el.group().map_or(2, |g| g.group_number() as usize - 1),
+        ),
+    }
+}
+
+/// The element drawn at `row` and `column`, if there is one
+pub fn at(row: usize, column: usize) -> Option<Element> {
+    Element::iter().find(|&el| position(el) == (row, column))
+}
+
+/// The atomic number of the next element from `number` in a direction,
+/// skipping the gaps in the table. It stays put at the edge
+pub fn step(number: u32, rows: isize, columns: isize) -> u32 {
+    let (mut row, mut column) = position(element(number));
+    loop {
+        match (
+            row.checked_add_signed(rows).filter(|&r| r < ROWS),
+            column.checked_add_signed(columns).filter(|&c| c < COLUMNS),
+        ) {
+            (Some(r), Some(c)) => (row, column) = (r, c),
+            _ => return number,
+        }
+        if let Some(el) = at(row, column) {
+            return el.atomic_number();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        assert_eq!(position(Element::H), (0, 0));
+        assert_eq!(position(Element::He), (0, 17));
+        assert_eq!(position(Element::Fe), (3, 7));
+        assert_eq!(position(Element::La), (7, 2));
+        assert_eq!(position(Element::Lr), (8, 16));
+        assert_eq!(position(Element::Og), (6, 17));
+        // every element has a place of its own
+        for el in Element::iter() {
+            assert_eq!(at(position(el).0, position(el).1), Some(el), "{el:?}");
+        }
+    }
+
+    #[test]
+    fn moving() {
+        // across the gap between the s and p blocks
+        assert_eq!(step(4, 0, 1), 5);
+        assert_eq!(step(5, 0, -1), 4);
+        // down from helium and up past the top
+        assert_eq!(step(2, 1, 0), 10);
+        assert_eq!(step(5, -1, 0), 5);
+        // yttrium down to the lanthanides
+        assert_eq!(step(39, 1, 0), 57);
+        assert_eq!(step(1, 0, -1), 1);
+        assert_eq!(step(118, 0, 1), 118);
+    }
+}
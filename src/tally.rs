@@ -0,0 +1,86 @@
+use chem_eq::Compound;
+
+/// How many atoms of an element there are on each side of an equation, with
+/// the coefficients as they're written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub element: &'static str,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Tally {
+    /// There are as many on each side
+    pub const fn matches(&self) -> bool {
+        self.left == self.right
+    }
+}
+
+/// Count the atoms of each element on both sides, whether or not they're
+/// balanced, in the order the elements first appear
+pub fn tally(left: &[Compound], right: &[Compound]) -> Vec<Tally> {
+    let mut tallies: Vec<Tally> = Vec::new();
+    let sides = [(left, true), (right, false)];
+    for (compounds, is_left) in sides {
+        for cmp in compounds {
+            for el in &cmp.elements {
+                let atoms = el.count * cmp.coefficient;
+                let i = match tallies.iter().position(|t| t.element == el.symbol()) {
+                    Some(i) => i,
+                    None => {
+                        tallies.push(Tally {
+                            element: el.symbol(),
+                            left: 0,
+                            right: 0,
+                        });
+                        tallies.len() - 1
+                    }
+                };
+                if is_left {
+                    tallies[i].left += atoms;
+                } else {
+                    tallies[i].right += atoms;
+                }
+            }
+        }
+    }
+    tallies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnose::parse_sides;
+
+    fn tally_str(input: &str) -> Vec<(&'static str, usize, usize)> {
+        let (left, right) = parse_sides(input).unwrap();
+        tally(&left, &right)
+            .iter()
+            .map(|t| (t.element, t.left, t.right))
+            .collect()
+    }
+
+    #[test]
+    fn counts() {
+        assert_eq!(tally_str("H2 + O2 -> H2O"), [("H", 2, 2), ("O", 2, 1)]);
+        assert_eq!(tally_str("2H2 + O2 -> 2H2O"), [("H", 4, 4), ("O", 2, 2)]);
+        // brackets and hydrates are multiplied out
+        assert_eq!(
+            tally_str("Ca(OH)2 -> CaO + H2O"),
+            [("Ca", 1, 1), ("O", 2, 2), ("H", 2, 2)]
+        );
+        assert_eq!(
+            tally_str("CuSO4·5H2O -> CuSO4 + H2O"),
+            [("Cu", 1, 1), ("S", 1, 1), ("O", 9, 5), ("H", 10, 2)]
+        );
+        // elements on one side only
+        assert_eq!(tally_str("Fe -> Fe2O3"), [("Fe", 1, 2), ("O", 0, 3)]);
+    }
+
+    #[test]
+    fn matches() {
+        let (left, right) = parse_sides("H2 + O2 -> H2O").unwrap();
+        let matched: Vec<_> = tally(&left, &right).iter().map(Tally::matches).collect();
+        assert_eq!(matched, [true, false]);
+    }
+}
@@ -0,0 +1,46 @@
+use ratatui::style::Color;
+use serde::Deserialize;
+
+/// The colours to draw with, to suit the terminal's background
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    /// Yellow and cyan are hard to read on white, so they're swapped out
+    Light,
+}
+
+impl Theme {
+    /// What's focused or typed, and warnings
+    pub const fn highlight(self) -> Color {
+        match self {
+            Self::Dark => Color::Yellow,
+            Self::Light => Color::Blue,
+        }
+    }
+
+    /// Coefficients that changed, and the states things are in
+    pub const fn accent(self) -> Color {
+        match self {
+            Self::Dark => Color::Cyan,
+            Self::Light => Color::Magenta,
+        }
+    }
+
+    /// The other theme
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Dark => Self::Light,
+            Self::Light => Self::Dark,
+        }
+    }
+
+    /// As written in the config file
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+        }
+    }
+}
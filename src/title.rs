@@ -0,0 +1,37 @@
+/// Longest equation put in the terminal's title, in characters
+const MAX_EQUATION: usize = 60;
+
+/// The terminal title for a balanced equation, eg. `balance-tui — 2H2 + O2 -> 2H2O`.
+/// Control characters are left out so they can't end the escape sequence the
+/// title is sent in, and long equations are cut off
+pub fn title(equation: &str) -> String {
+    let equation: String = equation.chars().filter(|c| !c.is_control()).collect();
+    let equation = equation.trim();
+    let equation = if equation.chars().count() > MAX_EQUATION {
+        let start: String = equation.chars().take(MAX_EQUATION - 1).collect();
+        format!("{}…", start.trim_end())
+    } else {
+        equation.to_string()
+    };
+    format!("balance-tui — {equation}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles() {
+        assert_eq!(title("2H2 + O2 -> 2H2O"), "balance-tui — 2H2 + O2 -> 2H2O");
+        assert_eq!(
+            title("H2\x1b]0;pwned\x07 + O2"),
+            "balance-tui — H2]0;pwned + O2"
+        );
+        let long = title(&"C6H12O6 + ".repeat(10));
+        assert_eq!(
+            long.chars().count(),
+            "balance-tui — ".chars().count() + MAX_EQUATION
+        );
+        assert!(long.ends_with("C6H12O6 +…"), "{long}");
+    }
+}
@@ -1,247 +1,353 @@
-use std::io;
+use std::{
+    fs, io, panic,
+    time::{Duration, Instant},
+};
 
-use arboard::Clipboard;
-use chem_eq::{
-    balance::EquationBalancer,
-    error::{BalanceError, EquationError},
-    Equation,
+use balance_tui::{
+    app::{Action, App, Status},
+    batch,
+    clipboard::Clipboard,
+    config::{Config, TuiConfig},
+    editor,
+    event::{handle_events, TerminalEvents},
+    favorites,
+    history::History,
+    render::ui,
+    session::Session,
+    worker::Worker,
 };
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    cursor::Show,
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use tui::{
-    backend::Backend,
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, Paragraph, Widget},
-    Frame, Terminal,
+    style::Print,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, EnterAlternateScreen,
+        LeaveAlternateScreen, SetTitle,
+    },
 };
+use ratatui::{backend::CrosstermBackend, Terminal};
 
-#[derive(Debug, Default)]
-struct App {
-    pub input_mode: InputMode,
-    pub input: String,
-    pub output: Option<Result<Equation, Error>>,
-}
+/// Longest time between ticks while an equation is being balanced
+const BUSY_TICK_RATE: Duration = Duration::from_millis(80);
 
-impl App {
-    pub fn input_body(&self) -> impl Widget + '_ {
-        let (text, text_colour) = if self.input.is_empty() {
-            ("Input equation...", Color::DarkGray)
-        } else {
-            (self.input.as_str(), Color::Yellow)
-        };
-        let (text_style, border_style) = if let InputMode::Editing = self.input_mode {
-            (
-                Style::default().fg(text_colour),
-                Style::default().fg(Color::Yellow),
-            )
-        } else {
-            (
-                Style::default().fg(Color::DarkGray),
-                Style::default().fg(Color::DarkGray),
-            )
-        };
-        Paragraph::new(Span::styled(format!(" {}", text), text_style))
-            .style(border_style)
-            .block(Block::default().borders(Borders::ALL))
-    }
+/// Puts the terminal into raw mode on the alternate screen, and restores it
+/// when dropped. This way early returns and panics don't leave the user's
+/// shell in a broken state.
+struct TerminalGuard;
 
-    pub fn output_body(&self) -> impl Widget + '_ {
-        let text = self.output.as_ref().map_or_else(
-            || "Waiting for equation...".to_string(),
-            |r| {
-                let res = r.as_ref().map(Equation::equation);
-                match res {
-                    Ok(s) => s.to_string(),
-                    Err(Error::Eq(EquationError::ParsingError(_))) => {
-                        "Couldn't parse equation".to_string()
-                    }
-                    Err(Error::Eq(EquationError::IncorrectEquation)) => {
-                        "Equation was not valid".to_string()
-                    }
-                    Err(Error::Eq(EquationError::TooMuchInput(s))) => {
-                        format!("Too much input: {s}")
-                    }
-                    Err(Error::Balance(e)) => e.to_string(),
-                }
-            },
-        );
-        let style = match &self.output {
-            Some(Ok(_)) => Style::default().fg(Color::Green),
-            Some(Err(_)) => Style::default().fg(Color::Red),
-            None => Style::default().fg(Color::DarkGray),
-        };
-        Paragraph::new(format!(" {}", text))
-            .style(style)
-            .block(Block::default().borders(Borders::ALL))
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        // construct the guard first so a failure part way through still restores raw mode
+        let guard = Self;
+        enter_terminal()?;
+        Ok(guard)
     }
+}
 
-    pub fn update_eq(&mut self) {
-        if self.input.is_empty() {
-            self.output = None;
-            return;
-        }
-        let res = Equation::new(self.input.as_str());
-        let Ok(eq) = res else {
-            self.output = Some(res.map_err(Into::into));
-            return;
-        };
-        let balancer = EquationBalancer::new(&eq);
-        let eq = balancer.balance().map_err(Into::into);
-        self.output = Some(eq);
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // nothing useful can be done with an error while dropping
+        let _ = restore_terminal();
     }
 }
 
-#[derive(Debug, Default)]
-enum InputMode {
-    Editing,
-    #[default]
-    Normal,
+/// Enter raw mode and the alternate screen the tui is drawn on
+fn enter_terminal() -> io::Result<()> {
+    enable_raw_mode()?;
+    // pasted text comes as one event instead of a key press per character
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )
 }
 
-impl InputMode {
-    pub const fn to_help(&self) -> &'static str {
-        match self {
-            Self::Normal => " i or e          to edit\n q or esc        to quit\n y               to copy balanced equation",
-            Self::Editing => " esc or ctrl-[   leave editing mode\n",
-        }
+/// Give the terminal back while `f` runs, for another program to use, then
+/// take it again. What was drawn is gone, so everything has to be drawn again
+fn suspended<T>(f: impl FnOnce() -> T) -> io::Result<T> {
+    restore_terminal()?;
+    let out = f();
+    enter_terminal()?;
+    Ok(out)
+}
+
+/// Stop the process as the shell would for ctrl-z, with the terminal restored
+/// so the shell can be used. Raw mode stops the terminal sending the signal
+/// itself. Returns once it's continued, eg. with `fg`
+#[cfg(unix)]
+fn suspend() -> io::Result<()> {
+    use signal_hook::{consts::SIGTSTP, low_level};
+
+    // with no handler for SIGTSTP this stops until SIGCONT
+    suspended(|| low_level::raise(SIGTSTP))?
+}
+
+#[cfg(not(unix))]
+fn suspend() -> io::Result<()> {
+    Ok(())
+}
+
+/// Leave raw mode and the alternate screen. Does nothing if the terminal was
+/// already restored, so it's safe to call from both the panic hook and [`TerminalGuard`].
+fn restore_terminal() -> io::Result<()> {
+    if !is_raw_mode_enabled()? {
+        return Ok(());
     }
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        Show
+    )
 }
 
-#[derive(Debug, Clone)]
-enum Error {
-    Eq(EquationError),
-    Balance(BalanceError),
+/// Save the terminal's title on its stack of titles, to put it back with
+/// [`POP_TITLE`]. Terminals without a stack ignore both
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Restore the terminal before the existing (`color_eyre`) panic hook prints
+/// its report, otherwise the report is lost on the alternate screen.
+fn install_panic_hook() {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        hook(info);
+    }));
 }
 
-impl ToString for Error {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Eq(EquationError::ParsingError(_)) => {
-                "Equation could not be balanced".to_string()
+/// Open the session saved with ctrl-s. An equation the tui was started with
+/// opens in a tab after it. A session that can't be read is only warned about
+fn resume_session(app: &mut App) {
+    let Some(path) = Session::path() else {
+        return;
+    };
+    match Session::load(&path) {
+        Ok(Some(session)) => {
+            let given = app.input.clone();
+            app.restore_session(session);
+            if !given.trim().is_empty() {
+                app.new_tab();
+                app.set_input(&given);
             }
-            Self::Eq(EquationError::IncorrectEquation) => "Equation is not valid".to_string(),
-            Self::Eq(EquationError::TooMuchInput(s)) => format!("Too much input: {s}"),
-            Self::Balance(e) => e.to_string(),
+        }
+        Ok(None) => {}
+        Err(e) => {
+            app.status = Some(Status::Warning(format!(
+                "Ignored the saved session, {}: {e}",
+                path.display()
+            )));
         }
     }
 }
 
-impl From<EquationError> for Error {
-    fn from(e: EquationError) -> Self {
-        Self::Eq(e)
+/// Enable the tui, allowing a user to solve the equation, starting from `app`.
+/// With `osc52` text is always copied through the terminal instead of the system
+/// clipboard. With `resume` the saved session is opened too. Returns the app as
+/// it was when the user quit.
+pub fn tui(mut app: App, config: &Config, osc52: bool, resume: bool) -> color_eyre::Result<App> {
+    // a file that can't be read is warned about and left alone, rather than
+    // replaced with an empty one on exit
+    let mut history_path = History::path().filter(|_| config.history.persist());
+    app.history = History::new(config.history.max_entries);
+    if let Some(path) = &history_path {
+        match History::load(path, config.history.max_entries) {
+            Ok(history) => app.history = history,
+            Err(e) => {
+                app.status = Some(Status::Warning(format!(
+                    "Started without history, couldn't read {}: {e}",
+                    path.display()
+                )));
+                history_path = None;
+            }
+        }
     }
-}
-
-impl From<BalanceError> for Error {
-    fn from(e: BalanceError) -> Self {
-        Self::Balance(e)
+    // favorites are kept with the history, but never trimmed to its size
+    let mut favorites_path = favorites::path().filter(|_| config.history.persist());
+    if let Some(path) = &favorites_path {
+        match favorites::load(path) {
+            Ok(favorites) => app.favorites = favorites,
+            Err(e) => {
+                app.status = Some(Status::Warning(format!(
+                    "Started without favorites, couldn't read {}: {e}",
+                    path.display()
+                )));
+                favorites_path = None;
+            }
+        }
+    }
+    if resume || config.session.restore {
+        resume_session(&mut app);
     }
-}
 
-/// Enable the tui, allowing a user to solve the equation
-pub fn tui() -> color_eyre::Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    install_panic_hook();
+    let guard = TerminalGuard::new()?;
 
-    let backend = CrosstermBackend::new(stdout);
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
-    // app state
-    let mut app = App::default();
-    let mut clipboard = Clipboard::new()?;
-
-    loop {
-        terminal.draw(|f| ui(f, &app))?;
-        if let Event::Key(key) = event::read()? {
-            match (&app.input_mode, key.code) {
-                (_, KeyCode::Char('c')) if key.modifiers == KeyModifiers::CONTROL => break,
-                (InputMode::Normal, KeyCode::Char('q') | KeyCode::Esc) => break,
-                (InputMode::Normal, KeyCode::Char('i' | 'e')) => {
-                    app.input_mode = InputMode::Editing;
+    let mut clipboard = Clipboard::new(osc52, config.clipboard.primary_selection);
+    // text the user tried to copy when no clipboard was available
+    let mut uncopied = None;
+
+    app.worker = Some(Worker::spawn());
+    let mut events = TerminalEvents;
+    let tick_rate = Duration::from_millis(config.tui.tick_rate_ms);
+    let mut last_tick = Instant::now();
+    let mut redraw = true;
+    // the title last set, so it's only sent again when another equation is balanced
+    let mut title = None;
+
+    'main: loop {
+        if redraw {
+            terminal.draw(|f| ui(f, &app))?;
+        }
+        if let Some(new) = app.window_title().filter(|_| config.tui.set_title) {
+            if title.as_ref() != Some(&new) {
+                if title.is_none() {
+                    execute!(io::stdout(), Print(PUSH_TITLE))?;
                 }
-                (InputMode::Normal, KeyCode::Char('y')) => {
-                    if let Some(Ok(ref eq)) = app.output {
-                        clipboard.set_text(eq.to_string())?;
+                execute!(io::stdout(), SetTitle(&new))?;
+                title = Some(new);
+            }
+        }
+
+        // tick faster while balancing to animate the spinner
+        let tick_rate = if app.balancing {
+            tick_rate.min(BUSY_TICK_RATE)
+        } else {
+            tick_rate
+        };
+        let timeout = tick_rate.saturating_sub(last_tick.elapsed());
+        let update = handle_events(&mut app, &mut events, timeout)?;
+        redraw = update.redraw;
+        for action in update.actions {
+            match action {
+                Action::Quit => break 'main,
+                // drawn again in full below, as the cleared screen won't match the last frame
+                Action::Redraw => terminal.clear()?,
+                Action::Copy { text, format } => match clipboard.copy(&text) {
+                    Ok(backend) => {
+                        app.status = Some(Status::Info(format!("Copied {format} with {backend}")));
                     }
+                    Err(e) => {
+                        app.status = Some(Status::Error(format!(
+                            "Couldn't copy, equation will be printed on exit: {e}"
+                        )));
+                        uncopied = Some(text);
+                    }
+                },
+                Action::CopyTranscript { text, format } => match clipboard.copy(&text) {
+                    Ok(backend) => {
+                        app.status = Some(Status::Info(format!(
+                            "Copied the transcript as {format} with {backend}"
+                        )));
+                    }
+                    Err(e) => {
+                        app.status = Some(Status::Error(format!(
+                            "Couldn't copy, the transcript will be printed on exit: {e}"
+                        )));
+                        uncopied = Some(text);
+                    }
+                },
+                Action::OpenEditor => {
+                    let editor = editor::command();
+                    let edited = suspended(|| editor::edit(&editor, &app.input))?;
+                    terminal.clear()?;
+                    app.status = match edited.map(|text| editor::join_lines(&text)) {
+                        Ok(text) if text.is_empty() => Some(Status::Warning(
+                            "Nothing was left in the editor, the equation is as it was".to_string(),
+                        )),
+                        Ok(text) => {
+                            app.set_input(&text);
+                            None
+                        }
+                        Err(e) => Some(Status::Error(format!("Couldn't edit with {editor}: {e}"))),
+                    };
+                }
+                Action::Suspend => {
+                    suspend()?;
+                    terminal.clear()?;
                 }
-                (InputMode::Editing, KeyCode::Esc) => app.input_mode = InputMode::Normal,
-                (InputMode::Editing, KeyCode::Char('['))
-                    if key.modifiers == KeyModifiers::CONTROL =>
-                {
-                    app.input_mode = InputMode::Normal;
+                Action::SaveSession => {
+                    app.status = Some(match Session::path() {
+                        Some(path) => match app.session().save(&path) {
+                            Ok(()) => {
+                                Status::Info(format!("Saved the session to {}", path.display()))
+                            }
+                            Err(e) => Status::Error(format!(
+                                "Couldn't save the session to {}: {e}",
+                                path.display()
+                            )),
+                        },
+                        None => Status::Error("Nowhere to save the session".to_string()),
+                    });
                 }
-                (InputMode::Editing, KeyCode::Char(c)) => {
-                    app.input.push(c);
-                    app.update_eq();
+                Action::Export { report, path } => {
+                    app.status = Some(match fs::write(&path, report) {
+                        Ok(()) => Status::Info(format!("Wrote {}", path.display())),
+                        Err(e) => Status::Error(format!("Couldn't write {}: {e}", path.display())),
+                    });
                 }
-                (InputMode::Editing, KeyCode::Backspace) => {
-                    app.input.pop();
-                    app.update_eq();
+                Action::Save { text, path, append } => {
+                    app.status = Some(match batch::save_line(&path, &text, append) {
+                        Ok(()) if append => Status::Info(format!("Added to {}", path.display())),
+                        Ok(()) => Status::Info(format!("Saved to {}", path.display())),
+                        Err(e) => {
+                            Status::Error(format!("Couldn't save to {}: {e}", path.display()))
+                        }
+                    });
                 }
-                _ => {}
             }
         }
-    }
+        // redraw with the new size now instead of on the next key press
+        if update.resized {
+            terminal.autoresize()?;
+        }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+        if last_tick.elapsed() >= tick_rate {
+            redraw |= app.on_tick();
+            last_tick = Instant::now();
+        }
+    }
 
-    Ok(())
-}
+    // print after leaving the alternate screen so the text ends up in the scrollback
+    drop(guard);
+    if title.is_some() {
+        execute!(io::stdout(), Print(POP_TITLE))?;
+    }
+    if let Some(text) = uncopied {
+        eprintln!("{text}");
+    }
 
-/// Draw tui ui
-fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints(
-            [
-                Constraint::Length(1),
-                Constraint::Length(3),
-                Constraint::Length(3),
-                Constraint::Length(5),
-                Constraint::Min(1),
-            ]
-            .as_ref(),
-        )
-        .split(f.size());
-
-    // title
-    let title = Paragraph::new("Chemical Equation Balancer")
-        .alignment(Alignment::Center)
-        .style(Style::default().add_modifier(Modifier::BOLD));
-    f.render_widget(title, chunks[0]);
-
-    // input area
-    let input_body = app.input_body();
-    f.render_widget(input_body, chunks[1]);
-
-    // output area
-    let output = app.output_body();
-    f.render_widget(output, chunks[2]);
-
-    // help area
-    let help_body = Paragraph::new(app.input_mode.to_help())
-        .block(Block::default().title("Help").borders(Borders::ALL));
-    f.render_widget(help_body, chunks[3]);
-
-    // cursor
-    match app.input_mode {
-        InputMode::Editing => {
-            f.set_cursor(chunks[1].x + app.input.len() as u16 + 2, chunks[1].y + 1);
+    if let Some(path) = history_path {
+        app.history.push(&app.input);
+        if let Err(e) = app.history.save(&path) {
+            eprintln!("error: couldn't save history to {}: {e}", path.display());
+        }
+    }
+    if app.settings_changed {
+        let tui = TuiConfig {
+            show_help: app.show_help,
+            subscripts: app.subscripts,
+            theme: app.theme,
+            confirm_quit: app.confirm_quit,
+            explicit_ones: app.explicit_ones,
+            ..config.tui.clone()
+        };
+        if let Err(e) = tui.save_settings() {
+            eprintln!("error: couldn't save settings to the config file: {e}");
+        }
+    }
+    if let Some(path) = favorites_path {
+        if let Err(e) = favorites::save(&path, &app.favorites) {
+            eprintln!("error: couldn't save favorites to {}: {e}", path.display());
         }
-        InputMode::Normal => {}
     }
+
+    Ok(app)
 }
@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+/// The input and cursor at some point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub input: String,
+    pub cursor: usize,
+}
+
+/// What kind of edit is being made, so runs of the same edit can be undone together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    /// Typing a character, which may be whitespace
+    Insert { space: bool },
+    /// Backspace or delete
+    Remove,
+    /// Replacing the whole input
+    Replace,
+}
+
+/// Undo and redo stacks of the input. Typing is undone a word at a time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Undo {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// The previous edit, if the next one of the same kind should join it
+    last: Option<Edit>,
+    /// Most snapshots to keep, dropping the oldest
+    max: usize,
+}
+
+impl Default for Undo {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl Undo {
+    pub const fn new(max: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            last: None,
+            max,
+        }
+    }
+
+    /// Call before making `edit`, with the state before it
+    pub fn record(&mut self, edit: Edit, before: Snapshot) {
+        self.redo.clear();
+        let joins = match (self.last, edit) {
+            // a new word starts a new group
+            (Some(Edit::Insert { space: true }), Edit::Insert { space: false }) => false,
+            (Some(Edit::Insert { .. }), Edit::Insert { .. }) => true,
+            (Some(Edit::Remove), Edit::Remove) => true,
+            _ => false,
+        };
+        self.last = Some(edit);
+        if joins {
+            return;
+        }
+        self.undo.push_back(before);
+        if self.undo.len() > self.max {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Stop the next edit joining the previous one, eg. after moving the cursor
+    pub fn split(&mut self) {
+        self.last = None;
+    }
+
+    /// Go back to before the last group of edits
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop_back()?;
+        self.redo.push(current);
+        self.last = None;
+        Some(snapshot)
+    }
+
+    /// Reapply the last undone group of edits
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push_back(current);
+        self.last = None;
+        Some(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snap(input: &str) -> Snapshot {
+        Snapshot {
+            input: input.to_string(),
+            cursor: input.chars().count(),
+        }
+    }
+
+    #[test]
+    fn bounded() {
+        let mut undo = Undo::new(2);
+        for input in ["a", "b", "c"] {
+            undo.record(Edit::Replace, snap(input));
+        }
+        assert_eq!(undo.undo(snap("d")), Some(snap("c")));
+        assert_eq!(undo.undo(snap("c")), Some(snap("b")));
+        assert_eq!(undo.undo(snap("b")), None);
+    }
+
+    #[test]
+    fn groups() {
+        let mut undo = Undo::default();
+        undo.record(Edit::Insert { space: false }, snap(""));
+        undo.record(Edit::Insert { space: false }, snap("a"));
+        undo.record(Edit::Remove, snap("ab"));
+        undo.record(Edit::Remove, snap("a"));
+        assert_eq!(undo.undo(snap("")), Some(snap("ab")));
+        assert_eq!(undo.undo(snap("ab")), Some(snap("")));
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use crate::{
+    balance, charge,
+    diagnose::parse_sides,
+    format,
+    tally::{tally, Tally},
+};
+
+/// Whether the coefficients an equation was typed with balance it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// They balance it and can't be any smaller
+    Minimal,
+    /// They balance it, but all share `factor`. `simplest` is the equation
+    /// with them divided by it
+    Reducible { factor: usize, simplest: String },
+    /// They don't balance it. The elements with different counts on each
+    /// side, and the net charge of each side if that's different too
+    Unbalanced {
+        elements: Vec<Tally>,
+        charge: Option<(i64, i64)>,
+    },
+}
+
+impl Verdict {
+    /// The code the cli exits with for `--verify`
+    pub const fn exit_code(&self) -> u8 {
+        match self {
+            Self::Minimal => 0,
+            Self::Reducible { .. } => 4,
+            Self::Unbalanced { .. } => 5,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Minimal => write!(f, "balanced and in lowest terms"),
+            Self::Reducible { simplest, .. } => write!(
+                f,
+                "balanced, but not in lowest terms, simplest form is {simplest}"
+            ),
+            Self::Unbalanced { elements, charge } => {
+                let mut off: Vec<String> = elements
+                    .iter()
+                    .map(|t| {
+                        format!(
+                            "{} is {} on the left but {} on the right",
+                            t.element, t.left, t.right
+                        )
+                    })
+                    .collect();
+                if let Some((left, right)) = charge {
+                    off.push(format!(
+                        "net charge is {} on the left but {} on the right",
+                        charge::format_net(*left),
+                        charge::format_net(*right)
+                    ));
+                }
+                write!(f, "unbalanced, {}", off.join("; "))
+            }
+        }
+    }
+}
+
+/// Check the coefficients `input` was typed with, 1 where there isn't one,
+/// against the smallest ones that balance it. `None` if it can't be read
+pub fn verify(input: &str) -> Option<Verdict> {
+    let (left, right) = parse_sides(input)?;
+    let elements: Vec<Tally> = tally(&left, &right)
+        .into_iter()
+        .filter(|t| !t.matches())
+        .collect();
+    let charge = charge::net(input).filter(|(left, right)| left != right);
+    if !elements.is_empty() || charge.is_some() {
+        return Some(Verdict::Unbalanced { elements, charge });
+    }
+
+    let eq = balance::parse(input).ok()?;
+    let (reduced, factor) = balance::reduce(eq);
+    Some(match factor {
+        1 => Verdict::Minimal,
+        factor => Verdict::Reducible {
+            factor,
+            simplest: format::plain_equation(input, &reduced, false),
+        },
+    })
+}
+
+/// Whether any compound in `input` was typed with a coefficient
+pub fn has_coefficients(input: &str) -> bool {
+    parse_sides(input)
+        .is_some_and(|(left, right)| left.iter().chain(&right).any(|c| c.coefficient != 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdicts() {
+        assert_eq!(verify("2H2 + O2 -> 2H2O"), Some(Verdict::Minimal));
+        assert_eq!(
+            verify("4H2 + 2O2 -> 4H2O"),
+            Some(Verdict::Reducible {
+                factor: 2,
+                simplest: "2H2 + O2 -> 2H2O".to_string(),
+            })
+        );
+        assert_eq!(
+            verify("2H2 + 2O2 -> 2H2O").unwrap().to_string(),
+            "unbalanced, O is 4 on the left but 2 on the right"
+        );
+        // nothing typed is ones
+        assert_eq!(
+            verify("H2 + O2 -> H2O").unwrap().to_string(),
+            "unbalanced, O is 2 on the left but 1 on the right"
+        );
+        assert_eq!(verify("Fe + O2"), None);
+    }
+
+    #[test]
+    fn ions() {
+        assert_eq!(
+            verify("4Fe^3+ + 2Cu -> 4Fe^2+ + 2Cu^2+"),
+            Some(Verdict::Reducible {
+                factor: 2,
+                simplest: "2Fe^3+ + Cu -> 2Fe^2+ + Cu^2+".to_string(),
+            })
+        );
+        assert_eq!(verify("Cu -> Cu^2+ + 2e-"), Some(Verdict::Minimal));
+        assert_eq!(
+            verify("Cu -> Cu^2+ + e-").unwrap().to_string(),
+            "unbalanced, net charge is 0 on the left but + on the right"
+        );
+        // the atoms match but the charges don't
+        assert_eq!(
+            verify("Fe^3+ + Cu -> Fe^2+ + Cu^2+").unwrap().to_string(),
+            "unbalanced, net charge is 3+ on the left but 4+ on the right"
+        );
+    }
+
+    #[test]
+    fn coefficients() {
+        assert!(has_coefficients("4H2 + 2O2 -> 4H2O"));
+        assert!(!has_coefficients("H2 + O2 -> H2O"));
+        assert!(!has_coefficients("H2 + O2"));
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+/// How long a file has to be left alone after it changes before it's read, so
+/// an editor saving in several writes is only balanced once
+pub const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Longest wait for a change before checking if ctrl-c was pressed
+const POLL: Duration = Duration::from_millis(50);
+
+/// Waits for changes to settle down before acting on them
+#[derive(Debug, Clone, Copy)]
+pub struct Debounce {
+    delay: Duration,
+    /// When the last change that hasn't been acted on was
+    pending: Option<Instant>,
+}
+
+impl Debounce {
+    pub const fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: None,
+        }
+    }
+
+    /// Something changed at `now`, which starts the wait over
+    pub fn change(&mut self, now: Instant) {
+        self.pending = Some(now);
+    }
+
+    /// Whether it's been long enough since the last change at `now`. Only
+    /// true once for each burst of changes
+    pub fn ready(&mut self, now: Instant) -> bool {
+        match self.pending {
+            Some(at) if now.duration_since(at) >= self.delay => {
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether an event is the file called `name` being written, made or moved
+/// into place. Editors often save by writing a new file and renaming it over
+/// the old one, so all of them count
+fn changes(event: &Event, name: &std::ffi::OsStr) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+    ) && event.paths.iter().any(|p| p.file_name() == Some(name))
+}
+
+/// Call `on_change` with the contents of the file at `path` straight away and
+/// again every time it changes, until ctrl-c is pressed. The directory it's in
+/// is watched rather than the file, so the watch survives it being replaced
+pub fn watch(path: &Path, mut on_change: impl FnMut(&str)) -> notify::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| notify::Error::generic("can only watch a file"))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&stop))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    let read = |on_change: &mut dyn FnMut(&str)| match std::fs::read_to_string(path) {
+        Ok(text) => on_change(&text),
+        // it can be gone for a moment while it's being replaced
+        Err(e) => eprintln!("error: couldn't read {}: {e}", path.display()),
+    };
+    read(&mut on_change);
+
+    let mut debounce = Debounce::new(DEBOUNCE);
+    while !stop.load(Ordering::Relaxed) {
+        match rx.recv_timeout(POLL) {
+            Ok(Ok(event)) if changes(&event, name) => debounce.change(Instant::now()),
+            Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(Err(e)) => return Err(e),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if debounce.ready(Instant::now()) {
+            read(&mut on_change);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, path::PathBuf};
+
+    use notify::event::{CreateKind, ModifyKind, RemoveKind};
+
+    use super::*;
+
+    #[test]
+    fn debounce() {
+        let start = Instant::now();
+        let mut debounce = Debounce::new(Duration::from_millis(100));
+        assert!(!debounce.ready(start));
+
+        // a burst of writes is acted on once, after the last of them
+        debounce.change(start);
+        debounce.change(start + Duration::from_millis(60));
+        assert!(!debounce.ready(start + Duration::from_millis(120)));
+        assert!(debounce.ready(start + Duration::from_millis(160)));
+        assert!(!debounce.ready(start + Duration::from_millis(300)));
+    }
+
+    #[test]
+    fn relevant_events() {
+        let event = |kind, path: &str| Event::new(kind).add_path(PathBuf::from(path));
+        let name = OsStr::new("eq.txt");
+        assert!(changes(
+            &event(EventKind::Modify(ModifyKind::Any), "/tmp/eq.txt"),
+            name
+        ));
+        assert!(changes(
+            &event(EventKind::Create(CreateKind::File), "/tmp/eq.txt"),
+            name
+        ));
+        // other files in the same directory, and removing it, don't count
+        assert!(!changes(
+            &event(EventKind::Modify(ModifyKind::Any), "/tmp/eq.txt.swp"),
+            name
+        ));
+        assert!(!changes(
+            &event(EventKind::Remove(RemoveKind::File), "/tmp/eq.txt"),
+            name
+        ));
+    }
+}
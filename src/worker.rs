@@ -0,0 +1,147 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chem_eq::Equation;
+
+use crate::{balance::balance_reduced, error::Error};
+
+/// An input sent to the worker, numbered so old results can be told apart
+#[derive(Debug)]
+struct Job {
+    generation: u64,
+    input: String,
+}
+
+/// The result of balancing one input
+#[derive(Debug)]
+pub struct Done {
+    generation: u64,
+    /// See [`balance_reduced`]
+    pub output: Result<(Equation, usize), Error>,
+    /// How long parsing and balancing took
+    pub took: Duration,
+}
+
+/// Balances equations on a background thread so a slow equation can't freeze
+/// the ui. Only the result for the newest input is ever returned.
+#[derive(Debug)]
+pub struct Worker {
+    jobs: Sender<Job>,
+    done: Receiver<Done>,
+    generation: u64,
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        Self::spawn()
+    }
+}
+
+impl Worker {
+    /// Start the worker thread. It's never joined: it stops once the worker is
+    /// dropped and it finishes its current equation, so quitting doesn't wait on it
+    pub fn spawn() -> Self {
+        let (jobs, job_rx) = mpsc::channel::<Job>();
+        let (done_tx, done) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(mut job) = job_rx.recv() {
+                // skip straight to the newest input if more were sent while busy
+                while let Ok(newer) = job_rx.try_recv() {
+                    job = newer;
+                }
+                let start = Instant::now();
+                let output = balance_reduced(&job.input);
+                let done = Done {
+                    generation: job.generation,
+                    output,
+                    took: start.elapsed(),
+                };
+                if done_tx.send(done).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            jobs,
+            done,
+            generation: 0,
+        }
+    }
+
+    /// Start balancing `input`, replacing anything that's still being balanced
+    pub fn submit(&mut self, input: &str) {
+        self.generation += 1;
+        // the thread only stops once the receiver is gone, so this can't fail
+        let _ = self.jobs.send(Job {
+            generation: self.generation,
+            input: input.to_string(),
+        });
+    }
+
+    /// Wait up to `timeout` for the newest input to be balanced
+    pub fn receive(&mut self, timeout: Duration) -> Option<Done> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.done.recv_timeout(left) {
+                Ok(done) if done.generation == self.generation => return Some(done),
+                // a result for an old input
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An equation that takes a good while to balance
+    fn huge_equation() -> String {
+        let alkanes: Vec<String> = (1..=150).map(|i| format!("C{i}H{}", 2 * i + 2)).collect();
+        format!("{} + O2 -> CO2 + H2O", alkanes.join(" + "))
+    }
+
+    #[test]
+    fn balance() {
+        let mut worker = Worker::spawn();
+        worker.submit("H2 + O2 -> H2O");
+        let done = worker.receive(Duration::from_secs(10)).unwrap();
+        let (eq, _) = done.output.unwrap();
+        assert_eq!(eq.equation(), "2H2 + O2 -> 2H2O");
+        assert!(worker.receive(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn stale_results() {
+        let mut worker = Worker::spawn();
+        let start = Instant::now();
+        worker.submit(&huge_equation());
+        worker.submit("Fe + O2 -> Fe2O3");
+        // submitting never waits for the worker
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        let (eq, _) = worker
+            .receive(Duration::from_secs(60))
+            .unwrap()
+            .output
+            .unwrap();
+        assert_eq!(eq.equation(), "4Fe + 3O2 -> 2Fe2O3");
+    }
+
+    #[test]
+    fn huge_equation_in_background() {
+        let mut worker = Worker::spawn();
+        worker.submit(&huge_equation());
+        assert!(worker.receive(Duration::ZERO).is_none());
+
+        // dropping a busy worker doesn't wait for it
+        let start = Instant::now();
+        drop(worker);
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,538 @@
+//! Run the `cmbl` binary the way a user would.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn cmbl() -> Command {
+    Command::cargo_bin("cmbl").unwrap()
+}
+
+#[test]
+fn balance_argument() {
+    cmbl()
+        .arg("Fe + O2 -> Fe2O3")
+        .assert()
+        .success()
+        .stdout("4Fe + 3O2 -> 2Fe2O3\n")
+        .stderr("");
+}
+
+#[test]
+fn textbook_arrows() {
+    cmbl()
+        .arg("N2 + H2 ⇌ NH3")
+        .assert()
+        .success()
+        .stdout("N2 + 3H2 <-> 2NH3\n");
+}
+
+#[test]
+fn parse_failure() {
+    cmbl()
+        .arg("Fe + O2 => Fe2O3")
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::starts_with("error: "));
+}
+
+#[test]
+fn balance_failure() {
+    cmbl()
+        .arg("Fe + S8 -> Fe2O3")
+        .assert()
+        .code(3)
+        .stderr("error: Equation is not valid\n");
+}
+
+#[test]
+fn json_output() {
+    cmbl()
+        .args(["--json", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""equation":"2H2 + O2 -> 2H2O""#));
+
+    // errors are part of the json instead of going to stderr
+    cmbl()
+        .args(["--format", "json", "Fe + S8 -> Fe2O3"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            r#""error":"Equation is not valid""#,
+        ))
+        .stderr("");
+}
+
+#[test]
+fn markdown_output() {
+    cmbl()
+        .args(["--format", "markdown", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout("| Input | Balanced | Status |\n| --- | --- | --- |\n| H2 + O2 -> H2O | 2H2 + O2 -> 2H2O | ok |\n");
+}
+
+#[test]
+fn unknown_format() {
+    cmbl()
+        .args(["--format", "yaml", "H2 + O2 -> H2O"])
+        .assert()
+        .failure()
+        .stdout("");
+}
+
+#[test]
+fn check() {
+    cmbl()
+        .args(["--check", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    cmbl()
+        .args(["--check", "Fe + S8 -> Fe2O3"])
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr("error: Fe + S8 -> Fe2O3: Equation is not valid\n");
+}
+
+#[test]
+fn ions() {
+    cmbl()
+        .arg("Fe^2+ + MnO4^- + H^+ -> Fe^3+ + Mn^2+ + H2O")
+        .assert()
+        .success()
+        .stdout("5Fe^2+ + MnO4^- + 8H^+ -> 5Fe^3+ + Mn^2+ + 4H2O\n");
+
+    // the net charge of each side says why it can't balance
+    cmbl()
+        .args(["--check", "Na+ -> Na"])
+        .assert()
+        .code(3)
+        .stderr(
+            "error: Na+ -> Na: Equation could not be balanced (net charge + on the left, 0 on the right)\n",
+        );
+}
+
+#[test]
+fn electrons() {
+    cmbl()
+        .arg("Fe^3+ + e- -> Fe^2+")
+        .assert()
+        .success()
+        .stdout("Fe^3+ + e- -> Fe^2+\n");
+
+    cmbl()
+        .arg("Na -> Na + e-")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("write charges like Fe^3+"));
+}
+
+#[test]
+fn oxidation() {
+    cmbl()
+        .args(["--oxidation", "Cu + Ag^+ -> Cu^2+ + Ag"])
+        .assert()
+        .success()
+        .stdout(
+            "Cu + 2Ag^+ -> Cu^2+ + 2Ag\nCu: Cu 0\nAg^+: Ag +1\nCu^2+: Cu +2\nAg: Ag 0\n\
+             oxidized: Cu 0 to +2, Cu to Cu^2+\nreduced: Ag +1 to 0, Ag^+ to Ag\n\
+             oxidizing agent: Ag^+\nreducing agent: Cu\n",
+        );
+}
+
+#[test]
+fn reverse() {
+    cmbl()
+        .args(["--reverse", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout("2H2O -> 2H2 + O2\n");
+    cmbl().args(["--reverse", "H2 + O2"]).assert().code(2);
+}
+
+#[test]
+fn unknown_elements() {
+    cmbl()
+        .arg("Xz2O + H2 -> Xz + H2O")
+        .assert()
+        .code(2)
+        .stderr("error: Unknown element Xz in compound 1\n");
+    cmbl()
+        .args(["--allow-unknown-elements", "M + HCl -> MCl2 + H2"])
+        .assert()
+        .success()
+        .stdout("M + 2HCl -> MCl2 + H2\n");
+}
+
+#[test]
+fn complete() {
+    cmbl()
+        .args(["--complete", "combustion", "C2H6 + O2 ->"])
+        .assert()
+        .success()
+        .stdout("2C2H6 + 7O2 -> 4CO2 + 6H2O\n");
+    // anything else is left for the usual error
+    cmbl()
+        .args(["--complete", "combustion", "Fe + O2 ->"])
+        .assert()
+        .code(2);
+    cmbl()
+        .args(["--complete", "fire", "CH4 + O2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected combustion"));
+}
+
+#[test]
+fn verify() {
+    cmbl()
+        .args(["--verify", "2H2 + O2 -> 2H2O"])
+        .assert()
+        .success()
+        .stdout("balanced and in lowest terms\n");
+    cmbl()
+        .args(["--verify", "4H2 + 2O2 -> 4H2O"])
+        .assert()
+        .code(4)
+        .stdout("balanced, but not in lowest terms, simplest form is 2H2 + O2 -> 2H2O\n");
+    cmbl()
+        .args(["--verify", "2H2 + 2O2 -> 2H2O"])
+        .assert()
+        .code(5)
+        .stdout("unbalanced, O is 4 on the left but 2 on the right\n");
+    cmbl()
+        .args(["--verify", "2H2 + O2"])
+        .assert()
+        .code(2)
+        .stderr("error: couldn't read \"2H2 + O2\" as an equation\n");
+}
+
+#[test]
+fn unique() {
+    cmbl()
+        .args(["--input", "-", "--unique"])
+        .write_stdin("H2 + O2 -> H2O\nO2 + H2 = H2O\nFe + O2 -> Fe2O3\n2H2 + O2 -> 2H2O\n")
+        .assert()
+        .success()
+        .stdout("2H2 + O2 -> 2H2O\n4Fe + 3O2 -> 2Fe2O3\n");
+}
+
+#[test]
+fn stdin_batch() {
+    cmbl()
+        .args(["--input", "-"])
+        .write_stdin("H2 + O2 -> H2O\n\nFe + O2 -> Fe2O3\n")
+        .assert()
+        .success()
+        .stdout("2H2 + O2 -> 2H2O\n4Fe + 3O2 -> 2Fe2O3\n");
+}
+
+#[test]
+fn check_stdin_batch() {
+    cmbl()
+        .args(["--check", "--input", "-", "--jobs", "1"])
+        .write_stdin("H2 + O2 -> H2O\nFe + S8 -> Fe2O3\n")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("error: Fe + S8 -> Fe2O3: Equation is not valid\n1 of 2 equations are invalid\n");
+}
+
+#[test]
+fn no_jobs() {
+    cmbl()
+        .args(["--input", "-", "--jobs", "0"])
+        .write_stdin("H2 + O2 -> H2O\n")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("there has to be at least one job"));
+}
+
+#[test]
+fn bad_value_after_equation() {
+    for args in [
+        ["H2 + O2 -> H2O", "--format", "weird"],
+        ["--format", "weird", "H2 + O2 -> H2O"],
+    ] {
+        cmbl()
+            .args(args)
+            .assert()
+            .failure()
+            .stdout("")
+            .stderr(predicate::str::contains("unknown format \"weird\""));
+    }
+    cmbl()
+        .args(["H2 + O2 -> H2O", "--jobs", "0"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("there has to be at least one job"));
+}
+
+#[test]
+fn redox_jobs() {
+    cmbl()
+        .args(["--input", "-", "--redox", "acidic", "--jobs", "2"])
+        .write_stdin("Cu + Ag^+ -> Cu^2+ + Ag\n")
+        .assert()
+        .success()
+        .stdout("Cu + 2Ag^+ -> Cu^2+ + 2Ag\n");
+}
+
+#[test]
+fn serve() {
+    cmbl()
+        .arg("--serve")
+        .write_stdin("H2 + O2 -> H2O\nFe + S8 -> Fe2O3\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""equation":"2H2 + O2 -> 2H2O""#))
+        .stdout(predicate::str::ends_with(
+            "{\"input\":\"Fe + S8 -> Fe2O3\",\"error\":\"Equation is not valid\"}\n",
+        ));
+}
+
+#[test]
+fn missing_input_file() {
+    cmbl()
+        .args(["--input", "does/not/exist.txt"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn no_terminal() {
+    cmbl()
+        .timeout(std::time::Duration::from_secs(10))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("the tui needs a terminal"));
+}
+
+#[test]
+fn tui_needs_terminal() {
+    // with --tui the equation is opened instead of printed
+    cmbl()
+        .args(["--tui", "H2 + O2 -> H2O"])
+        .timeout(std::time::Duration::from_secs(10))
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("the tui needs a terminal"));
+}
+
+#[test]
+fn log_file() {
+    let path = std::env::temp_dir().join(format!("cmbl-log-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    cmbl()
+        .arg("--log")
+        .arg(&path)
+        .arg("Fe + O2 -> Fe2O3")
+        .assert()
+        .success()
+        .stdout("4Fe + 3O2 -> 2Fe2O3\n")
+        .stderr("");
+    cmbl()
+        .arg("--log")
+        .arg(&path)
+        .arg("Fe + S8 -> Fe2O3")
+        .assert()
+        .code(3);
+
+    // both runs are appended
+    let log = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(
+        log.contains(r#"balanced input="Fe + O2 -> Fe2O3" output="4Fe + 3O2 -> 2Fe2O3" factor=1"#),
+        "{log}"
+    );
+    assert!(
+        log.contains(r#"parse failed input="Fe + S8 -> Fe2O3""#),
+        "{log}"
+    );
+}
+
+#[test]
+fn given() {
+    cmbl()
+        .args(["--given", "H2=4g", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout("2H2 + O2 -> 2H2O\nH2    1.984 mol  4 g\nO2   0.9921 mol  31.74 g\nH2O   1.984 mol  35.74 g\n");
+    cmbl()
+        .args(["--given", "CO2=1mol", "H2 + O2 -> H2O"])
+        .assert()
+        .failure()
+        .stderr("error: CO2 isn't in the equation\n");
+}
+
+#[test]
+fn limiting_reagent() {
+    cmbl()
+        .args(["--amounts", "H2=10g,O2=10g", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout(
+            "2H2 + O2 -> 2H2O\nlimiting reagent: O2\nH2    0.625 mol  1.26 g\nO2   0.3125 mol  10 g\n\
+             H2O   0.625 mol  11.26 g\nH2 left over: 4.335 mol, 8.74 g\n",
+        );
+    cmbl()
+        .args(["--amounts", "H2=10g,H2O=1mol", "H2 + O2 -> H2O"])
+        .assert()
+        .failure()
+        .stderr("error: H2O is a product, only reactants can be given with other amounts\n");
+}
+
+#[test]
+fn percent_yield() {
+    cmbl()
+        .args([
+            "--amounts",
+            "H2=10g,O2=10g",
+            "--actual",
+            "H2O=9g",
+            "H2 + O2 -> H2O",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "theoretical yield: 11.26 g H2O\npercent yield: 79.93%\n",
+        ));
+    cmbl()
+        .args(["--actual", "H2O=9g", "H2 + O2 -> H2O"])
+        .assert()
+        .failure()
+        .stderr("error: --actual needs an amount of a reactant from --given or --amounts\n");
+    cmbl()
+        .args([
+            "--given",
+            "O2=1mol",
+            "--actual",
+            "H2O=-2mol",
+            "H2 + O2 -> H2O",
+        ])
+        .assert()
+        .failure()
+        .stderr("error: -2 isn't an amount, it has to be above 0\n");
+}
+
+#[test]
+fn ratio() {
+    cmbl()
+        .args(["--ratio", "C3H8:O2", "C3H8 + O2 -> CO2 + H2O"])
+        .assert()
+        .success()
+        .stdout("C3H8 + 5O2 -> 3CO2 + 4H2O\nC3H8:O2 1:5\n");
+}
+
+#[test]
+fn percent_composition() {
+    cmbl()
+        .args(["--percent-composition", "H2O"])
+        .assert()
+        .success()
+        .stdout("H2O 18.02 g/mol\nH  11.19%\nO  88.81%\n");
+    cmbl()
+        .args(["--percent-composition", "Xy2"])
+        .assert()
+        .code(2)
+        .stderr("error: couldn't read \"Xy2\" as a formula\n");
+}
+
+#[test]
+fn convert() {
+    cmbl()
+        .args(["--convert", "18 g H2O"])
+        .assert()
+        .success()
+        .stdout("18 g H2O = 0.9992 mol\n");
+    cmbl()
+        .args(["--convert", "0.5 mol O2"])
+        .assert()
+        .success()
+        .stdout("0.5 mol O2 = 16 g\n");
+    cmbl()
+        .args(["--convert", "18 g Xy"])
+        .assert()
+        .code(2)
+        .stderr("error: couldn't read \"Xy\" as a formula\n");
+}
+
+#[test]
+fn kc() {
+    cmbl()
+        .args(["--kc", "CaCO3(s) <-> CaO(s) + CO2(g)"])
+        .assert()
+        .success()
+        .stdout("CaCO3(s) <-> CaO(s) + CO2(g)\nKc = [CO2]\nK_c = [\\mathrm{CO_{2}}]\n");
+    // nothing for a reaction that only goes one way
+    cmbl()
+        .args(["--kc", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout("2H2 + O2 -> 2H2O\n");
+}
+
+#[test]
+fn fractional() {
+    cmbl()
+        .args(["--fractional", "C2H6 + O2 -> CO2 + H2O"])
+        .assert()
+        .success()
+        .stdout("C2H6 + 7/2 O2 -> 2CO2 + 3H2O\n");
+    cmbl()
+        .args(["--fractional", "--per", "H2O", "H2 + O2 -> H2O"])
+        .assert()
+        .success()
+        .stdout("H2 + 1/2 O2 -> H2O\n");
+    cmbl()
+        .args(["--fractional", "--per", "CO2", "H2 + O2 -> H2O"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn net_ionic() {
+    cmbl()
+        .args(["--net-ionic", "Pb(NO3)2(aq) + KI(aq) -> PbI2(s) + KNO3(aq)"])
+        .assert()
+        .success()
+        .stdout("Pb(NO3)2(aq) + 2KI(aq) -> PbI2(s) + 2KNO3(aq)\nPb^2+(aq) + 2I^-(aq) -> PbI2(s)\n");
+    cmbl()
+        .args(["--net-ionic", "NaCl + KNO3 -> NaNO3 + KCl"])
+        .assert()
+        .success()
+        .stdout("NaCl + KNO3 -> NaNO3 + KCl\nno net reaction\n");
+}
+
+#[test]
+fn export() {
+    let path = std::env::temp_dir().join(format!("cmbl-report-{}.md", std::process::id()));
+    cmbl()
+        .arg("--export")
+        .arg(&path)
+        .args(["H2 + O2 -> H2O", "--given", "H2=4g"])
+        .assert()
+        .success()
+        .stdout("");
+    let report = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(report.starts_with("# Chemical equations\n\n## 1. H2 + O2 -> H2O\n"));
+    assert!(report.contains("Balanced: `2H2 + O2 -> 2H2O`"));
+    assert!(report.contains("| Compound | Moles | Grams |"));
+
+    cmbl()
+        .args(["--export", "does/not/exist/report.md", "H2 + O2 -> H2O"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(
+            "error: couldn't write the report to does/not/exist/report.md: ",
+        ));
+}